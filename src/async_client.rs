@@ -0,0 +1,959 @@
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use anchor_lang::Discriminator;
+use futures::stream::{BoxStream, StreamExt};
+use solana_client::client_error::ClientError as RpcClientError;
+use solana_sdk::transaction::Transaction;
+use tokio::time::sleep;
+
+use crate::client::{
+    decode_program_accounts, parse_asks_inner, parse_bids_inner, parse_open_orders,
+    parse_user_account_inner, program_accounts_config, BookLevel, Market, OptifiAccount,
+};
+use crate::compute_budget::{simulation_instructions, ComputeBudgetPolicy, Operation, UnitLimit};
+use crate::config::OptifiConfig;
+use crate::decoder::{OpenOrder, OpenOrdersView};
+use crate::error::OptifiClientError;
+use crate::instructions::InstructionBuilder;
+use crate::market_loader::{
+    complete_markets, pending_markets, MarketLoadFailure, PendingMarket, MAX_MULTIPLE_ACCOUNTS,
+};
+use crate::market_setup::{checks_market_setup, market_setup_accounts, order_setup, MarketSetup};
+use crate::network::Network;
+use crate::nonblocking::pubsub_client::PubsubClient;
+use crate::nonblocking::rpc_client::RpcClient;
+use crate::orders::{new_open_orders, pack_orders, OrderBatch, OrderRequest, PlacedOrder};
+use crate::prelude::*;
+use crate::send::{
+    confirmed_outcome, is_transient, poll_confirmation, send_config, send_failed, transaction_logs,
+    transaction_logs_config, transaction_signers, Confirmation, TransactionOutcome,
+};
+use crate::simulation::{simulation_config, simulation_result, SimulationResult};
+use crate::types::{Price, Quantity, UsdcAmount};
+use crate::validation::{check_orders, unix_timestamp};
+
+/// Tokio counterpart of `OptifiClient`, built on the nonblocking RPC and pubsub clients.
+///
+/// Instructions, order validation, market setup and the send and confirm decisions go through
+/// the same builders and helpers as `OptifiClient`; only the RPC calls differ.
+pub struct AsyncOptifiClient {
+    pub cluster: Cluster,
+    pub rpc: RpcClient,
+    pub pubsub: PubsubClient,
+    /// Pays transaction fees and rent for newly created accounts.
+    pub payer: Arc<dyn Signer + Send + Sync>,
+    /// Wallet that owns (or is delegated) `user_account` and signs its instructions.
+    pub owner: Arc<dyn Signer + Send + Sync>,
+    pub optifi_exchange: Pubkey,
+    pub user_account: Pubkey,
+    pub config: OptifiConfig,
+    account: RwLock<Arc<OptifiAccount>>,
+    /// Optifi markets the user is known to be set up on, see `ensure_market_ready`.
+    ready_markets: RwLock<HashSet<Pubkey>>,
+}
+
+impl AsyncOptifiClient {
+    pub async fn new(
+        cluster: Cluster,
+        network: Network,
+        wallet_path: Option<String>,
+        delegator: Option<Pubkey>,
+    ) -> std::result::Result<Self, OptifiClientError> {
+        // Wallet and cluster params.
+        let wallet_path = wallet_path.unwrap_or("~/.config/solana/id.json".to_owned());
+
+        let payer = read_keypair_file(shellexpand::tilde(&wallet_path).as_ref())
+            .map_err(|err| OptifiClientError::Keypair(format!("{}: {}", wallet_path, err)))?;
+
+        AsyncOptifiClient::new_with_signer(cluster, network, Arc::new(payer), delegator).await
+    }
+
+    /// Uses `signer` both as fee payer and as owner of the user account.
+    pub async fn new_with_signer(
+        cluster: Cluster,
+        network: Network,
+        signer: Arc<dyn Signer + Send + Sync>,
+        delegator: Option<Pubkey>,
+    ) -> std::result::Result<Self, OptifiClientError> {
+        AsyncOptifiClient::new_with_signers(cluster, network, signer.clone(), signer, delegator)
+            .await
+    }
+
+    /// Lets a separate `payer` fund transactions on behalf of `owner`.
+    pub async fn new_with_signers(
+        cluster: Cluster,
+        network: Network,
+        payer: Arc<dyn Signer + Send + Sync>,
+        owner: Arc<dyn Signer + Send + Sync>,
+        delegator: Option<Pubkey>,
+    ) -> std::result::Result<Self, OptifiClientError> {
+        AsyncOptifiClient::with_config(
+            cluster,
+            payer,
+            owner,
+            delegator,
+            OptifiConfig::for_network(&network)?,
+        )
+        .await
+    }
+
+    /// Builds a client for the deployment described by `config`, see `OptifiClientBuilder`.
+    pub async fn with_config(
+        cluster: Cluster,
+        payer: Arc<dyn Signer + Send + Sync>,
+        owner: Arc<dyn Signer + Send + Sync>,
+        delegator: Option<Pubkey>,
+        config: OptifiConfig,
+    ) -> std::result::Result<Self, OptifiClientError> {
+        let rpc = RpcClient::new_with_commitment(cluster.url().to_string(), config.read_commitment);
+
+        let pubsub = PubsubClient::new(cluster.ws_url());
+
+        let user_account = if let Some(delegator) = delegator {
+            delegator
+        } else {
+            get_user_account_pda(
+                &config.optifi_exchange,
+                &owner.pubkey(),
+                &config.optifi_program_id,
+            )
+            .0
+        };
+
+        Ok(Self {
+            cluster,
+            rpc,
+            pubsub,
+            payer,
+            owner,
+            optifi_exchange: config.optifi_exchange,
+            user_account,
+            config,
+            account: RwLock::new(Arc::new(OptifiAccount::default())),
+            ready_markets: RwLock::new(HashSet::new()),
+        })
+    }
+
+    /// Builds a client and loads the exchange, user account and markets.
+    ///
+    /// Markets that fail to load are left out, `load_markets` reports why.
+    pub async fn initialize(
+        cluster: Cluster,
+        network: Network,
+        wallet_path: Option<String>,
+        delegator: Option<Pubkey>,
+    ) -> std::result::Result<Self, OptifiClientError> {
+        let optifi_client =
+            AsyncOptifiClient::new(cluster, network, wallet_path, delegator).await?;
+
+        optifi_client.load_optifi_exchange().await?;
+        optifi_client.load_user_account().await?;
+        optifi_client.load_markets().await?;
+
+        Ok(optifi_client)
+    }
+
+    /// Instruction builder over `account`, usually a snapshot from `account()`.
+    pub fn instruction_builder<'a>(&'a self, account: &'a OptifiAccount) -> InstructionBuilder<'a> {
+        InstructionBuilder {
+            config: &self.config,
+            account,
+            payer: self.payer.pubkey(),
+            owner: self.owner.pubkey(),
+            user_account: self.user_account,
+        }
+    }
+
+    /// Snapshot of the cached exchange, user account and markets.
+    pub fn account(&self) -> Arc<OptifiAccount> {
+        self.account
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn update_account<F: FnOnce(&mut OptifiAccount)>(&self, update: F) {
+        let mut account = self.account.write().unwrap_or_else(PoisonError::into_inner);
+        update(Arc::make_mut(&mut account));
+    }
+
+    /// Awaits `request` until it succeeds or fails with a non transient error, backing off
+    /// between attempts.
+    async fn retry_transient<T, F, Fut>(
+        &self,
+        mut request: F,
+    ) -> std::result::Result<T, RpcClientError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = std::result::Result<T, RpcClientError>>,
+    {
+        let policy = self.config.retry_policy;
+        let mut retry = 0;
+
+        loop {
+            match request().await {
+                Err(err) if retry < policy.max_retries && is_transient(&err) => {
+                    sleep(policy.backoff(retry)).await;
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// See `OptifiClient::with_compute_budget`.
+    pub async fn with_compute_budget(
+        &self,
+        operation: Operation,
+        instructions: Vec<Instruction>,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        let policy = *self.config.compute_budgets.get(operation);
+
+        let units = match policy.unit_limit {
+            UnitLimit::Default => None,
+            UnitLimit::Fixed(units) => Some(units),
+            UnitLimit::Simulated { margin, max } => {
+                let simulation = self
+                    .simulate_instructions(simulation_instructions(max, &instructions))
+                    .await?;
+
+                Some(ComputeBudgetPolicy::simulated_units(
+                    margin,
+                    max,
+                    simulation.units_consumed,
+                ))
+            }
+        };
+
+        Ok(policy.prepend(units, instructions))
+    }
+
+    /// See `OptifiClient::simulate_instructions`.
+    pub async fn simulate_instructions(
+        &self,
+        instructions: Vec<Instruction>,
+    ) -> std::result::Result<SimulationResult, OptifiClientError> {
+        let mut tx = Transaction::new_with_payer(&instructions, Some(&self.payer.pubkey()));
+
+        tx.message.recent_blockhash = self.rpc.get_latest_blockhash().await?;
+
+        let response = self
+            .rpc
+            .simulate_transaction_with_config(&tx, simulation_config(&self.config))
+            .await?;
+
+        Ok(simulation_result(&self.config, &instructions, response))
+    }
+
+    /// See `OptifiClient::send_and_confirm`.
+    pub async fn send_instructions(
+        &self,
+        instructions: Vec<Instruction>,
+    ) -> std::result::Result<TransactionOutcome, OptifiClientError> {
+        let mut attempts = 0;
+
+        loop {
+            attempts += 1;
+
+            let (blockhash, last_valid_block_height) = self
+                .retry_transient(|| {
+                    self.rpc
+                        .get_latest_blockhash_with_commitment(self.config.send_commitment)
+                })
+                .await?;
+
+            let mut tx = Transaction::new_with_payer(&instructions, Some(&self.payer.pubkey()));
+            tx.try_sign(
+                &transaction_signers(self.payer.as_ref(), self.owner.as_ref(), &instructions, &[]),
+                blockhash,
+            )?;
+
+            let signature = tx.signatures[0];
+
+            let sent = self
+                .retry_transient(|| {
+                    self.rpc
+                        .send_transaction_with_config(&tx, send_config(&self.config))
+                })
+                .await;
+
+            if let Err(err) = sent {
+                match send_failed(&self.config, &instructions, signature, attempts, err)? {
+                    Some(outcome) => return Ok(outcome),
+                    None => continue,
+                }
+            }
+
+            let confirmation = self
+                .confirm(&signature, last_valid_block_height)
+                .await
+                .map_err(|err| OptifiClientError::Unconfirmed {
+                    signature,
+                    source: Box::new(err),
+                })?;
+
+            let logs = if confirmation.failed() {
+                self.transaction_logs(&signature).await
+            } else {
+                vec![]
+            };
+
+            if let Some(outcome) = confirmed_outcome(
+                &self.config,
+                &instructions,
+                signature,
+                attempts,
+                confirmation,
+                &logs,
+            ) {
+                return Ok(outcome);
+            }
+        }
+    }
+
+    /// Logs of a landed transaction, empty if they can not be fetched (yet).
+    async fn transaction_logs(&self, signature: &Signature) -> Vec<String> {
+        self.retry_transient(|| {
+            self.rpc
+                .get_transaction_with_config(signature, transaction_logs_config(&self.config))
+        })
+        .await
+        .map(transaction_logs)
+        .unwrap_or_default()
+    }
+
+    /// Polls the status of `signature` until it reaches the send commitment or the block height
+    /// passed `last_valid_block_height` without it.
+    async fn confirm(
+        &self,
+        signature: &Signature,
+        last_valid_block_height: u64,
+    ) -> std::result::Result<Confirmation, OptifiClientError> {
+        let commitment = self.config.send_commitment;
+        let signatures = [*signature];
+
+        loop {
+            // Read the height before the status, so a missing status past it means expired.
+            let block_height = self
+                .retry_transient(|| self.rpc.get_block_height_with_commitment(commitment))
+                .await?;
+
+            let status = self
+                .retry_transient(|| self.rpc.get_signature_statuses(&signatures))
+                .await?
+                .value
+                .pop()
+                .flatten();
+
+            match poll_confirmation(status, commitment, block_height, last_valid_block_height) {
+                Some(confirmation) => return Ok(confirmation),
+                None => sleep(self.config.retry_policy.poll_interval).await,
+            }
+        }
+    }
+
+    async fn fetch_account(
+        &self,
+        pubkey: &Pubkey,
+    ) -> std::result::Result<solana_sdk::account::Account, OptifiClientError> {
+        self.rpc
+            .get_account_with_commitment(pubkey, self.config.read_commitment)
+            .await?
+            .value
+            .ok_or(OptifiClientError::AccountNotFound(*pubkey))
+    }
+
+    async fn fetch<T: AccountDeserialize>(
+        &self,
+        pubkey: Pubkey,
+    ) -> std::result::Result<T, OptifiClientError> {
+        let account = self.fetch_account(&pubkey).await?;
+
+        Ok(T::try_deserialize(&mut (&account.data as &[u8]))?)
+    }
+
+    pub async fn load_optifi_exchange(&self) -> std::result::Result<(), OptifiClientError> {
+        let optifi_exchange = self.fetch(self.optifi_exchange).await?;
+        self.update_account(|account| account.optifi_exchange = Some(optifi_exchange));
+        Ok(())
+    }
+
+    pub async fn load_user_account(&self) -> std::result::Result<(), OptifiClientError> {
+        let user_account = self.fetch(self.user_account).await?;
+        self.update_account(|account| account.user_account = Some(user_account));
+        Ok(())
+    }
+
+    /// See `OptifiClient::fetch_multiple_accounts`.
+    pub async fn fetch_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> std::result::Result<Vec<Option<solana_sdk::account::Account>>, OptifiClientError> {
+        let mut accounts = Vec::with_capacity(pubkeys.len());
+
+        for chunk in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            accounts.extend(
+                self.rpc
+                    .get_multiple_accounts_with_commitment(chunk, self.config.read_commitment)
+                    .await?
+                    .value,
+            );
+        }
+
+        Ok(accounts)
+    }
+
+    pub async fn custom_accounts<T: AccountDeserialize + Discriminator>(
+        &self,
+        filters: Vec<RpcFilterType>,
+    ) -> std::result::Result<Vec<(Pubkey, T)>, OptifiClientError> {
+        let accounts = self
+            .rpc
+            .get_program_accounts_with_config(
+                &self.config.optifi_program_id,
+                program_accounts_config::<T>(self.config.read_commitment, filters),
+            )
+            .await?;
+
+        Ok(decode_program_accounts(accounts))
+    }
+
+    /// See `OptifiClient::load_markets`.
+    pub async fn load_markets(
+        &self,
+    ) -> std::result::Result<Vec<MarketLoadFailure>, OptifiClientError> {
+        let account = self.account();
+
+        let optifi_exchange = account.get_optifi_exchange()?;
+
+        let optifi_markets = self.custom_accounts::<OptifiMarket>(vec![]).await?;
+
+        let (pending, mut failures) =
+            pending_markets(optifi_exchange, &optifi_exchange.markets, &optifi_markets);
+
+        let serum_markets: Vec<Pubkey> = pending.iter().map(PendingMarket::serum_market).collect();
+
+        let serum_accounts = self.fetch_multiple_accounts(&serum_markets).await?;
+
+        let markets = complete_markets(pending, serum_accounts, &mut failures);
+
+        self.update_account(|account| account.markets = markets);
+
+        Ok(failures)
+    }
+
+    pub fn get_markets(&self, asset: Option<Asset>) -> Vec<Market> {
+        self.account()
+            .markets
+            .iter()
+            .filter(|market| asset.map_or(true, |asset| market.instrument_common.asset == asset))
+            .cloned()
+            .collect()
+    }
+
+    pub fn get_user_account(&self) -> std::result::Result<UserAccount, OptifiClientError> {
+        self.account().get_user_account().cloned()
+    }
+
+    pub fn set_delegation_instructions(
+        &self,
+        delegatee: Option<Pubkey>,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        Ok(self
+            .instruction_builder(&self.account())
+            .set_delegation(delegatee))
+    }
+
+    pub async fn set_delegation(
+        &self,
+        delegatee: Option<Pubkey>,
+    ) -> std::result::Result<TransactionOutcome, OptifiClientError> {
+        log::debug!("set delegation to: {:?}", delegatee);
+
+        let instructions = self
+            .with_compute_budget(
+                Operation::Other,
+                self.set_delegation_instructions(delegatee)?,
+            )
+            .await?;
+
+        self.send_instructions(instructions).await
+    }
+
+    pub async fn deposit_instructions(
+        &self,
+        amount: UsdcAmount,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        let user_account: UserAccount = self.fetch(self.user_account).await?;
+
+        Ok(self
+            .instruction_builder(&self.account())
+            .deposit(amount.to_native()?, user_account.user_margin_account_usdc))
+    }
+
+    pub async fn deposit(
+        &self,
+        amount: UsdcAmount,
+    ) -> std::result::Result<TransactionOutcome, OptifiClientError> {
+        log::debug!("deposit amount: {}", amount);
+
+        let instructions = self
+            .with_compute_budget(Operation::Other, self.deposit_instructions(amount).await?)
+            .await?;
+
+        self.send_instructions(instructions).await
+    }
+
+    pub async fn withdraw_instructions(
+        &self,
+        amount: UsdcAmount,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        let user_account: UserAccount = self.fetch(self.user_account).await?;
+
+        Ok(self
+            .instruction_builder(&self.account())
+            .withdraw(amount.to_native()?, user_account.user_margin_account_usdc))
+    }
+
+    pub async fn withdraw(
+        &self,
+        amount: UsdcAmount,
+    ) -> std::result::Result<TransactionOutcome, OptifiClientError> {
+        log::debug!("withdraw amount: {}", amount);
+
+        let instructions = self
+            .with_compute_budget(Operation::Other, self.withdraw_instructions(amount).await?)
+            .await?;
+
+        self.send_instructions(instructions).await
+    }
+
+    pub fn initialize_user_on_market_instructions(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        Ok(self
+            .instruction_builder(&self.account())
+            .initialize_user_on_market(market))
+    }
+
+    pub async fn initialize_user_on_market(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<TransactionOutcome, OptifiClientError> {
+        let instructions = self
+            .with_compute_budget(
+                Operation::Other,
+                self.initialize_user_on_market_instructions(market)?,
+            )
+            .await?;
+
+        let outcome = self.send_instructions(instructions).await?;
+
+        if outcome.is_confirmed() {
+            self.mark_market_ready(market);
+        }
+
+        Ok(outcome)
+    }
+
+    /// See `OptifiClient::load_market_setup`.
+    pub async fn load_market_setup(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<MarketSetup, OptifiClientError> {
+        let accounts = self
+            .rpc
+            .get_multiple_accounts_with_commitment(
+                &market_setup_accounts(&self.config, &self.user_account, market),
+                self.config.read_commitment,
+            )
+            .await?;
+
+        let setup = MarketSetup::from_accounts(&accounts.value);
+
+        if setup.is_ready() {
+            self.mark_market_ready(market);
+        }
+
+        Ok(setup)
+    }
+
+    /// Whether `market` is known to be set up, without touching the network.
+    pub fn is_market_ready(&self, market: &Market) -> bool {
+        self.ready_markets
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains(&market.optifi_market_key_data.optifi_market_pubkey)
+    }
+
+    fn mark_market_ready(&self, market: &Market) {
+        self.ready_markets
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(market.optifi_market_key_data.optifi_market_pubkey);
+    }
+
+    /// See `OptifiClient::ensure_market_ready_instructions`.
+    pub async fn ensure_market_ready_instructions(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        if self.is_market_ready(market) {
+            return Ok(vec![]);
+        }
+
+        let setup = self.load_market_setup(market).await?;
+
+        if setup.is_ready() {
+            return Ok(vec![]);
+        }
+
+        Ok(self
+            .instruction_builder(&self.account())
+            .complete_market_setup(market, setup))
+    }
+
+    /// Creates the user's accounts missing on `market`, returning `None` if there were none.
+    pub async fn ensure_market_ready(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<Option<TransactionOutcome>, OptifiClientError> {
+        let instructions = self.ensure_market_ready_instructions(market).await?;
+
+        if instructions.is_empty() {
+            return Ok(None);
+        }
+
+        let outcome = self
+            .send_instructions(
+                self.with_compute_budget(Operation::Other, instructions)
+                    .await?,
+            )
+            .await?;
+
+        if outcome.is_confirmed() {
+            self.mark_market_ready(market);
+        }
+
+        Ok(Some(outcome))
+    }
+
+    /// Setup instructions to prepend to the first order on `market`, see `order_setup`.
+    async fn order_setup_instructions(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        if !checks_market_setup(&self.config) || self.is_market_ready(market) {
+            return Ok(vec![]);
+        }
+
+        let setup = self.load_market_setup(market).await?;
+
+        order_setup(&self.instruction_builder(&self.account()), market, setup)
+    }
+
+    /// See `OptifiClient::validate_orders`.
+    pub fn validate_orders(
+        &self,
+        market: &Market,
+        orders: &[OrderRequest],
+    ) -> std::result::Result<Vec<OrderRequest>, OptifiClientError> {
+        check_orders(
+            self.config.order_validation,
+            market,
+            orders,
+            unix_timestamp(),
+        )
+    }
+
+    /// See `OptifiClient::place_order_instructions`.
+    pub async fn place_order_instructions(
+        &self,
+        market: &Market,
+        side: OrderSide,
+        price: Price,
+        size: Quantity,
+        order_type: OrderType,
+    ) -> std::result::Result<Vec<OrderBatch>, OptifiClientError> {
+        let orders = self.validate_orders(
+            market,
+            &[OrderRequest {
+                side,
+                price,
+                size,
+                order_type,
+            }],
+        )?;
+
+        let setup = self.order_setup_instructions(market).await?;
+
+        let account = self.account();
+        let builder = self.instruction_builder(&account);
+
+        pack_orders(
+            self.config.max_orders_per_transaction,
+            &self.payer.pubkey(),
+            orders.len(),
+            setup,
+            |range| builder.place_orders(market, &orders[range]),
+        )
+    }
+
+    /// See `OptifiClient::place_order`.
+    pub async fn place_order(
+        &self,
+        market: &Market,
+        side: OrderSide,
+        price: Price,
+        size: Quantity,
+        order_type: OrderType,
+    ) -> std::result::Result<PlacedOrder, OptifiClientError> {
+        let batches = self
+            .place_order_instructions(market, side, price, size, order_type)
+            .await?;
+
+        let batches = self.send_setup_batches(market, batches).await?;
+
+        let instructions = self
+            .with_compute_budget(
+                Operation::PlaceOrder,
+                batches
+                    .into_iter()
+                    .flat_map(|batch| batch.instructions)
+                    .collect(),
+            )
+            .await?;
+
+        let placed = self
+            .send_tracking_client_order_id(market, instructions)
+            .await?;
+
+        if placed.outcome.is_confirmed() {
+            self.mark_market_ready(market);
+        }
+
+        Ok(placed)
+    }
+
+    /// See `OptifiClient::send_setup_batches`.
+    async fn send_setup_batches(
+        &self,
+        market: &Market,
+        batches: Vec<OrderBatch>,
+    ) -> std::result::Result<Vec<OrderBatch>, OptifiClientError> {
+        let (setup, batches): (Vec<_>, Vec<_>) = batches
+            .into_iter()
+            .partition(|batch| batch.orders.is_empty());
+
+        for batch in setup {
+            let instructions = self
+                .with_compute_budget(Operation::Other, batch.instructions)
+                .await?;
+
+            self.send_instructions(instructions).await?.into_result()?;
+
+            self.mark_market_ready(market);
+        }
+
+        Ok(batches)
+    }
+
+    /// See `OptifiClient::send_tracking_client_order_id`.
+    async fn send_tracking_client_order_id(
+        &self,
+        market: &Market,
+        instructions: Vec<Instruction>,
+    ) -> std::result::Result<PlacedOrder, OptifiClientError> {
+        let before: Vec<u64> = self
+            .load_user_open_orders(market)
+            .await?
+            .iter()
+            .map(|order| order.client_order_id)
+            .collect();
+
+        let outcome = self.send_instructions(instructions).await?;
+
+        let client_order_id = if outcome.is_confirmed() {
+            match self.load_user_open_orders(market).await {
+                Ok(orders) => new_open_orders(orders, &before)
+                    .first()
+                    .map(|order| order.client_order_id),
+                Err(err) => {
+                    log::warn!(
+                        "could not read the open orders on market {}: {}",
+                        market.optifi_market_key_data.optifi_market_pubkey,
+                        err
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        Ok(PlacedOrder {
+            client_order_id,
+            outcome,
+        })
+    }
+
+    /// Orders resting in the user's open orders account of `market`, none if it does not exist.
+    pub async fn load_user_open_orders(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<Vec<OpenOrder>, OptifiClientError> {
+        let orders_account = match self.fetch_account(&self.open_orders(market)).await {
+            Ok(account) => account,
+            Err(OptifiClientError::AccountNotFound(_)) => return Ok(vec![]),
+            Err(err) => return Err(err),
+        };
+
+        Ok(OpenOrdersView::decode(&orders_account.data)?.orders())
+    }
+
+    fn open_orders(&self, market: &Market) -> Pubkey {
+        get_serum_open_orders_account(
+            &self.optifi_exchange,
+            &self.user_account,
+            &market.optifi_market.serum_market,
+            &self.config.optifi_program_id,
+        )
+        .0
+    }
+
+    pub fn settle_order_instructions(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        self.instruction_builder(&self.account())
+            .settle_order(market)
+    }
+
+    pub async fn settle_order(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<TransactionOutcome, OptifiClientError> {
+        let instructions = self
+            .with_compute_budget(
+                Operation::SettleOrder,
+                self.settle_order_instructions(market)?,
+            )
+            .await?;
+
+        self.send_instructions(instructions).await
+    }
+
+    /// Margin stress, cancel order and settlement, without a compute budget instruction.
+    pub fn cancel_order_instructions(
+        &self,
+        market: &Market,
+        side: OrderSide,
+        client_order_id: u64,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        self.instruction_builder(&self.account())
+            .cancel_order(market, side, client_order_id)
+    }
+
+    pub async fn cancel_order(
+        &self,
+        market: &Market,
+        side: OrderSide,
+        client_order_id: u64,
+    ) -> std::result::Result<TransactionOutcome, OptifiClientError> {
+        let instructions = self
+            .with_compute_budget(
+                Operation::CancelOrder,
+                self.cancel_order_instructions(market, side, client_order_id)?,
+            )
+            .await?;
+
+        self.send_instructions(instructions).await
+    }
+
+    /// Streams the updates of `pubkey` at the read commitment, parsed by `parse`.
+    ///
+    /// The stream ends when the connection closes; dropping it unsubscribes.
+    async fn subscribe<T, F>(
+        &self,
+        pubkey: &Pubkey,
+        parse: F,
+    ) -> std::result::Result<
+        BoxStream<'static, std::result::Result<T, OptifiClientError>>,
+        OptifiClientError,
+    >
+    where
+        T: Send + 'static,
+        F: Fn(Response<UiAccount>) -> std::result::Result<T, OptifiClientError> + Send + 'static,
+    {
+        let updates = self
+            .pubsub
+            .account_subscribe(
+                pubkey,
+                Some(RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    data_slice: None,
+                    commitment: Some(self.config.read_commitment),
+                }),
+            )
+            .await?;
+
+        Ok(updates.map(move |update| parse(update?)).boxed())
+    }
+
+    pub async fn subscribe_asks(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<
+        BoxStream<'static, std::result::Result<Vec<BookLevel>, OptifiClientError>>,
+        OptifiClientError,
+    > {
+        let asks = *market.market_pubkeys.asks;
+        let market = market.clone();
+
+        self.subscribe(&asks, move |ui_account| {
+            parse_asks_inner(&market, ui_account)
+        })
+        .await
+    }
+
+    pub async fn subscribe_bids(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<
+        BoxStream<'static, std::result::Result<Vec<BookLevel>, OptifiClientError>>,
+        OptifiClientError,
+    > {
+        let bids = *market.market_pubkeys.bids;
+        let market = market.clone();
+
+        self.subscribe(&bids, move |ui_account| {
+            parse_bids_inner(&market, ui_account)
+        })
+        .await
+    }
+
+    /// Streams the orders resting in the user's open orders account of `market`.
+    pub async fn subscribe_open_orders(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<
+        BoxStream<'static, std::result::Result<Vec<OpenOrder>, OptifiClientError>>,
+        OptifiClientError,
+    > {
+        self.subscribe(&self.open_orders(market), parse_open_orders)
+            .await
+    }
+
+    pub async fn subscribe_user_account(
+        &self,
+    ) -> std::result::Result<
+        BoxStream<'static, std::result::Result<UserAccount, OptifiClientError>>,
+        OptifiClientError,
+    > {
+        self.subscribe(&self.user_account, parse_user_account_inner)
+            .await
+    }
+}
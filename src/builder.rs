@@ -0,0 +1,302 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::async_client::AsyncOptifiClient;
+use crate::client::OptifiClient;
+use crate::compute_budget::{ComputeBudgetPolicy, ComputeBudgets, Operation, UnitLimit};
+use crate::config::OptifiConfig;
+use crate::error::OptifiClientError;
+use crate::network::{Deployment, Network};
+use crate::prelude::*;
+use crate::send::RetryPolicy;
+use crate::validation::OrderValidation;
+
+/// Configures an `OptifiClient` or `AsyncOptifiClient` at runtime.
+///
+/// Anything left unset falls back to the addresses of `network` (the one selected by the `devnet`
+/// feature by default), `processed` commitment and the default compute budget.
+pub struct OptifiClientBuilder {
+    cluster: Cluster,
+    network: Network,
+    ws_url: Option<String>,
+    wallet_path: Option<String>,
+    payer: Option<Arc<dyn Signer + Send + Sync>>,
+    owner: Option<Arc<dyn Signer + Send + Sync>>,
+    delegator: Option<Pubkey>,
+    read_commitment: Option<CommitmentConfig>,
+    send_commitment: Option<CommitmentConfig>,
+    optifi_exchange: Option<Pubkey>,
+    optifi_program_id: Option<Pubkey>,
+    optifi_usdc_program_id: Option<Pubkey>,
+    serum_dex_program_id: Option<Pubkey>,
+    usdc_token_mint: Option<Pubkey>,
+    compute_budgets: Option<ComputeBudgets>,
+    consume_events_limit: Option<u16>,
+    max_orders_per_transaction: Option<usize>,
+    order_validation: Option<OrderValidation>,
+    auto_initialize_markets: Option<bool>,
+    market_cache_dir: Option<PathBuf>,
+    retry_policy: Option<RetryPolicy>,
+}
+
+impl OptifiClientBuilder {
+    pub fn new(cluster: Cluster) -> Self {
+        Self {
+            cluster,
+            network: Network::default(),
+            ws_url: None,
+            wallet_path: None,
+            payer: None,
+            owner: None,
+            delegator: None,
+            read_commitment: None,
+            send_commitment: None,
+            optifi_exchange: None,
+            optifi_program_id: None,
+            optifi_usdc_program_id: None,
+            serum_dex_program_id: None,
+            usdc_token_mint: None,
+            compute_budgets: None,
+            consume_events_limit: None,
+            max_orders_per_transaction: None,
+            order_validation: None,
+            auto_initialize_markets: None,
+            market_cache_dir: None,
+            retry_policy: None,
+        }
+    }
+
+    pub fn network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Overrides the websocket url derived from the cluster.
+    pub fn ws_url(mut self, ws_url: impl Into<String>) -> Self {
+        self.ws_url = Some(ws_url.into());
+        self
+    }
+
+    /// Keypair file used when no signer is given, `~/.config/solana/id.json` by default.
+    pub fn wallet_path(mut self, wallet_path: impl Into<String>) -> Self {
+        self.wallet_path = Some(wallet_path.into());
+        self
+    }
+
+    /// Uses `signer` both as fee payer and as owner.
+    pub fn signer(mut self, signer: Arc<dyn Signer + Send + Sync>) -> Self {
+        self.payer = Some(signer.clone());
+        self.owner = Some(signer);
+        self
+    }
+
+    pub fn payer(mut self, payer: Arc<dyn Signer + Send + Sync>) -> Self {
+        self.payer = Some(payer);
+        self
+    }
+
+    pub fn owner(mut self, owner: Arc<dyn Signer + Send + Sync>) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    pub fn delegator(mut self, delegator: Pubkey) -> Self {
+        self.delegator = Some(delegator);
+        self
+    }
+
+    /// Sets both the read and the send commitment.
+    pub fn commitment(mut self, commitment: CommitmentConfig) -> Self {
+        self.read_commitment = Some(commitment);
+        self.send_commitment = Some(commitment);
+        self
+    }
+
+    pub fn read_commitment(mut self, commitment: CommitmentConfig) -> Self {
+        self.read_commitment = Some(commitment);
+        self
+    }
+
+    pub fn send_commitment(mut self, commitment: CommitmentConfig) -> Self {
+        self.send_commitment = Some(commitment);
+        self
+    }
+
+    pub fn optifi_exchange(mut self, optifi_exchange: Pubkey) -> Self {
+        self.optifi_exchange = Some(optifi_exchange);
+        self
+    }
+
+    pub fn optifi_program_id(mut self, program_id: Pubkey) -> Self {
+        self.optifi_program_id = Some(program_id);
+        self
+    }
+
+    pub fn optifi_usdc_program_id(mut self, program_id: Pubkey) -> Self {
+        self.optifi_usdc_program_id = Some(program_id);
+        self
+    }
+
+    pub fn serum_dex_program_id(mut self, program_id: Pubkey) -> Self {
+        self.serum_dex_program_id = Some(program_id);
+        self
+    }
+
+    pub fn usdc_token_mint(mut self, usdc_token_mint: Pubkey) -> Self {
+        self.usdc_token_mint = Some(usdc_token_mint);
+        self
+    }
+
+    /// Fixed compute unit limit for placing and cancelling orders.
+    pub fn compute_units(mut self, compute_units: u32) -> Self {
+        let mut compute_budgets = self.compute_budgets.unwrap_or_default();
+
+        compute_budgets.place_order.unit_limit = UnitLimit::Fixed(compute_units);
+        compute_budgets.cancel_order.unit_limit = UnitLimit::Fixed(compute_units);
+
+        self.compute_budgets = Some(compute_budgets);
+        self
+    }
+
+    pub fn compute_budget(mut self, operation: Operation, policy: ComputeBudgetPolicy) -> Self {
+        let mut compute_budgets = self.compute_budgets.unwrap_or_default();
+
+        compute_budgets.set(operation, policy);
+
+        self.compute_budgets = Some(compute_budgets);
+        self
+    }
+
+    pub fn consume_events_limit(mut self, limit: u16) -> Self {
+        self.consume_events_limit = Some(limit);
+        self
+    }
+
+    /// Most orders `place_orders` packs into one transaction.
+    pub fn max_orders_per_transaction(mut self, max_orders: usize) -> Self {
+        self.max_orders_per_transaction = Some(max_orders);
+        self
+    }
+
+    pub fn order_validation(mut self, order_validation: OrderValidation) -> Self {
+        self.order_validation = Some(order_validation);
+        self
+    }
+
+    /// Whether placing the first order on a market also creates the user's accounts on it.
+    pub fn auto_initialize_markets(mut self, auto_initialize_markets: bool) -> Self {
+        self.auto_initialize_markets = Some(auto_initialize_markets);
+        self
+    }
+
+    /// Caches market metadata in `dir` so `load_markets_cached` only fetches new markets.
+    pub fn market_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.market_cache_dir = Some(dir.into());
+        self
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Addresses of `network`, overridden by the ones set on the builder.
+    fn deployment(&self) -> std::result::Result<Deployment, OptifiClientError> {
+        let deployment = self.network.deployment()?;
+
+        Ok(Deployment {
+            optifi_exchange: self.optifi_exchange.unwrap_or(deployment.optifi_exchange),
+            optifi_program_id: self
+                .optifi_program_id
+                .unwrap_or(deployment.optifi_program_id),
+            optifi_usdc_program_id: self
+                .optifi_usdc_program_id
+                .unwrap_or(deployment.optifi_usdc_program_id),
+            serum_dex_program_id: self
+                .serum_dex_program_id
+                .unwrap_or(deployment.serum_dex_program_id),
+            usdc_token_mint: self.usdc_token_mint.unwrap_or(deployment.usdc_token_mint),
+        })
+    }
+
+    fn config(&self) -> std::result::Result<OptifiConfig, OptifiClientError> {
+        let defaults = OptifiConfig::for_deployment(self.deployment()?);
+
+        Ok(OptifiConfig {
+            read_commitment: self.read_commitment.unwrap_or(defaults.read_commitment),
+            send_commitment: self.send_commitment.unwrap_or(defaults.send_commitment),
+            optifi_exchange: defaults.optifi_exchange,
+            optifi_program_id: defaults.optifi_program_id,
+            optifi_usdc_program_id: defaults.optifi_usdc_program_id,
+            serum_dex_program_id: defaults.serum_dex_program_id,
+            usdc_token_mint: defaults.usdc_token_mint,
+            compute_budgets: self.compute_budgets.unwrap_or(defaults.compute_budgets),
+            consume_events_limit: self
+                .consume_events_limit
+                .unwrap_or(defaults.consume_events_limit),
+            max_orders_per_transaction: self
+                .max_orders_per_transaction
+                .unwrap_or(defaults.max_orders_per_transaction),
+            order_validation: self.order_validation.unwrap_or(defaults.order_validation),
+            auto_initialize_markets: self
+                .auto_initialize_markets
+                .unwrap_or(defaults.auto_initialize_markets),
+            market_cache_dir: self.market_cache_dir.clone().or(defaults.market_cache_dir),
+            retry_policy: self.retry_policy.unwrap_or(defaults.retry_policy),
+        })
+    }
+
+    fn cluster(&self) -> Cluster {
+        match &self.ws_url {
+            Some(ws_url) => Cluster::Custom(self.cluster.url().to_owned(), ws_url.clone()),
+            None => self.cluster.clone(),
+        }
+    }
+
+    /// Resolves the payer and owner, each falling back to the other and then to the wallet file.
+    fn signers(
+        &self,
+    ) -> std::result::Result<
+        (Arc<dyn Signer + Send + Sync>, Arc<dyn Signer + Send + Sync>),
+        OptifiClientError,
+    > {
+        match (&self.payer, &self.owner) {
+            (Some(payer), Some(owner)) => Ok((payer.clone(), owner.clone())),
+            (Some(signer), None) | (None, Some(signer)) => Ok((signer.clone(), signer.clone())),
+            (None, None) => {
+                let wallet_path = self
+                    .wallet_path
+                    .clone()
+                    .unwrap_or("~/.config/solana/id.json".to_owned());
+
+                let keypair = read_keypair_file(shellexpand::tilde(&wallet_path).as_ref())
+                    .map_err(|err| {
+                        OptifiClientError::Keypair(format!("{}: {}", wallet_path, err))
+                    })?;
+
+                let signer: Arc<dyn Signer + Send + Sync> = Arc::new(keypair);
+
+                Ok((signer.clone(), signer))
+            }
+        }
+    }
+
+    pub fn build(self) -> std::result::Result<OptifiClient, OptifiClientError> {
+        let (payer, owner) = self.signers()?;
+
+        Ok(OptifiClient::with_config(
+            self.cluster(),
+            payer,
+            owner,
+            self.delegator,
+            self.config()?,
+        ))
+    }
+
+    pub async fn build_async(self) -> std::result::Result<AsyncOptifiClient, OptifiClientError> {
+        let (payer, owner) = self.signers()?;
+
+        AsyncOptifiClient::with_config(self.cluster(), payer, owner, self.delegator, self.config()?)
+            .await
+    }
+}
@@ -1,11 +1,30 @@
-use anchor_lang::Discriminator;
+use std::collections::HashSet;
+use std::sync::{Arc, PoisonError, RwLock};
 
+use anchor_lang::Discriminator;
+use solana_client::pubsub_client::AccountSubscription;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcProgramAccountsConfig;
+use solana_sdk::signer::SignerError;
+
+use crate::compute_budget::{simulation_instructions, ComputeBudgetPolicy, Operation, UnitLimit};
+use crate::config::OptifiConfig;
+use crate::decoder::{MarketState, OpenOrder, OpenOrdersView, OrderSlab, SlabOrder};
+use crate::error::OptifiClientError;
+use crate::instructions::InstructionBuilder;
+use crate::network::Network;
+use crate::orders::{CancelResult, OrderBatch, OrderRequest, PlacedOrder};
 use crate::prelude::*;
+use crate::send::TransactionOutcome;
+use crate::types::{Price, Quantity, UsdcAmount};
 
 pub struct OptifiClient {
     pub cluster: Cluster,
-    pub program: Program,
-    pub usdc_program: Program,
+    pub rpc: RpcClient,
+    /// Pays transaction fees and rent for newly created accounts.
+    pub payer: Arc<dyn Signer + Send + Sync>,
+    /// Wallet that owns (or is delegated) `user_account` and signs its instructions.
+    pub owner: Arc<dyn Signer + Send + Sync>,
     pub optifi_exchange: Pubkey,
     pub user_account: Pubkey,
     pub usdc_token_mint: Pubkey,
@@ -13,26 +32,58 @@ pub struct OptifiClient {
     pub token_program: Pubkey,
     pub system_program: Pubkey,
     pub rent: Pubkey,
-    pub account: OptifiAccount,
+    pub config: OptifiConfig,
+    account: RwLock<Arc<OptifiAccount>>,
+    /// Optifi markets the user is known to be set up on, see `ensure_market_ready`.
+    pub(crate) ready_markets: RwLock<HashSet<Pubkey>>,
 }
 
-unsafe impl Send for OptifiClient {}
-unsafe impl Sync for OptifiClient {}
+/// Lends a shared signer to anchor's `Rc<dyn Signer>` based `Program` for the duration of a call.
+struct SharedSigner(Arc<dyn Signer + Send + Sync>);
+
+impl Signer for SharedSigner {
+    fn try_pubkey(&self) -> std::result::Result<Pubkey, SignerError> {
+        self.0.try_pubkey()
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> std::result::Result<Signature, SignerError> {
+        self.0.try_sign_message(message)
+    }
+
+    fn is_interactive(&self) -> bool {
+        self.0.is_interactive()
+    }
+}
 
 #[derive(Debug)]
 pub struct OptifiOrder {
     pub side: OrderSide,
-    pub price: f64,
-    pub size: f64,
+    pub price: Price,
+    pub size: Quantity,
     pub client_order_id: u64,
 }
 
+#[derive(Clone, Default)]
 pub struct OptifiAccount {
     pub optifi_exchange: Option<Exchange>,
     pub user_account: Option<UserAccount>,
     pub markets: Vec<Market>,
 }
 
+impl OptifiAccount {
+    pub fn get_optifi_exchange(&self) -> std::result::Result<&Exchange, OptifiClientError> {
+        self.optifi_exchange
+            .as_ref()
+            .ok_or(OptifiClientError::NotInitialized("optifi exchange"))
+    }
+
+    pub fn get_user_account(&self) -> std::result::Result<&UserAccount, OptifiClientError> {
+        self.user_account
+            .as_ref()
+            .ok_or(OptifiClientError::NotInitialized("user account"))
+    }
+}
+
 pub struct Market {
     pub optifi_market: OptifiMarket,
     pub optifi_market_key_data: OptifiMarketKeyData,
@@ -69,6 +120,7 @@ impl Clone for Market {
 impl std::fmt::Debug for Market {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         f.debug_struct("Market")
+            .field("symbol", &format_args!("{}", self))
             .field("optifi_market", &self.optifi_market)
             .field("optifi_market_key_data", &self.optifi_market_key_data)
             .field("instrument_common", &self.instrument_common)
@@ -80,240 +132,259 @@ impl std::fmt::Debug for Market {
 }
 
 impl OptifiClient {
-    pub fn new(cluster: Cluster, wallet_path: Option<String>, delegator: Option<Pubkey>) -> Self {
-        let optifi_exchange = Pubkey::from_str(OPTIFI_EXCHANGE).unwrap();
-
+    pub fn new(
+        cluster: Cluster,
+        network: Network,
+        wallet_path: Option<String>,
+        delegator: Option<Pubkey>,
+    ) -> std::result::Result<Self, OptifiClientError> {
         // Wallet and cluster params.
         let wallet_path = wallet_path.unwrap_or("~/.config/solana/id.json".to_owned());
 
         let payer = read_keypair_file(shellexpand::tilde(&wallet_path).as_ref())
-            .expect("Example requires a keypair file");
+            .map_err(|err| OptifiClientError::Keypair(format!("{}: {}", wallet_path, err)))?;
 
-        let user = payer.pubkey();
+        OptifiClient::new_with_signer(cluster, network, Arc::new(payer), delegator)
+    }
 
-        // Client.
-        let client = Client::new_with_options(
-            cluster.clone(),
-            Rc::new(payer),
-            CommitmentConfig::processed(),
-        );
+    /// Uses `signer` both as fee payer and as owner of the user account.
+    pub fn new_with_signer(
+        cluster: Cluster,
+        network: Network,
+        signer: Arc<dyn Signer + Send + Sync>,
+        delegator: Option<Pubkey>,
+    ) -> std::result::Result<Self, OptifiClientError> {
+        OptifiClient::new_with_signers(cluster, network, signer.clone(), signer, delegator)
+    }
 
-        // Program client.
-        let program = client.program(optifi_cpi::id());
+    /// Lets a separate `payer` fund transactions on behalf of `owner`.
+    pub fn new_with_signers(
+        cluster: Cluster,
+        network: Network,
+        payer: Arc<dyn Signer + Send + Sync>,
+        owner: Arc<dyn Signer + Send + Sync>,
+        delegator: Option<Pubkey>,
+    ) -> std::result::Result<Self, OptifiClientError> {
+        Ok(OptifiClient::with_config(
+            cluster,
+            payer,
+            owner,
+            delegator,
+            OptifiConfig::for_network(&network)?,
+        ))
+    }
 
-        let usdc_program = client.program(optifi_usdc_cpi::id());
+    /// Builds a client for the deployment described by `config`, see `OptifiClientBuilder`.
+    pub fn with_config(
+        cluster: Cluster,
+        payer: Arc<dyn Signer + Send + Sync>,
+        owner: Arc<dyn Signer + Send + Sync>,
+        delegator: Option<Pubkey>,
+        config: OptifiConfig,
+    ) -> Self {
+        let user = owner.pubkey();
 
-        let (optifi_usdc, ..) = Pubkey::find_program_address(&[b"optifi_usdc"], &usdc_program.id());
+        let rpc = RpcClient::new_with_commitment(cluster.url().to_string(), config.read_commitment);
 
-        let (user_account_key, ..) = if delegator.is_some() {
-            (delegator.unwrap(), 0)
+        let user_account_key = if let Some(delegator) = delegator {
+            delegator
         } else {
-            get_user_account_pda(&optifi_exchange, &user, &optifi_cpi::id())
-        };
-
-        let usdc_token_mint = Pubkey::from_str(USDC_TOKEN_MINT).unwrap();
-        let token_program = spl_token::id();
-        let system_program = solana_program::system_program::id();
-        let rent = solana_program::sysvar::rent::id();
-
-        let account = OptifiAccount {
-            optifi_exchange: None,
-            user_account: None,
-            markets: vec![],
+            get_user_account_pda(&config.optifi_exchange, &user, &config.optifi_program_id).0
         };
 
-        let optifi_client = Self {
+        Self {
             cluster,
-            program,
-            usdc_program,
-            optifi_exchange,
+            rpc,
+            payer,
+            owner,
+            optifi_exchange: config.optifi_exchange,
             user_account: user_account_key,
-            usdc_token_mint,
-            optifi_usdc_token_mint: optifi_usdc,
-            token_program,
-            system_program,
-            rent,
-            account,
-        };
-
-        optifi_client
+            usdc_token_mint: config.usdc_token_mint,
+            optifi_usdc_token_mint: config.optifi_usdc_token_mint(),
+            token_program: spl_token::id(),
+            system_program: solana_program::system_program::id(),
+            rent: solana_program::sysvar::rent::id(),
+            config,
+            account: RwLock::new(Arc::new(OptifiAccount::default())),
+            ready_markets: RwLock::new(HashSet::new()),
+        }
     }
 
+    /// Builds a client and loads the exchange, user account and markets.
+    ///
+    /// Markets that fail to load are left out, `load_markets` reports why.
     pub fn initialize(
         cluster: Cluster,
+        network: Network,
         wallet_path: Option<String>,
         delegator: Option<Pubkey>,
-    ) -> Self {
-        let mut optifi_client = OptifiClient::new(cluster, wallet_path, delegator);
+    ) -> std::result::Result<Self, OptifiClientError> {
+        let optifi_client = OptifiClient::new(cluster, network, wallet_path, delegator)?;
 
-        optifi_client.load_optifi_exchange();
-        optifi_client.load_user_account();
-        optifi_client.load_markets();
+        optifi_client.load_optifi_exchange()?;
+        optifi_client.load_user_account()?;
+        optifi_client.load_markets_cached()?;
 
-        optifi_client
+        Ok(optifi_client)
     }
 
-    pub fn load_optifi_exchange(&mut self) {
-        loop {
-            match self.program.account(self.optifi_exchange) {
-                Ok(result) => {
-                    self.account.optifi_exchange = Some(result);
-                    return;
-                }
-                Err(error) => {
-                    println!("reload optifi exchange error: {}", error);
-                    sleep(time::Duration::from_secs(10))
-                }
-            }
-        }
+    /// Builds an anchor `Program` for the optifi program, signed by `payer`.
+    pub fn program(&self) -> Program {
+        self.program_for(self.config.optifi_program_id)
     }
 
-    pub fn load_user_account(&mut self) {
-        loop {
-            match self.program.account(self.user_account) {
-                Ok(result) => {
-                    self.account.user_account = Some(result);
-                    return;
-                }
-                Err(error) => {
-                    println!("reload user account {} error: {}", self.user_account, error);
-                    sleep(time::Duration::from_secs(10))
-                }
-            }
-        }
+    /// Builds an anchor `Program` for the optifi usdc program, signed by `payer`.
+    pub fn usdc_program(&self) -> Program {
+        self.program_for(self.config.optifi_usdc_program_id)
     }
 
-    pub fn load_margin_stress_account(&self, asset: Asset) -> MarginStressAccount {
-        let (margin_stress, ..) =
-            get_margin_stress_account(&self.optifi_exchange, asset as u8, &optifi_cpi::id());
-
-        let margin_stress_account: MarginStressAccount =
-            self.program.account(margin_stress).unwrap();
-
-        margin_stress_account
+    fn program_for(&self, program_id: Pubkey) -> Program {
+        Client::new_with_options(
+            self.cluster.clone(),
+            Rc::new(SharedSigner(self.payer.clone())),
+            self.config.send_commitment,
+        )
+        .program(program_id)
     }
 
-    pub fn load_fee_account(&self) -> FeeAccount {
-        let (fee_account, ..) =
-            get_user_fee_account_pda(&self.optifi_exchange, &self.user_account, &optifi_cpi::id());
+    /// Instruction builder over `account`, usually a snapshot from `account()`.
+    pub fn instruction_builder<'a>(&'a self, account: &'a OptifiAccount) -> InstructionBuilder<'a> {
+        InstructionBuilder {
+            config: &self.config,
+            account,
+            payer: self.payer.pubkey(),
+            owner: self.owner.pubkey(),
+            user_account: self.user_account,
+        }
+    }
 
-        let fee_account: FeeAccount = self.program.account(fee_account).unwrap();
+    /// Prepends the compute budget instruction of `operation`'s policy, if it requests anything.
+    ///
+    /// `UnitLimit::Simulated` simulates `instructions` with the policy's maximum first.
+    pub fn with_compute_budget(
+        &self,
+        operation: Operation,
+        instructions: Vec<Instruction>,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        let policy = *self.config.compute_budgets.get(operation);
+
+        let units = match policy.unit_limit {
+            UnitLimit::Default => None,
+            UnitLimit::Fixed(units) => Some(units),
+            UnitLimit::Simulated { margin, max } => {
+                let simulation =
+                    self.simulate_instructions(simulation_instructions(max, &instructions))?;
+
+                Some(ComputeBudgetPolicy::simulated_units(
+                    margin,
+                    max,
+                    simulation.units_consumed,
+                ))
+            }
+        };
 
-        fee_account
+        Ok(policy.prepend(units, instructions))
     }
 
-    pub fn custom_accounts<T: AccountDeserialize + Discriminator>(
+    /// Sends `instructions` in one transaction paid by `payer`, also signed by `owner` when one
+    /// of them requires it, and waits for the send commitment.
+    pub fn send_instructions(
         &self,
-        filters: Vec<RpcFilterType>,
-    ) -> std::result::Result<Vec<(Pubkey, T)>, ClientError> {
-        let account_type_filter = RpcFilterType::Memcmp(Memcmp {
-            offset: 0,
-            bytes: MemcmpEncodedBytes::Base58(
-                solana_sdk::bs58::encode(T::discriminator()).into_string(),
-            ),
-            encoding: None,
-        });
-        let config = solana_client::rpc_config::RpcProgramAccountsConfig {
-            filters: Some([vec![account_type_filter], filters].concat()),
-            account_config: RpcAccountInfoConfig {
-                encoding: Some(UiAccountEncoding::Base64),
-                data_slice: None,
-                commitment: None,
-            },
-            with_context: None,
-        };
-        Ok(self
-            .program
-            .rpc()
-            .get_program_accounts_with_config(&self.program.id(), config)?
-            .into_iter()
-            .filter_map(|(key, account)| {
-                Some((key, T::try_deserialize(&mut (&account.data as &[u8])).ok()?))
-            })
-            .collect::<Vec<_>>())
+        instructions: Vec<Instruction>,
+    ) -> std::result::Result<TransactionOutcome, OptifiClientError> {
+        self.send_with_signers(instructions, &[])
     }
 
-    pub fn load_markets(&mut self) {
-        let optifi_exchange = self.account.optifi_exchange.as_ref().unwrap();
+    /// Like `send_instructions`, with `signers` signing in addition to `payer` and `owner`.
+    pub fn send_with_signers(
+        &self,
+        instructions: Vec<Instruction>,
+        signers: &[&dyn Signer],
+    ) -> std::result::Result<TransactionOutcome, OptifiClientError> {
+        self.send_and_confirm(&instructions, signers)
+    }
 
-        let optifi_markets = self.custom_accounts::<OptifiMarket>(vec![]).unwrap();
+    /// Snapshot of the cached exchange, user account and markets.
+    pub fn account(&self) -> Arc<OptifiAccount> {
+        self.account
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
 
-        // println!("{:#?}", optifi_exchange);
+    pub(crate) fn update_account<F: FnOnce(&mut OptifiAccount)>(&self, update: F) {
+        let mut account = self.account.write().unwrap_or_else(PoisonError::into_inner);
+        update(Arc::make_mut(&mut account));
+    }
 
-        // println!("{:?}", optifi_markets);
+    fn fetch<T: AccountDeserialize>(
+        &self,
+        pubkey: Pubkey,
+    ) -> std::result::Result<T, OptifiClientError> {
+        let account = self.fetch_account(&pubkey)?;
 
-        let mut markets = vec![];
+        Ok(T::try_deserialize(&mut (&account.data as &[u8]))?)
+    }
 
-        for optifi_market_key_data in optifi_exchange.markets.iter() {
-            if optifi_market_key_data.is_stopped {
-                continue;
-            }
+    pub(crate) fn fetch_account(
+        &self,
+        pubkey: &Pubkey,
+    ) -> std::result::Result<solana_sdk::account::Account, OptifiClientError> {
+        self.rpc
+            .get_account_with_commitment(pubkey, self.config.read_commitment)?
+            .value
+            .ok_or(OptifiClientError::AccountNotFound(*pubkey))
+    }
 
-            let optifi_market_pubkey = optifi_market_key_data.optifi_market_pubkey;
+    pub fn load_optifi_exchange(&self) -> std::result::Result<(), OptifiClientError> {
+        let optifi_exchange = self.fetch(self.optifi_exchange)?;
+        self.update_account(|account| account.optifi_exchange = Some(optifi_exchange));
+        Ok(())
+    }
 
-            let optifi_market: OptifiMarket = optifi_markets
-                .iter()
-                .find_map(|(pubkey, optifi_market)| {
-                    if pubkey == &optifi_market_pubkey {
-                        Some(optifi_market.clone())
-                    } else {
-                        None
-                    }
-                })
-                .unwrap();
+    pub fn load_user_account(&self) -> std::result::Result<(), OptifiClientError> {
+        let user_account = self.fetch(self.user_account)?;
+        self.update_account(|account| account.user_account = Some(user_account));
+        Ok(())
+    }
 
-            let instrument_pubkey = optifi_market.instrument;
+    pub fn load_margin_stress_account(
+        &self,
+        asset: Asset,
+    ) -> std::result::Result<MarginStressAccount, OptifiClientError> {
+        let (margin_stress, ..) = get_margin_stress_account(
+            &self.optifi_exchange,
+            asset as u8,
+            &self.config.optifi_program_id,
+        );
 
-            // println!("{}", instrument_pubkey);
+        self.fetch(margin_stress)
+    }
 
-            let (instrument_common, strike, is_call) = optifi_exchange
-                .get_instrument_data(&instrument_pubkey)
-                .unwrap();
+    pub fn load_fee_account(&self) -> std::result::Result<FeeAccount, OptifiClientError> {
+        let (fee_account, ..) = get_user_fee_account_pda(
+            &self.optifi_exchange,
+            &self.user_account,
+            &self.config.optifi_program_id,
+        );
 
-            let instrument_type = if is_call {
-                InstrumentType::Call
-            } else {
-                InstrumentType::Put
-            };
+        self.fetch(fee_account)
+    }
 
-            let serum_dex_program_id = Pubkey::from_str(SERUM_DEX_PROGRAM_ID).unwrap();
-
-            let market_pubkeys: MarketPubkeys = get_keys_for_market(
-                &self.program.rpc(),
-                &serum_dex_program_id,
-                &optifi_market.serum_market,
-            )
-            .unwrap();
-
-            let serum_account: solana_sdk::account::Account = self
-                .program
-                .rpc()
-                .get_account_with_commitment(
-                    &optifi_market.serum_market,
-                    CommitmentConfig::processed(),
-                )
-                .unwrap()
-                .value
-                .ok_or(ClientError::AccountNotFound)
-                .unwrap();
-
-            let market = Market {
-                optifi_market,
-                optifi_market_key_data: optifi_market_key_data.clone(),
-                instrument_common,
-                strike,
-                instrument_type,
-                market_pubkeys,
-                serum_account,
-            };
+    pub fn custom_accounts<T: AccountDeserialize + Discriminator>(
+        &self,
+        filters: Vec<RpcFilterType>,
+    ) -> std::result::Result<Vec<(Pubkey, T)>, OptifiClientError> {
+        let accounts = self.rpc.get_program_accounts_with_config(
+            &self.config.optifi_program_id,
+            program_accounts_config::<T>(self.config.read_commitment, filters),
+        )?;
 
-            markets.push(market);
-        }
-        self.account.markets = markets;
+        Ok(decode_program_accounts(accounts))
     }
 
     pub fn get_markets(&self, asset: Option<Asset>) -> Vec<Market> {
-        self.account
+        self.account()
             .markets
             .iter()
             .filter_map(|market| {
@@ -330,21 +401,14 @@ impl OptifiClient {
             .collect()
     }
 
-    pub fn get_user_account(&self) -> &UserAccount {
-        self.account.user_account.as_ref().unwrap()
+    pub fn get_user_account(&self) -> std::result::Result<UserAccount, OptifiClientError> {
+        self.account().get_user_account().cloned()
     }
 
-    pub fn get_usdc_balance(&self) -> Result<u64> {
-        let pubkey = &self.get_user_account().user_margin_account_usdc;
+    pub fn get_usdc_balance(&self) -> std::result::Result<u64, OptifiClientError> {
+        let pubkey = self.account().get_user_account()?.user_margin_account_usdc;
 
-        let mut account: solana_sdk::account::Account = self
-            .program
-            .rpc()
-            .get_account_with_commitment(pubkey, CommitmentConfig::processed())
-            .unwrap()
-            .value
-            .ok_or(ClientError::AccountNotFound)
-            .unwrap();
+        let mut account = self.fetch_account(&pubkey)?;
 
         let account_info = AccountInfo::new(
             &pubkey,
@@ -357,7 +421,7 @@ impl OptifiClient {
             account.rent_epoch,
         );
 
-        accessor::amount(&account_info)
+        Ok(accessor::amount(&account_info)?)
     }
     // pub fn get_user_position(&self) -> Vec<(Market, UserPosition)> {
     //     self.account
@@ -372,977 +436,329 @@ impl OptifiClient {
     //         .collect()
     // }
 
-    pub fn initialize_user_account(&self) -> std::result::Result<Signature, ClientError> {
-        let user = self.program.payer();
-
-        let (user_account_key, user_account_bump) =
-            get_user_account_pda(&self.optifi_exchange, &user, &optifi_cpi::id());
+    /// Instructions creating the user account, `user_margin_account_usdc` must also sign.
+    pub fn initialize_user_account_instructions(
+        &self,
+        user_margin_account_usdc: &Pubkey,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        let rent_lamports = self
+            .rpc
+            .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)?;
+
+        self.instruction_builder(&self.account())
+            .initialize_user_account(*user_margin_account_usdc, rent_lamports)
+    }
 
+    pub fn initialize_user_account(
+        &self,
+    ) -> std::result::Result<TransactionOutcome, OptifiClientError> {
         let user_margin_account_usdc = Keypair::new();
 
-        let (liquidation_account, liquidation_account_bump) = get_user_liquidation_account_pda(
-            &self.optifi_exchange,
-            &user_account_key,
-            &optifi_cpi::id(),
-        );
-
-        println!(
+        log::debug!(
             "user_margin_account_usdc: {}",
             user_margin_account_usdc.pubkey()
         );
 
-        let ix_1 = self
-            .program
-            .request()
-            .accounts(optifi_cpi::accounts::InitializeUserAccount {
-                optifi_exchange: self.optifi_exchange,
-                user_account: user_account_key,
-                user_margin_account_usdc: user_margin_account_usdc.pubkey(),
-                owner: user,
-                payer: user,
-                token_program: self.token_program,
-                system_program: self.system_program,
-                rent: self.rent,
-                liquidation_account,
-            })
-            .args(optifi_cpi::instruction::InitUserAccount {
-                bump: InitUserAccountBumpSeeds {
-                    user_account: user_account_bump,
-                    liquidation_account: liquidation_account_bump,
-                },
-            })
-            .instructions()
-            .unwrap()
-            .pop()
-            .unwrap();
-
-        let (fee_account, ..) =
-            get_user_fee_account_pda(&self.optifi_exchange, &user_account_key, &optifi_cpi::id());
-
-        let ix_2 = self
-            .program
-            .request()
-            .accounts(optifi_cpi::accounts::InitializeFeeAccount {
-                optifi_exchange: self.optifi_exchange,
-                user_account: user_account_key,
-                payer: user,
-                fee_account,
-                system_program: self.system_program,
-            })
-            .args(optifi_cpi::instruction::InitializeFeeAccount {})
-            .instructions()
-            .unwrap()
-            .pop()
-            .unwrap();
-
-        // Build and send a transaction.
-        let tx = self
-            .program
-            .request()
-            .instruction(system_instruction::create_account(
-                &user,
-                &user_margin_account_usdc.pubkey(),
-                self.program
-                    .rpc()
-                    .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)
-                    .unwrap(),
-                spl_token::state::Account::LEN as u64,
-                &self.token_program,
-            ))
-            .instruction(
-                spl_token::instruction::initialize_account(
-                    &self.token_program,
-                    &user_margin_account_usdc.pubkey(),
-                    &self.optifi_usdc_token_mint,
-                    &user,
-                )
-                .unwrap(),
-            )
-            .signer(&user_margin_account_usdc)
-            .instruction(ix_1)
-            .instruction(ix_2)
-            .send();
-
-        tx
+        self.send_with_signers(
+            self.with_compute_budget(
+                Operation::Other,
+                self.initialize_user_account_instructions(&user_margin_account_usdc.pubkey())?,
+            )?,
+            &[&user_margin_account_usdc],
+        )
     }
 
-    pub fn initialize_fee_account(&self) -> std::result::Result<Signature, ClientError> {
-        let user = self.program.payer();
-
-        let (user_account_key, ..) =
-            get_user_account_pda(&self.optifi_exchange, &user, &optifi_cpi::id());
-
-        let (fee_account, ..) =
-            get_user_fee_account_pda(&self.optifi_exchange, &user_account_key, &optifi_cpi::id());
-
-        let ix_2 = self
-            .program
-            .request()
-            .accounts(optifi_cpi::accounts::InitializeFeeAccount {
-                optifi_exchange: self.optifi_exchange,
-                user_account: user_account_key,
-                payer: user,
-                fee_account,
-                system_program: self.system_program,
-            })
-            .args(optifi_cpi::instruction::InitializeFeeAccount {})
-            .instructions()
-            .unwrap()
-            .pop()
-            .unwrap();
+    pub fn initialize_fee_account_instructions(
+        &self,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        Ok(self
+            .instruction_builder(&self.account())
+            .initialize_fee_account())
+    }
 
-        // Build and send a transaction.
-        let tx = self.program.request().instruction(ix_2).send();
+    pub fn initialize_fee_account(
+        &self,
+    ) -> std::result::Result<TransactionOutcome, OptifiClientError> {
+        self.send_instructions(self.with_compute_budget(
+            Operation::Other,
+            self.initialize_fee_account_instructions()?,
+        )?)
+    }
 
-        tx
+    pub fn set_delegation_instructions(
+        &self,
+        delegatee: Option<Pubkey>,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        Ok(self
+            .instruction_builder(&self.account())
+            .set_delegation(delegatee))
     }
 
     pub fn set_delegation(
         &self,
         delegatee: Option<Pubkey>,
-    ) -> std::result::Result<Signature, ClientError> {
-        let user = self.program.payer();
-
-        let user_account_key = self.user_account;
+    ) -> std::result::Result<TransactionOutcome, OptifiClientError> {
+        log::debug!("set delegation to: {:?}", delegatee);
 
-        println!("set delegation to: {:?}", delegatee);
-
-        // Build and send a transaction.
-        let tx = self
-            .program
-            .request()
-            .accounts(optifi_cpi::accounts::SetDelegation {
-                optifi_exchange: self.optifi_exchange,
-                user_account: user_account_key,
-                user,
-            })
-            .args(optifi_cpi::instruction::SetDelegation { delegatee })
-            .send();
-
-        tx
+        self.send_instructions(self.with_compute_budget(
+            Operation::Other,
+            self.set_delegation_instructions(delegatee)?,
+        )?)
     }
 
-    pub fn deposit(&self, ui_amount: f64) -> std::result::Result<Signature, ClientError> {
-        let user = self.program.payer();
-
-        let user_account_key = self.user_account;
-
-        let user_account: UserAccount = self.program.account(user_account_key).unwrap();
-
-        let user_margin_account_usdc = user_account.user_margin_account_usdc;
-
-        let amount = (ui_amount * 1000000.) as u64;
-
-        println!("deposit ui_amount: {}, amount: {}", ui_amount, amount);
-
-        // For optifi-usdc-cpi
-
-        let associated_token_program = anchor_spl::associated_token::AssociatedToken::id();
-
-        let (authority, ..) =
-            Pubkey::find_program_address(&[b"authority"], &self.usdc_program.id());
-
-        let usdc_vault = get_associated_token_address(&authority, &self.usdc_token_mint);
-
-        let owner_usdc = get_associated_token_address(&user, &self.usdc_token_mint);
-
-        let owner_optifi_usdc = get_associated_token_address(&user, &self.optifi_usdc_token_mint);
-
-        let ix = self
-            .usdc_program
-            .request()
-            .accounts(optifi_usdc_cpi::accounts::Wrap {
-                authority,
-                optifi_usdc: self.optifi_usdc_token_mint,
-                usdc_vault,
-                usdc_mint: self.usdc_token_mint,
-                owner_usdc,
-                owner_optifi_usdc,
-                owner: user,
-                token_program: self.token_program,
-                associated_token_program,
-                system_program: self.system_program,
-                rent: self.rent,
-            })
-            .args(optifi_usdc_cpi::instruction::Wrap { _amount: amount })
-            .instructions()
-            .unwrap()
-            .pop()
-            .unwrap();
-
-        // Build and send a transaction.
-        let tx = self
-            .program
-            .request()
-            .instruction(ix)
-            .accounts(optifi_cpi::accounts::Deposit {
-                optifi_exchange: self.optifi_exchange,
-                user_account: user_account_key,
-                user_margin_account_usdc,
-                user,
-                deposit_source: owner_optifi_usdc,
-                token_program: self.token_program,
-            })
-            .args(optifi_cpi::instruction::Deposit { amount })
-            .send();
+    pub fn deposit_instructions(
+        &self,
+        amount: UsdcAmount,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        let user_account: UserAccount = self.fetch(self.user_account)?;
 
-        tx
+        Ok(self
+            .instruction_builder(&self.account())
+            .deposit(amount.to_native()?, user_account.user_margin_account_usdc))
     }
 
-    pub fn withdraw(&self, ui_amount: f64) -> std::result::Result<Signature, ClientError> {
-        let user = self.program.payer();
-
-        let user_account_key = self.user_account;
-
-        let user_account: UserAccount = self.program.account(user_account_key).unwrap();
-
-        let user_margin_account_usdc = user_account.user_margin_account_usdc;
+    pub fn deposit(
+        &self,
+        amount: UsdcAmount,
+    ) -> std::result::Result<TransactionOutcome, OptifiClientError> {
+        log::debug!("deposit amount: {}", amount);
 
-        let withdraw_dest = get_associated_token_address(&user, &self.usdc_token_mint);
+        self.send_instructions(
+            self.with_compute_budget(Operation::Other, self.deposit_instructions(amount)?)?,
+        )
+    }
 
-        let amount = (ui_amount * 1000000.) as u64;
+    pub fn withdraw_instructions(
+        &self,
+        amount: UsdcAmount,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        let user_account: UserAccount = self.fetch(self.user_account)?;
 
-        println!("withdraw ui_amount: {}, amount: {}", ui_amount, amount);
+        Ok(self
+            .instruction_builder(&self.account())
+            .withdraw(amount.to_native()?, user_account.user_margin_account_usdc))
+    }
 
-        // Build and send a transaction.
-        let tx = self
-            .program
-            .request()
-            .accounts(optifi_cpi::accounts::Withdraw {
-                optifi_exchange: self.optifi_exchange,
-                user_account: user_account_key,
-                user_margin_account_usdc,
-                user,
-                withdraw_dest,
-                token_program: self.token_program,
-            })
-            .args(optifi_cpi::instruction::Withdraw { amount })
-            .send();
+    pub fn withdraw(
+        &self,
+        amount: UsdcAmount,
+    ) -> std::result::Result<TransactionOutcome, OptifiClientError> {
+        log::debug!("withdraw amount: {}", amount);
 
-        tx
+        self.send_instructions(
+            self.with_compute_budget(Operation::Other, self.withdraw_instructions(amount)?)?,
+        )
     }
 
-    pub fn initialize_user_on_market(
+    pub fn initialize_user_on_market_instructions(
         &self,
         market: &Market,
-    ) -> std::result::Result<Signature, ClientError> {
-        let user = self.program.payer();
-        let user_account = self.user_account;
-
-        let serum_dex_program_id = Pubkey::from_str(SERUM_DEX_PROGRAM_ID).unwrap();
-
-        let (serum_market_authority, ..) =
-            get_serum_market_auth_pda(&self.optifi_exchange, &optifi_cpi::id());
-
-        let optifi_market = market.optifi_market_key_data.optifi_market_pubkey;
-        let serum_market = market.optifi_market.serum_market;
-
-        let (serum_open_orders, bump) = get_serum_open_orders_account(
-            &self.optifi_exchange,
-            &user_account,
-            &serum_market,
-            &optifi_cpi::id(),
-        );
-
-        // println!("serum_open_orders: {}", serum_open_orders);
-
-        // Build and send a transaction.
-        let tx = self
-            .program
-            .request()
-            .instruction(create_associated_token_account(
-                &user,
-                &user_account,
-                &market.optifi_market.instrument_long_spl_token,
-            ))
-            .instruction(create_associated_token_account(
-                &user,
-                &user_account,
-                &market.optifi_market.instrument_short_spl_token,
-            ))
-            .accounts(optifi_cpi::accounts::InitUserOnOptifiMarket {
-                optifi_exchange: self.optifi_exchange,
-                user,
-                user_account,
-
-                optifi_market,
-                serum_market,
-                serum_open_orders,
-
-                serum_dex_program_id,
-                serum_market_authority,
-
-                payer: user,
-                system_program: self.system_program,
-                rent: self.rent,
-            })
-            .args(optifi_cpi::instruction::InitUserOnOptifiMarket { bump })
-            .send();
-
-        tx
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        Ok(self
+            .instruction_builder(&self.account())
+            .initialize_user_on_market(market))
     }
 
-    fn get_margin_stress_calculate_instruction(&self, asset: Asset) -> Instruction {
-        let exchange = self.account.optifi_exchange.as_ref().unwrap();
-
-        let oracle = exchange.get_oracle(asset);
-
-        let asset_feed = oracle.spot_oracle.unwrap();
-
-        let usdc_feed = exchange.get_oracle(Asset::USDC).spot_oracle.unwrap();
-
-        let (margin_stress_account, ..) =
-            get_margin_stress_account(&self.optifi_exchange, asset as u8, &optifi_cpi::id());
-
-        let ix = self
-            .program
-            .request()
-            .accounts(optifi_cpi::accounts::CalculateMarginStressContext {
-                optifi_exchange: self.optifi_exchange,
-                margin_stress_account,
-                asset_feed,
-                usdc_feed,
-            })
-            .args(optifi_cpi::instruction::MarginStressCalculate {})
-            .instructions()
-            .unwrap()
-            .pop()
-            .unwrap();
+    pub fn initialize_user_on_market(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<TransactionOutcome, OptifiClientError> {
+        let outcome = self.send_instructions(self.with_compute_budget(
+            Operation::Other,
+            self.initialize_user_on_market_instructions(market)?,
+        )?)?;
+
+        if outcome.is_confirmed() {
+            self.mark_market_ready(market);
+        }
 
-        ix
+        Ok(outcome)
     }
 
     // pub fn load_all_open_orders(&self) -> Vec<(&Market, OptifiOrder)> {}
 
-    pub fn load_open_orders(&self, market: &Market) -> Vec<OptifiOrder> {
-        let serum_market = market.optifi_market.serum_market;
-
+    pub fn load_open_orders(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<Vec<OptifiOrder>, OptifiClientError> {
         let asset = market.instrument_common.asset;
 
-        let serum_dex_program_id = Pubkey::from_str(SERUM_DEX_PROGRAM_ID).unwrap();
-
         let (open_orders, ..) = get_serum_open_orders_account(
             &self.optifi_exchange,
             &self.user_account,
-            &serum_market,
-            &optifi_cpi::id(),
+            &market.optifi_market.serum_market,
+            &self.config.optifi_program_id,
         );
 
-        let mut market_account = market.serum_account.clone();
+        let serum_market_pubkeys: &MarketPubkeys = &market.market_pubkeys;
 
-        let market_account_info = AccountInfo::new(
-            &serum_market,
-            false,
-            false,
-            &mut market_account.lamports,
-            &mut market_account.data,
-            &mut market_account.owner,
-            market_account.executable,
-            market_account.rent_epoch,
-        );
+        let accounts = self.fetch_multiple_accounts(&[
+            open_orders,
+            *serum_market_pubkeys.asks,
+            *serum_market_pubkeys.bids,
+        ])?;
 
-        let mut orders_account = match self
-            .program
-            .rpc()
-            .get_account_with_commitment(&open_orders, CommitmentConfig::processed())
-            .unwrap()
-            .value
-            .ok_or(ClientError::AccountNotFound)
-        {
-            Ok(account) => account,
-            Err(_) => {
-                // println!("AccountNotFound");
-                return vec![];
+        let (orders_account, asks_account, bids_account) = match accounts.as_slice() {
+            [None, ..] => return Ok(vec![]),
+            [Some(orders_account), Some(asks_account), Some(bids_account)] => {
+                (orders_account, asks_account, bids_account)
+            }
+            [_, None, _] => {
+                return Err(OptifiClientError::AccountNotFound(
+                    *serum_market_pubkeys.asks,
+                ))
+            }
+            _ => {
+                return Err(OptifiClientError::AccountNotFound(
+                    *serum_market_pubkeys.bids,
+                ))
             }
         };
 
-        let orders_account_info = AccountInfo::new(
-            &open_orders,
-            false,
-            false,
-            &mut orders_account.lamports,
-            &mut orders_account.data,
-            &mut orders_account.owner,
-            orders_account.executable,
-            orders_account.rent_epoch,
-        );
-
-        let serum_market_pubkeys: &MarketPubkeys = &market.market_pubkeys;
-
-        let mut asks_account = self
-            .program
-            .rpc()
-            .get_account_with_commitment(&serum_market_pubkeys.asks, CommitmentConfig::processed())
-            .unwrap()
-            .value
-            .ok_or(ClientError::AccountNotFound)
-            .unwrap();
-
-        let asks_account_info = AccountInfo::new(
-            &serum_market_pubkeys.asks,
-            false,
-            true,
-            &mut asks_account.lamports,
-            &mut asks_account.data,
-            &mut asks_account.owner,
-            asks_account.executable,
-            asks_account.rent_epoch,
-        );
-
-        let mut bids_account = self
-            .program
-            .rpc()
-            .get_account_with_commitment(&serum_market_pubkeys.bids, CommitmentConfig::processed())
-            .unwrap()
-            .value
-            .ok_or(ClientError::AccountNotFound)
-            .unwrap();
-
-        let bids_account_info = AccountInfo::new(
-            &serum_market_pubkeys.bids,
-            false,
-            true,
-            &mut bids_account.lamports,
-            &mut bids_account.data,
-            &mut bids_account.owner,
-            bids_account.executable,
-            bids_account.rent_epoch,
-        );
-
-        let market =
-            serum_dex::state::Market::load(&market_account_info, &serum_dex_program_id, false)
-                .unwrap();
-
-        let open_orders = market
-            .load_orders_mut(
-                &orders_account_info,
-                None,
-                &serum_dex_program_id,
-                None,
-                None,
-            )
-            .unwrap();
-
-        let mut asks = market
-            .load_asks_mut(&asks_account_info)
-            .map_err(|err| Error::ProgramError(ProgramError::from(err).into()))
-            .unwrap();
-
-        let mut bids = market
-            .load_bids_mut(&bids_account_info)
-            .map_err(|err| Error::ProgramError(ProgramError::from(err).into()))
-            .unwrap();
-
-        // let client_order_ids = open_orders.client_order_ids;
-
-        // println!("{:#?}", client_order_ids);
+        let asks = OrderSlab::decode(&asks_account.data, OrderSide::Ask)?;
+        let bids = OrderSlab::decode(&bids_account.data, OrderSide::Bid)?;
 
         let mut orders: Vec<OptifiOrder> = vec![];
 
-        for order_id in open_orders.orders.into_iter() {
-            if let Some(key) = asks.find_by_key(order_id) {
-                let node = asks.deref_mut().get(key).unwrap().as_leaf().unwrap();
-                let order = OptifiOrder {
-                    side: OrderSide::Ask,
-                    price: u64::from(node.price()) as f64
-                        / 10_u32.pow(USDC_DECIMALS - asset.get_decimal()) as f64,
-                    size: node.quantity() as f64 / 10_u32.pow(asset.get_decimal()) as f64,
-                    client_order_id: node.client_order_id(),
-                };
-                orders.push(order);
-            } else if let Some(key) = bids.find_by_key(order_id) {
-                let node = bids.deref_mut().get(key).unwrap().as_leaf().unwrap();
-                let order = OptifiOrder {
-                    side: OrderSide::Bid,
-                    price: u64::from(node.price()) as f64
-                        / 10_u32.pow(USDC_DECIMALS - asset.get_decimal()) as f64,
-                    size: node.quantity() as f64 / 10_u32.pow(asset.get_decimal()) as f64,
-                    client_order_id: node.client_order_id(),
-                };
-                orders.push(order);
+        for open_order in OpenOrdersView::decode(&orders_account.data)?.orders() {
+            let slab = match open_order.side {
+                OrderSide::Ask => &asks,
+                OrderSide::Bid => &bids,
+            };
+
+            if let Some(order) = slab.find(open_order.order_id)? {
+                orders.push(OptifiOrder {
+                    side: open_order.side,
+                    price: Price::from_native(asset, order.price()),
+                    size: Quantity::from_native(asset, order.quantity),
+                    client_order_id: order.client_order_id,
+                });
             }
         }
-        orders
-    }
-
-    pub fn load_order_book(&self, market: &Market) -> Book {
-        let serum_market = market.optifi_market.serum_market;
 
-        let asset = market.instrument_common.asset;
-
-        let serum_dex_program_id = Pubkey::from_str(SERUM_DEX_PROGRAM_ID).unwrap();
-
-        let mut market_account = market.serum_account.clone();
-
-        let market_account_info = AccountInfo::new(
-            &serum_market,
-            false,
-            false,
-            &mut market_account.lamports,
-            &mut market_account.data,
-            &mut market_account.owner,
-            market_account.executable,
-            market_account.rent_epoch,
-        );
+        Ok(orders)
+    }
 
+    /// Bids and asks of `market` read at the same slot, at most `depth` levels per side.
+    pub fn load_order_book(
+        &self,
+        market: &Market,
+        depth: Option<usize>,
+    ) -> std::result::Result<Book, OptifiClientError> {
         let serum_market_pubkeys: &MarketPubkeys = &market.market_pubkeys;
 
-        let mut asks_account = self
-            .program
-            .rpc()
-            .get_account_with_commitment(&serum_market_pubkeys.asks, CommitmentConfig::processed())
-            .unwrap()
-            .value
-            .ok_or(ClientError::AccountNotFound)
-            .unwrap();
-
-        let asks_account_info = AccountInfo::new(
-            &serum_market_pubkeys.asks,
-            false,
-            true,
-            &mut asks_account.lamports,
-            &mut asks_account.data,
-            &mut asks_account.owner,
-            asks_account.executable,
-            asks_account.rent_epoch,
-        );
-
-        let mut bids_account = self
-            .program
-            .rpc()
-            .get_account_with_commitment(&serum_market_pubkeys.bids, CommitmentConfig::processed())
-            .unwrap()
-            .value
-            .ok_or(ClientError::AccountNotFound)
-            .unwrap();
-
-        let bids_account_info = AccountInfo::new(
-            &serum_market_pubkeys.bids,
-            false,
-            true,
-            &mut bids_account.lamports,
-            &mut bids_account.data,
-            &mut bids_account.owner,
-            bids_account.executable,
-            bids_account.rent_epoch,
-        );
-
-        let market =
-            serum_dex::state::Market::load(&market_account_info, &serum_dex_program_id, false)
-                .unwrap();
-
-        let asks = market
-            .load_asks_mut(&asks_account_info)
-            .map_err(|err| Error::ProgramError(ProgramError::from(err).into()))
-            .unwrap();
-
-        let bids = market
-            .load_bids_mut(&bids_account_info)
-            .map_err(|err| Error::ProgramError(ProgramError::from(err).into()))
-            .unwrap();
-
-        let mut ask_levels: Vec<BookLevel> = vec![];
-
-        for node in asks.traverse().iter() {
-            let order = OptifiOrder {
-                side: OrderSide::Ask,
-                price: u64::from(node.price()) as f64
-                    / 10_u32.pow(USDC_DECIMALS - asset.get_decimal()) as f64,
-                size: node.quantity() as f64 / 10_u32.pow(asset.get_decimal()) as f64,
-                client_order_id: node.client_order_id(),
-            };
-            // println!("{:#?}", order);
-
-            if let Some(level) = ask_levels
-                .iter_mut()
-                .find(|level| level.price == order.price)
-            {
-                level.size += order.size;
-            } else {
-                ask_levels.push(BookLevel {
-                    price: order.price,
-                    size: order.size,
-                });
+        let response = self.rpc.get_multiple_accounts_with_commitment(
+            &[*serum_market_pubkeys.asks, *serum_market_pubkeys.bids],
+            self.config.read_commitment,
+        )?;
+
+        let (asks_account, bids_account) = match response.value.as_slice() {
+            [Some(asks_account), Some(bids_account)] => (asks_account, bids_account),
+            [None, _] => {
+                return Err(OptifiClientError::AccountNotFound(
+                    *serum_market_pubkeys.asks,
+                ))
             }
-        }
-
-        let mut bid_levels: Vec<BookLevel> = vec![];
-
-        for node in bids.traverse().iter() {
-            let order = OptifiOrder {
-                side: OrderSide::Bid,
-                price: u64::from(node.price()) as f64
-                    / 10_u32.pow(USDC_DECIMALS - asset.get_decimal()) as f64,
-                size: node.quantity() as f64 / 10_u32.pow(asset.get_decimal()) as f64,
-                client_order_id: node.client_order_id(),
-            };
-            // println!("{:#?}", order);
-
-            if let Some(level) = bid_levels
-                .iter_mut()
-                .find(|level| level.price == order.price)
-            {
-                level.size += order.size;
-            } else {
-                bid_levels.push(BookLevel {
-                    price: order.price,
-                    size: order.size,
-                });
+            _ => {
+                return Err(OptifiClientError::AccountNotFound(
+                    *serum_market_pubkeys.bids,
+                ))
             }
-        }
+        };
 
-        Book {
-            bids: bid_levels,
-            asks: ask_levels,
-        }
+        Ok(Book {
+            bids: parse_book_side(market, OrderSide::Bid, &bids_account.data, depth)?,
+            asks: parse_book_side(market, OrderSide::Ask, &asks_account.data, depth)?,
+            slot: response.context.slot,
+        })
     }
 
-    pub fn place_order(
+    /// Every transaction `place_order` sends, without compute budget instructions.
+    ///
+    /// The order is validated first, see `validate_orders`. The instructions creating the
+    /// user's missing accounts on `market`, see `ensure_market_ready`, come before the margin
+    /// stress when they fit, and are a setup-only batch before the order's otherwise.
+    pub fn place_order_instructions(
         &self,
         market: &Market,
         side: OrderSide,
-        price: f64,
-        size: f64,
+        price: Price,
+        size: Quantity,
         order_type: OrderType,
-    ) -> std::result::Result<Signature, ClientError> {
-        let user = self.program.payer();
-        let user_account = self.user_account;
-
-        let serum_dex_program_id = Pubkey::from_str(SERUM_DEX_PROGRAM_ID).unwrap();
-
-        let optifi_market = market.optifi_market_key_data.optifi_market_pubkey;
-        let serum_market = market.optifi_market.serum_market;
-
-        let (open_orders, ..) = get_serum_open_orders_account(
-            &self.optifi_exchange,
-            &user_account,
-            &serum_market,
-            &optifi_cpi::id(),
-        );
-
-        let serum_market_pubkeys: &MarketPubkeys = &market.market_pubkeys;
-
-        // println!("{:#?}", market);
-
-        let asset = market.instrument_common.asset;
-
-        let (margin_stress_account, ..) =
-            get_margin_stress_account(&self.optifi_exchange, asset as u8, &optifi_cpi::id());
-
-        let usdc_fee_pool = self.account.optifi_exchange.as_ref().unwrap().usdc_fee_pool;
-
-        let user_margin_account = self
-            .account
-            .user_account
-            .as_ref()
-            .unwrap()
-            .user_margin_account_usdc;
-
-        let (instrument_token_mint_authority_pda, ..) =
-            get_optifi_market_mint_auth_pda(&self.optifi_exchange, &optifi_cpi::id());
-
-        let user_instrument_long_token_vault = get_associated_token_address(
-            &user_account,
-            &market.optifi_market.instrument_long_spl_token,
-        );
-
-        let user_instrument_short_token_vault = get_associated_token_address(
-            &user_account,
-            &market.optifi_market.instrument_short_spl_token,
-        );
-
-        let (fee_account, ..) =
-            get_user_fee_account_pda(&self.optifi_exchange, &user_account, &optifi_cpi::id());
-
-        // Calculation
-
-        // let limit = (price * 10_u32.pow(USDC_DECIMALS - asset.get_decimal()) as f64) as u64;
-        let limit = Decimal::from_f64(price)
-            .unwrap()
-            .checked_mul(
-                Decimal::from_u64(10_u64.pow(USDC_DECIMALS - asset.get_decimal())).unwrap(),
-            )
-            .unwrap()
-            .to_u64()
-            .unwrap();
-
-        // let max_coin_qty = (size * 10_u32.pow(asset.get_decimal()) as f64) as u64;
-        let max_coin_qty = Decimal::from_f64(size)
-            .unwrap()
-            .checked_mul(Decimal::from_u64(10_u64.pow(asset.get_decimal())).unwrap())
-            .unwrap()
-            .to_u64()
-            .unwrap();
-
-        // let max_pc_qty = ((limit * max_coin_qty) as f64 * (1.0 + TAKER_FEE)) as u64;
-        let max_pc_qty = Decimal::from_u64(limit * max_coin_qty)
-            .unwrap()
-            .checked_mul(
-                Decimal::from_f64(1.0)
-                    .unwrap()
-                    .checked_add(Decimal::from_f64(SERUM_TAKER_FEE).unwrap())
-                    .unwrap(),
-            )
-            .unwrap()
-            .to_u64()
-            .unwrap();
-
-        // Margin Stress
-
-        let ix_2 = self.get_margin_stress_calculate_instruction(asset);
-
-        let ix_3 = self
-            .program
-            .request()
-            .accounts(optifi_cpi::accounts::PlaceOrderContext {
-                optifi_exchange: self.optifi_exchange,
-
-                user,
-                user_account,
-                user_margin_account,
-                fee_account,
-
-                optifi_market,
-                serum_market,
-                open_orders,
-
-                asks: *serum_market_pubkeys.asks,
-                bids: *serum_market_pubkeys.bids,
-                pc_vault: *serum_market_pubkeys.pc_vault,
-                coin_vault: *serum_market_pubkeys.coin_vault,
-                request_queue: *serum_market_pubkeys.req_q,
-                event_queue: *serum_market_pubkeys.event_q,
-
-                coin_mint: market.optifi_market.instrument_long_spl_token,
-                instrument_short_spl_token_mint: market.optifi_market.instrument_short_spl_token,
-                instrument_token_mint_authority_pda,
-                user_instrument_long_token_vault,
-                user_instrument_short_token_vault,
-
-                usdc_fee_pool,
-
-                margin_stress_account,
-
-                serum_dex_program_id,
-                token_program: self.token_program,
-                rent: self.rent,
-            })
-            .args(optifi_cpi::instruction::PlaceOrder {
+    ) -> std::result::Result<Vec<OrderBatch>, OptifiClientError> {
+        self.place_orders_instructions(
+            market,
+            &[OrderRequest {
                 side,
-                limit: limit as u64,
-                max_coin_qty: max_coin_qty as u64,
-                max_pc_qty,
-                order_type: order_type as u8,
-            })
-            .instructions()
-            .unwrap()
-            .pop()
-            .unwrap();
-
-        let (serum_market_authority, ..) =
-            get_serum_market_auth_pda(&self.optifi_exchange, &optifi_cpi::id());
-
-        let ix_4 = self
-            .program
-            .request()
-            .accounts(optifi_cpi::accounts::ConsumeEventQueue {
-                optifi_exchange: self.optifi_exchange,
-                serum_market,
-                event_queue: *serum_market_pubkeys.event_q,
-                user_serum_open_orders: open_orders,
-                consume_events_authority: serum_market_authority,
-                serum_dex_program_id,
-            })
-            .args(optifi_cpi::instruction::ConsumeEventQueue { limit: Some(5) })
-            .instructions()
-            .unwrap()
-            .pop()
-            .unwrap();
-
-        let ix_5 = self
-            .program
-            .request()
-            .accounts(optifi_cpi::accounts::OrderSettlement {
-                optifi_exchange: self.optifi_exchange,
-
-                user_account,
-                user_margin_account,
-
-                optifi_market,
-                serum_market,
-                user_serum_open_orders: open_orders,
-                fee_account,
-
-                pc_vault: *serum_market_pubkeys.pc_vault,
-                coin_vault: *serum_market_pubkeys.coin_vault,
-                vault_signer: *serum_market_pubkeys.vault_signer_key,
-
-                instrument_long_spl_token_mint: market.optifi_market.instrument_long_spl_token,
-                instrument_short_spl_token_mint: market.optifi_market.instrument_short_spl_token,
-                user_instrument_long_token_vault,
-                user_instrument_short_token_vault,
-
-                serum_dex_program_id,
-                token_program: self.token_program,
-            })
-            .args(optifi_cpi::instruction::SettleOrderFunds {})
-            .instructions()
-            .unwrap()
-            .pop()
-            .unwrap();
-
-        let ix_6 = self
-            .program
-            .request()
-            .accounts(optifi_cpi::accounts::MarginContext {
-                optifi_exchange: self.optifi_exchange,
-                user_account,
-                margin_stress_account,
-            })
-            .args(optifi_cpi::instruction::UserMarginCalculate {})
-            .instructions()
-            .unwrap()
-            .pop()
-            .unwrap();
-
-        // Build and send a transaction.
-        let tx = self
-            .program
-            .request()
-            .instruction(ComputeBudgetInstruction::request_units(1400000, 0))
-            .instruction(ix_2)
-            .instruction(ix_3)
-            .instruction(ix_4)
-            .instruction(ix_5)
-            .instruction(ix_6)
-            .send();
-
-        tx
+                price,
+                size,
+                order_type,
+            }],
+        )
     }
 
-    pub fn settle_order(&self, market: &Market) -> std::result::Result<Signature, ClientError> {
-        let user_account = self.user_account;
-
-        let serum_dex_program_id = Pubkey::from_str(SERUM_DEX_PROGRAM_ID).unwrap();
-
-        let optifi_market = market.optifi_market_key_data.optifi_market_pubkey;
-        let serum_market = market.optifi_market.serum_market;
-
-        let (open_orders, ..) = get_serum_open_orders_account(
-            &self.optifi_exchange,
-            &user_account,
-            &serum_market,
-            &optifi_cpi::id(),
-        );
-
-        let (fee_account, ..) =
-            get_user_fee_account_pda(&self.optifi_exchange, &user_account, &optifi_cpi::id());
-
-        let serum_market_pubkeys: &MarketPubkeys = &market.market_pubkeys;
-
-        let user_margin_account = self
-            .account
-            .user_account
-            .as_ref()
-            .unwrap()
-            .user_margin_account_usdc;
-
-        let user_instrument_long_token_vault = get_associated_token_address(
-            &user_account,
-            &market.optifi_market.instrument_long_spl_token,
-        );
-
-        let user_instrument_short_token_vault = get_associated_token_address(
-            &user_account,
-            &market.optifi_market.instrument_short_spl_token,
-        );
-
-        let asset = market.instrument_common.asset;
+    /// Places an order and reports the client order id the program assigned to it, see
+    /// `PlacedOrder`.
+    ///
+    /// Setup that does not fit with the order is sent first and must confirm.
+    pub fn place_order(
+        &self,
+        market: &Market,
+        side: OrderSide,
+        price: Price,
+        size: Quantity,
+        order_type: OrderType,
+    ) -> std::result::Result<PlacedOrder, OptifiClientError> {
+        let batches = self.send_setup_batches(
+            market,
+            self.place_order_instructions(market, side, price, size, order_type)?,
+        )?;
+
+        let placed = self.send_tracking_client_order_id(
+            market,
+            self.with_compute_budget(
+                Operation::PlaceOrder,
+                batches
+                    .into_iter()
+                    .flat_map(|batch| batch.instructions)
+                    .collect(),
+            )?,
+        )?;
+
+        if placed.outcome.is_confirmed() {
+            self.mark_market_ready(market);
+        }
 
-        let ix_2 = self.get_margin_stress_calculate_instruction(asset);
-
-        let (serum_market_authority, ..) =
-            get_serum_market_auth_pda(&self.optifi_exchange, &optifi_cpi::id());
-
-        let ix_4 = self
-            .program
-            .request()
-            .accounts(optifi_cpi::accounts::ConsumeEventQueue {
-                optifi_exchange: self.optifi_exchange,
-                serum_market,
-                event_queue: *serum_market_pubkeys.event_q,
-                user_serum_open_orders: open_orders,
-                consume_events_authority: serum_market_authority,
-                serum_dex_program_id,
-            })
-            .args(optifi_cpi::instruction::ConsumeEventQueue { limit: Some(5) })
-            .instructions()
-            .unwrap()
-            .pop()
-            .unwrap();
-
-        let ix_5 = self
-            .program
-            .request()
-            .accounts(optifi_cpi::accounts::OrderSettlement {
-                optifi_exchange: self.optifi_exchange,
-
-                user_account,
-                user_margin_account,
-
-                optifi_market,
-                serum_market,
-                user_serum_open_orders: open_orders,
-                fee_account,
-
-                pc_vault: *serum_market_pubkeys.pc_vault,
-                coin_vault: *serum_market_pubkeys.coin_vault,
-                vault_signer: *serum_market_pubkeys.vault_signer_key,
-
-                instrument_long_spl_token_mint: market.optifi_market.instrument_long_spl_token,
-                instrument_short_spl_token_mint: market.optifi_market.instrument_short_spl_token,
-                user_instrument_long_token_vault,
-                user_instrument_short_token_vault,
-
-                serum_dex_program_id,
-                token_program: self.token_program,
-            })
-            .args(optifi_cpi::instruction::SettleOrderFunds {})
-            .instructions()
-            .unwrap()
-            .pop()
-            .unwrap();
+        Ok(placed)
+    }
 
-        let asset = market.instrument_common.asset;
+    pub fn settle_order_instructions(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        self.instruction_builder(&self.account())
+            .settle_order(market)
+    }
 
-        let (margin_stress_account, ..) =
-            get_margin_stress_account(&self.optifi_exchange, asset as u8, &optifi_cpi::id());
+    pub fn settle_order(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<TransactionOutcome, OptifiClientError> {
+        self.send_instructions(self.with_compute_budget(
+            Operation::SettleOrder,
+            self.settle_order_instructions(market)?,
+        )?)
+    }
 
-        let ix_6 = self
-            .program
-            .request()
-            .accounts(optifi_cpi::accounts::MarginContext {
-                optifi_exchange: self.optifi_exchange,
-                user_account,
-                margin_stress_account,
-            })
-            .args(optifi_cpi::instruction::UserMarginCalculate {})
-            .instructions()
-            .unwrap()
-            .pop()
-            .unwrap();
-
-        // Build and send a transaction.
-        let tx = self
-            .program
-            .request()
-            .instruction(ix_2)
-            .instruction(ix_4)
-            .instruction(ix_5)
-            .instruction(ix_6)
-            .send();
-
-        tx
+    /// Margin stress, cancel order and settlement, without a compute budget instruction.
+    pub fn cancel_order_instructions(
+        &self,
+        market: &Market,
+        side: OrderSide,
+        client_order_id: u64,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        self.instruction_builder(&self.account())
+            .cancel_order(market, side, client_order_id)
     }
 
     pub fn cancel_order(
@@ -1350,283 +766,102 @@ impl OptifiClient {
         market: &Market,
         side: OrderSide,
         client_order_id: u64,
-    ) -> std::result::Result<Signature, ClientError> {
-        let user = self.program.payer();
-        let user_account = self.user_account;
-
-        let serum_dex_program_id = Pubkey::from_str(SERUM_DEX_PROGRAM_ID).unwrap();
-
-        let optifi_market = market.optifi_market_key_data.optifi_market_pubkey;
-        let serum_market = market.optifi_market.serum_market;
-
-        let (open_orders, ..) = get_serum_open_orders_account(
-            &self.optifi_exchange,
-            &user_account,
-            &serum_market,
-            &optifi_cpi::id(),
-        );
-
-        let serum_market_pubkeys: &MarketPubkeys = &market.market_pubkeys;
-
-        let usdc_fee_pool = self.account.optifi_exchange.as_ref().unwrap().usdc_fee_pool;
-
-        let user_margin_account = self
-            .account
-            .user_account
-            .as_ref()
-            .unwrap()
-            .user_margin_account_usdc;
-
-        let user_instrument_long_token_vault = get_associated_token_address(
-            &user_account,
-            &market.optifi_market.instrument_long_spl_token,
-        );
-
-        let user_instrument_short_token_vault = get_associated_token_address(
-            &user_account,
-            &market.optifi_market.instrument_short_spl_token,
-        );
-
-        let (fee_account, ..) =
-            get_user_fee_account_pda(&self.optifi_exchange, &user_account, &optifi_cpi::id());
-
-        let (central_usdc_pool_auth, ..) =
-            get_central_usdc_pool_auth_pda(&self.optifi_exchange, &optifi_cpi::id());
-
-        let asset = market.instrument_common.asset;
-
-        let (margin_stress_account, ..) =
-            get_margin_stress_account(&self.optifi_exchange, asset as u8, &optifi_cpi::id());
-
-        let ix_2 = self.get_margin_stress_calculate_instruction(asset);
-
-        let ix_3 = self
-            .program
-            .request()
-            .accounts(optifi_cpi::accounts::CancelOrderContext {
-                optifi_exchange: self.optifi_exchange,
-
-                user,
-                user_account,
-                user_margin_account,
-                fee_account,
-
-                serum_market,
-                open_orders,
-
-                asks: *serum_market_pubkeys.asks,
-                bids: *serum_market_pubkeys.bids,
-                event_queue: *serum_market_pubkeys.event_q,
-
-                usdc_fee_pool,
-                central_usdc_pool_auth,
-
-                margin_stress_account,
-
-                serum_dex_program_id,
-                token_program: self.token_program,
-            })
-            .args(optifi_cpi::instruction::CancelOrderByClientOrderId {
-                side,
-                client_order_id,
-            })
-            .instructions()
-            .unwrap()
-            .pop()
-            .unwrap();
-
-        let (serum_market_authority, ..) =
-            get_serum_market_auth_pda(&self.optifi_exchange, &optifi_cpi::id());
-
-        let ix_4 = self
-            .program
-            .request()
-            .accounts(optifi_cpi::accounts::ConsumeEventQueue {
-                optifi_exchange: self.optifi_exchange,
-                serum_market,
-                event_queue: *serum_market_pubkeys.event_q,
-                user_serum_open_orders: open_orders,
-                consume_events_authority: serum_market_authority,
-                serum_dex_program_id,
-            })
-            .args(optifi_cpi::instruction::ConsumeEventQueue { limit: Some(5) })
-            .instructions()
-            .unwrap()
-            .pop()
-            .unwrap();
-
-        let ix_5 = self
-            .program
-            .request()
-            .accounts(optifi_cpi::accounts::OrderSettlement {
-                optifi_exchange: self.optifi_exchange,
-
-                user_account,
-                user_margin_account,
-
-                optifi_market,
-                serum_market,
-                user_serum_open_orders: open_orders,
-                fee_account,
-
-                pc_vault: *serum_market_pubkeys.pc_vault,
-                coin_vault: *serum_market_pubkeys.coin_vault,
-                vault_signer: *serum_market_pubkeys.vault_signer_key,
-
-                instrument_long_spl_token_mint: market.optifi_market.instrument_long_spl_token,
-                instrument_short_spl_token_mint: market.optifi_market.instrument_short_spl_token,
-                user_instrument_long_token_vault,
-                user_instrument_short_token_vault,
-
-                serum_dex_program_id,
-                token_program: self.token_program,
-            })
-            .args(optifi_cpi::instruction::SettleOrderFunds {})
-            .instructions()
-            .unwrap()
-            .pop()
-            .unwrap();
-
-        let (margin_stress_account, ..) =
-            get_margin_stress_account(&self.optifi_exchange, asset as u8, &optifi_cpi::id());
-
-        let ix_6 = self
-            .program
-            .request()
-            .accounts(optifi_cpi::accounts::MarginContext {
-                optifi_exchange: self.optifi_exchange,
-                user_account,
-                margin_stress_account,
-            })
-            .args(optifi_cpi::instruction::UserMarginCalculate {})
-            .instructions()
-            .unwrap()
-            .pop()
-            .unwrap();
-
-        // Build and send a transaction.
-        let tx = self
-            .program
-            .request()
-            .instruction(ComputeBudgetInstruction::request_units(1400000, 0))
-            .instruction(ix_2)
-            .instruction(ix_3)
-            .instruction(ix_4)
-            .instruction(ix_5)
-            .instruction(ix_6)
-            .send();
-
-        tx
+    ) -> std::result::Result<TransactionOutcome, OptifiClientError> {
+        self.send_instructions(self.with_compute_budget(
+            Operation::CancelOrder,
+            self.cancel_order_instructions(market, side, client_order_id)?,
+        )?)
     }
 
-    pub fn cancel_all_order(&self, market: &Market) {
-        let orders = self.load_open_orders(market);
+    /// Cancels every open order on `market`, packed into as few transactions as possible.
+    pub fn cancel_all_order(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<Vec<CancelResult>, OptifiClientError> {
+        let orders: Vec<(OrderSide, u64)> = self
+            .load_open_orders(market)?
+            .iter()
+            .map(|order| (order.side, order.client_order_id))
+            .collect();
 
-        for order in orders.iter() {
-            let signature = self
-                .cancel_order(market, order.side, order.client_order_id)
-                .unwrap();
+        self.cancel_orders(market, &orders)
+    }
 
-            println!("signature: {:#?}", signature);
-        }
+    /// Subscribes to updates of `pubkey` at the read commitment.
+    pub fn account_subscribe(
+        &self,
+        pubkey: &Pubkey,
+    ) -> std::result::Result<AccountSubscription, OptifiClientError> {
+        Ok(PubsubClient::account_subscribe(
+            self.cluster.ws_url(),
+            pubkey,
+            Some(RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                data_slice: None,
+                commitment: Some(self.config.read_commitment),
+            }),
+        )?)
     }
 
-    pub fn subscribe_ask(&self, market: &Market) {
+    /// Calls `on_update` with every update of `pubkey` parsed by `parse`, subscribing again
+    /// whenever the connection drops.
+    ///
+    /// Only returns on a subscription or parse error.
+    fn subscribe_with<T>(
+        &self,
+        pubkey: &Pubkey,
+        parse: impl Fn(Response<UiAccount>) -> std::result::Result<T, OptifiClientError>,
+        mut on_update: impl FnMut(T),
+    ) -> std::result::Result<(), OptifiClientError> {
         loop {
-            let start = Instant::now();
-
-            let (_subscription, receiver) = PubsubClient::account_subscribe(
-                self.cluster.ws_url(),
-                &market.market_pubkeys.asks,
-                Some(RpcAccountInfoConfig {
-                    encoding: Some(UiAccountEncoding::Base64),
-                    data_slice: None,
-                    commitment: None,
-                }),
-            )
-            .unwrap();
-
-            loop {
-                match receiver.recv() {
-                    Ok(ui_account) => {
-                        let _levels = parse_asks_inner(market, ui_account);
-                        println!("{:#?}", _levels);
-                    }
-                    Err(_e) => {
-                        // println!("{}", _e);
-                        println!("Time for one subscription: {:?}", start.elapsed());
-                        break;
-                    }
-                }
+            let (_subscription, receiver) = self.account_subscribe(pubkey)?;
+
+            for ui_account in receiver.iter() {
+                on_update(parse(ui_account)?);
             }
         }
     }
 
-    pub fn subscribe_open_orders(&self, market: &Market) {
+    /// Calls `on_levels` with the ask levels of `market` on every update.
+    pub fn subscribe_ask(
+        &self,
+        market: &Market,
+        on_levels: impl FnMut(Vec<BookLevel>),
+    ) -> std::result::Result<(), OptifiClientError> {
+        self.subscribe_with(
+            &market.market_pubkeys.asks,
+            |ui_account| parse_asks_inner(market, ui_account),
+            on_levels,
+        )
+    }
+
+    /// Calls `on_orders` with the orders resting in the user's open orders account of `market`
+    /// on every update.
+    pub fn subscribe_open_orders(
+        &self,
+        market: &Market,
+        on_orders: impl FnMut(Vec<OpenOrder>),
+    ) -> std::result::Result<(), OptifiClientError> {
         let (open_orders, ..) = get_serum_open_orders_account(
             &self.optifi_exchange,
             &self.user_account,
             &market.optifi_market.serum_market,
-            &optifi_cpi::id(),
+            &self.config.optifi_program_id,
         );
 
-        loop {
-            // let start = Instant::now();
-
-            let (_subscription, orders_receiver) = PubsubClient::account_subscribe(
-                self.cluster.ws_url(),
-                &open_orders,
-                Some(RpcAccountInfoConfig {
-                    encoding: Some(UiAccountEncoding::Base64),
-                    data_slice: None,
-                    commitment: None,
-                }),
-            )
-            .unwrap();
-
-            loop {
-                match orders_receiver.recv() {
-                    Ok(orders_ui_account) => {
-                        parse_open_orders(market, orders_ui_account);
-                    }
-                    Err(_e) => {
-                        // println!("{}", _e);
-                        // println!("Time for one subscription: {:?}", start.elapsed());
-                        break;
-                    }
-                }
-            }
-        }
+        self.subscribe_with(&open_orders, parse_open_orders, on_orders)
     }
 
-    pub fn subscribe_user_account(&self) {
-        loop {
-            let (_subscription, receiver) = PubsubClient::account_subscribe(
-                self.cluster.ws_url(),
-                &self.user_account,
-                Some(RpcAccountInfoConfig {
-                    encoding: Some(UiAccountEncoding::Base64),
-                    data_slice: None,
-                    commitment: None,
-                }),
-            )
-            .unwrap();
-
-            loop {
-                match receiver.recv() {
-                    Ok(ui_account) => {
-                        let user_account = parse_user_account_inner(ui_account);
-
-                        println!("{:#?}", user_account.unwrap());
-                    }
-                    Err(_e) => {
-                        println!("{}", _e);
-                        // println!("Time for one subscription: {:?}", start.elapsed());
-                        break;
-                    }
-                }
-            }
-        }
+    /// Calls `on_user_account` with the user account on every update.
+    pub fn subscribe_user_account(
+        &self,
+        on_user_account: impl FnMut(UserAccount),
+    ) -> std::result::Result<(), OptifiClientError> {
+        self.subscribe_with(
+            &self.user_account,
+            parse_user_account_inner,
+            on_user_account,
+        )
     }
 }
 
@@ -1634,246 +869,275 @@ pub fn get_optifi_id() -> Pubkey {
     optifi_cpi::id()
 }
 
-pub fn parse_asks(market: &Market, result: &Value) -> Vec<BookLevel> {
-    parse_asks_inner(&market, serde_json::from_value(result.clone()).unwrap())
+/// `getProgramAccounts` config selecting the accounts of type `T` that match `filters`.
+pub fn program_accounts_config<T: Discriminator>(
+    commitment: CommitmentConfig,
+    filters: Vec<RpcFilterType>,
+) -> RpcProgramAccountsConfig {
+    let account_type_filter = RpcFilterType::Memcmp(Memcmp {
+        offset: 0,
+        bytes: MemcmpEncodedBytes::Base58(
+            solana_sdk::bs58::encode(T::discriminator()).into_string(),
+        ),
+        encoding: None,
+    });
+
+    RpcProgramAccountsConfig {
+        filters: Some([vec![account_type_filter], filters].concat()),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: None,
+            commitment: Some(commitment),
+        },
+        with_context: None,
+    }
 }
 
-pub fn parse_asks_inner(market: &Market, ui_account: Response<UiAccount>) -> Vec<BookLevel> {
-    let serum_market = market.optifi_market.serum_market;
-
-    let asset = market.instrument_common.asset;
-
-    let mut market_account = market.serum_account.clone();
-
-    let market_account_info = AccountInfo::new(
-        &serum_market,
-        false,
-        false,
-        &mut market_account.lamports,
-        &mut market_account.data,
-        &mut market_account.owner,
-        market_account.executable,
-        market_account.rent_epoch,
-    );
+/// Deserializes fetched program `accounts` as `T`, leaving out those that fail to.
+pub fn decode_program_accounts<T: AccountDeserialize>(
+    accounts: Vec<(Pubkey, solana_sdk::account::Account)>,
+) -> Vec<(Pubkey, T)> {
+    accounts
+        .into_iter()
+        .filter_map(|(key, account)| {
+            Some((key, T::try_deserialize(&mut (&account.data as &[u8])).ok()?))
+        })
+        .collect()
+}
 
-    let serum_dex_program_id = Pubkey::from_str(SERUM_DEX_PROGRAM_ID).unwrap();
+/// Converts a price and size into serum native `(limit, max_coin_qty, max_pc_qty)`.
+pub fn get_order_native_amounts(
+    asset: Asset,
+    price: Price,
+    size: Quantity,
+) -> std::result::Result<(u64, u64, u64), OptifiClientError> {
+    let limit = price.to_native(asset)?;
+
+    let max_coin_qty = size.to_native(asset)?;
+
+    // let max_pc_qty = ((limit * max_coin_qty) as f64 * (1.0 + TAKER_FEE)) as u64;
+    let max_pc_qty = limit
+        .checked_mul(max_coin_qty)
+        .zip(Decimal::from_f64(SERUM_TAKER_FEE))
+        .and_then(|(notional, taker_fee)| {
+            Decimal::from(notional).checked_mul(Decimal::ONE.checked_add(taker_fee)?)
+        })
+        .and_then(|max_pc_qty| max_pc_qty.to_u64())
+        .ok_or_else(|| {
+            OptifiClientError::Conversion(format!("invalid notional {} x {}", price, size))
+        })?;
+
+    Ok((limit, max_coin_qty, max_pc_qty))
+}
 
-    let serum_market =
-        serum_dex::state::Market::load(&market_account_info, &serum_dex_program_id, false).unwrap();
+/// Derives the serum market pubkeys from an already fetched serum market account.
+pub fn get_market_pubkeys(
+    serum_market: &Pubkey,
+    serum_account: &solana_sdk::account::Account,
+) -> std::result::Result<MarketPubkeys, OptifiClientError> {
+    // The serum market account is owned by the dex program it was created with.
+    let serum_dex_program_id = serum_account.owner;
+
+    let market_state = MarketState::decode(&serum_account.data)?;
+
+    let vault_signer_key = serum_dex::state::gen_vault_signer_key(
+        market_state.vault_signer_nonce,
+        serum_market,
+        &serum_dex_program_id,
+    )
+    .map_err(|err| OptifiClientError::SerumSlab(err.to_string()))?;
+
+    Ok(MarketPubkeys {
+        market: Box::new(*serum_market),
+        req_q: Box::new(market_state.req_q),
+        event_q: Box::new(market_state.event_q),
+        bids: Box::new(market_state.bids),
+        asks: Box::new(market_state.asks),
+        coin_vault: Box::new(market_state.coin_vault),
+        pc_vault: Box::new(market_state.pc_vault),
+        vault_signer_key: Box::new(vault_signer_key),
+    })
+}
 
-    let mut asks_account = ui_account
-        .value
-        .decode::<anchor_client::solana_sdk::account::Account>()
-        .unwrap();
+/// Orders of the `side` slab of `market`, decoded from the slab account's bytes.
+pub fn parse_slab_orders(
+    market: &Market,
+    side: OrderSide,
+    data: &[u8],
+) -> std::result::Result<Vec<OptifiOrder>, OptifiClientError> {
+    let asset = market.instrument_common.asset;
 
-    let asks_account_info = AccountInfo::new(
-        &market.market_pubkeys.asks,
-        false,
-        true,
-        &mut asks_account.lamports,
-        &mut asks_account.data,
-        &mut asks_account.owner,
-        asks_account.executable,
-        asks_account.rent_epoch,
-    );
+    Ok(OrderSlab::decode(data, side)?
+        .orders()?
+        .iter()
+        .map(|order| OptifiOrder {
+            side,
+            price: Price::from_native(asset, order.price()),
+            size: Quantity::from_native(asset, order.quantity),
+            client_order_id: order.client_order_id,
+        })
+        .collect())
+}
 
-    let asks = serum_market
-        .load_asks_mut(&asks_account_info)
-        .map_err(|err| Error::ProgramError(ProgramError::from(err).into()))
-        .unwrap();
+/// Aggregates `orders` of an `asset` market, by ascending key as `OrderSlab::orders` returns
+/// them, into at most `depth` levels of `side`, best price first.
+///
+/// Orders are summed on their native price, so one pass over the orders is enough.
+pub fn book_levels(
+    asset: Asset,
+    side: OrderSide,
+    orders: &[SlabOrder],
+    depth: Option<usize>,
+) -> Vec<BookLevel> {
+    fn native_levels<'a>(
+        orders: impl Iterator<Item = &'a SlabOrder>,
+        depth: usize,
+    ) -> Vec<(u64, u64)> {
+        let mut levels: Vec<(u64, u64)> = vec![];
+
+        for order in orders {
+            match levels.last_mut() {
+                Some((price, size)) if *price == order.price() => {
+                    *size = size.saturating_add(order.quantity)
+                }
+                _ if levels.len() == depth => break,
+                _ => levels.push((order.price(), order.quantity)),
+            }
+        }
 
-    let mut ask_levels: Vec<BookLevel> = vec![];
+        levels
+    }
 
-    for node in asks.traverse().iter() {
-        let order = OptifiOrder {
-            side: OrderSide::Ask,
-            price: u64::from(node.price()) as f64
-                / 10_u32.pow(USDC_DECIMALS - asset.get_decimal()) as f64,
-            size: node.quantity() as f64 / 10_u32.pow(asset.get_decimal()) as f64,
-            client_order_id: node.client_order_id(),
-        };
-        // println!("{:#?}", order);
+    let depth = depth.unwrap_or(usize::MAX);
 
-        if let Some(level) = ask_levels
-            .iter_mut()
-            .find(|level| level.price == order.price)
-        {
-            level.size += order.size;
-        } else {
-            ask_levels.push(BookLevel {
-                price: order.price,
-                size: order.size,
-            });
-        }
-    }
+    let levels = match side {
+        OrderSide::Bid => native_levels(orders.iter().rev(), depth),
+        OrderSide::Ask => native_levels(orders.iter(), depth),
+    };
 
-    ask_levels
+    levels
+        .into_iter()
+        .map(|(price, size)| BookLevel {
+            price: Price::from_native(asset, price),
+            size: Quantity::from_native(asset, size),
+        })
+        .collect()
 }
 
-pub fn parse_bids(market: &Market, result: &Value) -> Vec<BookLevel> {
-    parse_bids_inner(&market, serde_json::from_value(result.clone()).unwrap())
+/// Levels of the `side` slab of `market`, decoded from the slab account's bytes, see
+/// `book_levels`.
+pub fn parse_book_side(
+    market: &Market,
+    side: OrderSide,
+    data: &[u8],
+    depth: Option<usize>,
+) -> std::result::Result<Vec<BookLevel>, OptifiClientError> {
+    Ok(book_levels(
+        market.instrument_common.asset,
+        side,
+        &OrderSlab::decode(data, side)?.orders()?,
+        depth,
+    ))
 }
 
-pub fn parse_bids_inner(market: &Market, ui_account: Response<UiAccount>) -> Vec<BookLevel> {
-    let serum_market = market.optifi_market.serum_market;
-
-    let asset = market.instrument_common.asset;
-
-    let mut market_account = market.serum_account.clone();
-
-    let market_account_info = AccountInfo::new(
-        &serum_market,
-        false,
-        false,
-        &mut market_account.lamports,
-        &mut market_account.data,
-        &mut market_account.owner,
-        market_account.executable,
-        market_account.rent_epoch,
-    );
-
-    let serum_dex_program_id = Pubkey::from_str(SERUM_DEX_PROGRAM_ID).unwrap();
-
-    let serum_market =
-        serum_dex::state::Market::load(&market_account_info, &serum_dex_program_id, false).unwrap();
-
-    let mut bids_account = ui_account
+fn decode_ui_account(
+    ui_account: Response<UiAccount>,
+    name: &str,
+) -> std::result::Result<solana_sdk::account::Account, OptifiClientError> {
+    ui_account
         .value
         .decode::<anchor_client::solana_sdk::account::Account>()
-        .unwrap();
+        .ok_or_else(|| OptifiClientError::Deserialization(format!("{} account", name)))
+}
 
-    let bids_account_info = AccountInfo::new(
-        &market.market_pubkeys.bids,
-        false,
-        true,
-        &mut bids_account.lamports,
-        &mut bids_account.data,
-        &mut bids_account.owner,
-        bids_account.executable,
-        bids_account.rent_epoch,
-    );
+pub fn parse_asks(
+    market: &Market,
+    result: &Value,
+) -> std::result::Result<Vec<BookLevel>, OptifiClientError> {
+    parse_asks_inner(&market, serde_json::from_value(result.clone())?)
+}
 
-    let bids = serum_market
-        .load_bids_mut(&bids_account_info)
-        .map_err(|err| Error::ProgramError(ProgramError::from(err).into()))
-        .unwrap();
+pub fn parse_asks_inner(
+    market: &Market,
+    ui_account: Response<UiAccount>,
+) -> std::result::Result<Vec<BookLevel>, OptifiClientError> {
+    let asks_account = decode_ui_account(ui_account, "asks")?;
 
-    let mut bid_levels: Vec<BookLevel> = vec![];
+    parse_book_side(market, OrderSide::Ask, &asks_account.data, None)
+}
 
-    for node in bids.traverse().iter() {
-        let order = OptifiOrder {
-            side: OrderSide::Bid,
-            price: u64::from(node.price()) as f64
-                / 10_u32.pow(USDC_DECIMALS - asset.get_decimal()) as f64,
-            size: node.quantity() as f64 / 10_u32.pow(asset.get_decimal()) as f64,
-            client_order_id: node.client_order_id(),
-        };
-        // println!("{:#?}", order);
+pub fn parse_bids(
+    market: &Market,
+    result: &Value,
+) -> std::result::Result<Vec<BookLevel>, OptifiClientError> {
+    parse_bids_inner(&market, serde_json::from_value(result.clone())?)
+}
 
-        if let Some(level) = bid_levels
-            .iter_mut()
-            .find(|level| level.price == order.price)
-        {
-            level.size += order.size;
-        } else {
-            bid_levels.push(BookLevel {
-                price: order.price,
-                size: order.size,
-            });
-        }
-    }
+pub fn parse_bids_inner(
+    market: &Market,
+    ui_account: Response<UiAccount>,
+) -> std::result::Result<Vec<BookLevel>, OptifiClientError> {
+    let bids_account = decode_ui_account(ui_account, "bids")?;
 
-    bid_levels
+    parse_book_side(market, OrderSide::Bid, &bids_account.data, None)
 }
 
-#[derive(Debug)]
+/// Order book snapshot of a market.
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Book {
+    /// Highest price first.
     pub bids: Vec<BookLevel>,
+    /// Lowest price first.
     pub asks: Vec<BookLevel>,
+    /// Slot the bids and asks were read at.
+    pub slot: u64,
 }
 
-#[derive(Debug)]
-pub struct BookLevel {
-    pub price: f64,
-    pub size: f64,
-}
-
-pub fn parse_open_orders(market: &Market, orders_ui_account: Response<UiAccount>) {
-    let serum_market = market.optifi_market.serum_market;
-
-    let mut market_account = market.serum_account.clone();
-
-    let market_account_info = AccountInfo::new(
-        &serum_market,
-        false,
-        false,
-        &mut market_account.lamports,
-        &mut market_account.data,
-        &mut market_account.owner,
-        market_account.executable,
-        market_account.rent_epoch,
-    );
-
-    let serum_dex_program_id = Pubkey::from_str(SERUM_DEX_PROGRAM_ID).unwrap();
-
-    let serum_market =
-        serum_dex::state::Market::load(&market_account_info, &serum_dex_program_id, false).unwrap();
-
-    let mut orders_account = orders_ui_account
-        .value
-        .decode::<anchor_client::solana_sdk::account::Account>()
-        .unwrap();
+impl Book {
+    pub fn best_bid(&self) -> Option<BookLevel> {
+        self.bids.first().copied()
+    }
 
-    let orders_account_info = AccountInfo::new(
-        &market.market_pubkeys.bids,
-        false,
-        true,
-        &mut orders_account.lamports,
-        &mut orders_account.data,
-        &mut orders_account.owner,
-        orders_account.executable,
-        orders_account.rent_epoch,
-    );
+    pub fn best_ask(&self) -> Option<BookLevel> {
+        self.asks.first().copied()
+    }
+}
 
-    let open_orders = serum_market
-        .load_orders_mut(
-            &orders_account_info,
-            None,
-            &serum_dex_program_id,
-            None,
-            None,
-        )
-        .unwrap();
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BookLevel {
+    pub price: Price,
+    pub size: Quantity,
+}
 
-    for open_order in open_orders.orders_with_client_ids() {
-        println!("client_order_ids: {:#?}", open_order);
-    }
+/// Orders resting in an open orders account update.
+pub fn parse_open_orders(
+    orders_ui_account: Response<UiAccount>,
+) -> std::result::Result<Vec<OpenOrder>, OptifiClientError> {
+    let orders_account = decode_ui_account(orders_ui_account, "orders")?;
 
-    // let if_need_settle = open_orders.native_coin_free > 0 || open_orders.native_pc_free > 0;
+    Ok(OpenOrdersView::decode(&orders_account.data)?.orders())
 }
 
-pub fn parse_user_account(result: &Value) -> Result<UserAccount> {
-    parse_user_account_inner(serde_json::from_value(result.clone()).unwrap())
+pub fn parse_user_account(result: &Value) -> std::result::Result<UserAccount, OptifiClientError> {
+    parse_user_account_inner(serde_json::from_value(result.clone())?)
 }
 
-pub fn parse_user_account_inner(ui_account: Response<UiAccount>) -> Result<UserAccount> {
-    let account = ui_account
-        .value
-        .decode::<anchor_client::solana_sdk::account::Account>()
-        .unwrap();
+pub fn parse_user_account_inner(
+    ui_account: Response<UiAccount>,
+) -> std::result::Result<UserAccount, OptifiClientError> {
+    let account = decode_ui_account(ui_account, "user")?;
 
-    UserAccount::try_deserialize(&mut (&account.data as &[u8]))
+    Ok(UserAccount::try_deserialize(&mut (&account.data as &[u8]))?)
 }
 
-pub fn parse_usdc_account(result: &Value) -> Result<u64> {
-    parse_usdc_account_inner(serde_json::from_value(result.clone()).unwrap())
+pub fn parse_usdc_account(result: &Value) -> std::result::Result<u64, OptifiClientError> {
+    parse_usdc_account_inner(serde_json::from_value(result.clone())?)
 }
 
-pub fn parse_usdc_account_inner(ui_account: Response<UiAccount>) -> Result<u64> {
-    let mut account = ui_account
-        .value
-        .decode::<anchor_client::solana_sdk::account::Account>()
-        .unwrap();
+pub fn parse_usdc_account_inner(
+    ui_account: Response<UiAccount>,
+) -> std::result::Result<u64, OptifiClientError> {
+    let mut account = decode_ui_account(ui_account, "usdc")?;
 
     let pubkey = Pubkey::default();
 
@@ -1888,137 +1152,25 @@ pub fn parse_usdc_account_inner(ui_account: Response<UiAccount>) -> Result<u64>
         account.rent_epoch,
     );
 
-    accessor::amount(&account_info)
+    Ok(accessor::amount(&account_info)?)
 }
 
-pub fn load_asks(client: &OptifiClient, market: &Market) {
-    let serum_market = market.optifi_market.serum_market;
+/// Orders resting on the asks side of `market`.
+pub fn load_asks(
+    client: &OptifiClient,
+    market: &Market,
+) -> std::result::Result<Vec<OptifiOrder>, OptifiClientError> {
+    let asks_account = client.rpc.get_account(&market.market_pubkeys.asks)?;
 
-    let asset = market.instrument_common.asset;
-
-    let mut market_account = market.serum_account.clone();
-
-    let market_account_info = AccountInfo::new(
-        &serum_market,
-        false,
-        false,
-        &mut market_account.lamports,
-        &mut market_account.data,
-        &mut market_account.owner,
-        market_account.executable,
-        market_account.rent_epoch,
-    );
-
-    let serum_dex_program_id = Pubkey::from_str(SERUM_DEX_PROGRAM_ID).unwrap();
-
-    let serum_market =
-        serum_dex::state::Market::load(&market_account_info, &serum_dex_program_id, false).unwrap();
-
-    let mut asks_account = client
-        .program
-        .rpc()
-        .get_account(&market.market_pubkeys.asks)
-        .unwrap();
-
-    let asks_account_info = AccountInfo::new(
-        &market.market_pubkeys.asks,
-        false,
-        true,
-        &mut asks_account.lamports,
-        &mut asks_account.data,
-        &mut asks_account.owner,
-        asks_account.executable,
-        asks_account.rent_epoch,
-    );
-
-    let asks = serum_market
-        .load_asks_mut(&asks_account_info)
-        .map_err(|err| Error::ProgramError(ProgramError::from(err).into()))
-        .unwrap();
-
-    for node in asks.traverse().iter() {
-        let order = OptifiOrder {
-            side: OrderSide::Ask,
-            price: u64::from(node.price()) as f64
-                / 10_u32.pow(USDC_DECIMALS - asset.get_decimal()) as f64,
-            size: node.quantity() as f64 / 10_u32.pow(asset.get_decimal()) as f64,
-            client_order_id: node.client_order_id(),
-        };
-        // println!("Ask");
-        // println!("{:#?}", node);
-        println!(
-            "{:?}, {}, {:?}, {}, {}",
-            market.instrument_common.asset,
-            market.strike,
-            market.instrument_type,
-            order.size,
-            order.price
-        );
-    }
+    parse_slab_orders(market, OrderSide::Ask, &asks_account.data)
 }
 
-pub fn load_bids(client: &OptifiClient, market: &Market) {
-    let serum_market = market.optifi_market.serum_market;
-
-    let asset = market.instrument_common.asset;
-
-    let mut market_account = market.serum_account.clone();
-
-    let market_account_info = AccountInfo::new(
-        &serum_market,
-        false,
-        false,
-        &mut market_account.lamports,
-        &mut market_account.data,
-        &mut market_account.owner,
-        market_account.executable,
-        market_account.rent_epoch,
-    );
-
-    let serum_dex_program_id = Pubkey::from_str(SERUM_DEX_PROGRAM_ID).unwrap();
-
-    let serum_market =
-        serum_dex::state::Market::load(&market_account_info, &serum_dex_program_id, false).unwrap();
-
-    let mut bids_account = client
-        .program
-        .rpc()
-        .get_account(&market.market_pubkeys.bids)
-        .unwrap();
+/// Orders resting on the bids side of `market`.
+pub fn load_bids(
+    client: &OptifiClient,
+    market: &Market,
+) -> std::result::Result<Vec<OptifiOrder>, OptifiClientError> {
+    let bids_account = client.rpc.get_account(&market.market_pubkeys.bids)?;
 
-    let bids_account_info = AccountInfo::new(
-        &market.market_pubkeys.bids,
-        false,
-        true,
-        &mut bids_account.lamports,
-        &mut bids_account.data,
-        &mut bids_account.owner,
-        bids_account.executable,
-        bids_account.rent_epoch,
-    );
-
-    let bids = serum_market
-        .load_bids_mut(&bids_account_info)
-        .map_err(|err| Error::ProgramError(ProgramError::from(err).into()))
-        .unwrap();
-
-    for node in bids.traverse().iter() {
-        let order = OptifiOrder {
-            side: OrderSide::Bid,
-            price: u64::from(node.price()) as f64
-                / 10_u32.pow(USDC_DECIMALS - asset.get_decimal()) as f64,
-            size: node.quantity() as f64 / 10_u32.pow(asset.get_decimal()) as f64,
-            client_order_id: node.client_order_id(),
-        };
-        // println!("Bid");
-        // println!("{:#?}", node);
-        println!(
-            "{:?}, {}, {:?}, {}, {}",
-            market.instrument_common.asset,
-            market.strike,
-            market.instrument_type,
-            order.size,
-            order.price
-        );
-    }
+    parse_slab_orders(market, OrderSide::Bid, &bids_account.data)
 }
@@ -0,0 +1,172 @@
+use crate::prelude::*;
+
+/// Most compute units a transaction can request.
+pub const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+
+/// Compute units the runtime grants each instruction without a request.
+pub const DEFAULT_UNITS_PER_INSTRUCTION: u32 = 200_000;
+
+/// Sending operations that can have their own compute budget policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    PlaceOrder,
+    CancelOrder,
+    SettleOrder,
+    /// Deposits, withdrawals, account initialization and delegation.
+    Other,
+}
+
+/// Compute unit limit requested for a transaction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnitLimit {
+    /// No request, each instruction gets the runtime default.
+    Default,
+    Fixed(u32),
+    /// Simulates the transaction first and requests the consumed units plus `margin` (0.2 for
+    /// 20%), capped at `max`.
+    Simulated {
+        margin: f64,
+        max: u32,
+    },
+}
+
+/// Fee paid on top of the signature fees to prioritise a transaction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PriorityFee {
+    None,
+    /// Flat additional fee in lamports.
+    Additional(u32),
+    /// Price per requested compute unit in micro-lamports.
+    PerUnit(u64),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ComputeBudgetPolicy {
+    pub unit_limit: UnitLimit,
+    pub priority_fee: PriorityFee,
+}
+
+impl ComputeBudgetPolicy {
+    pub fn none() -> Self {
+        Self {
+            unit_limit: UnitLimit::Default,
+            priority_fee: PriorityFee::None,
+        }
+    }
+
+    pub fn fixed(units: u32) -> Self {
+        Self {
+            unit_limit: UnitLimit::Fixed(units),
+            priority_fee: PriorityFee::None,
+        }
+    }
+
+    pub fn simulated(margin: f64) -> Self {
+        Self {
+            unit_limit: UnitLimit::Simulated {
+                margin,
+                max: MAX_COMPUTE_UNITS,
+            },
+            priority_fee: PriorityFee::None,
+        }
+    }
+
+    pub fn with_priority_fee(mut self, priority_fee: PriorityFee) -> Self {
+        self.priority_fee = priority_fee;
+        self
+    }
+
+    /// Units to request once a simulation consumed `units_consumed`.
+    pub fn simulated_units(margin: f64, max: u32, units_consumed: u64) -> u32 {
+        let units = (units_consumed as f64 * (1.0 + margin.max(0.0))).ceil();
+
+        if units >= max as f64 {
+            max
+        } else {
+            units as u32
+        }
+    }
+
+    /// The compute budget instruction for a transaction of `instruction_count` instructions,
+    /// `units` being the resolved unit limit, if anything needs to be requested.
+    pub fn instruction(&self, units: Option<u32>, instruction_count: usize) -> Option<Instruction> {
+        if units.is_none() && self.priority_fee == PriorityFee::None {
+            return None;
+        }
+
+        // Requesting units replaces the per instruction default for the whole transaction.
+        let units = units.unwrap_or_else(|| {
+            (DEFAULT_UNITS_PER_INSTRUCTION as u64 * instruction_count as u64)
+                .min(MAX_COMPUTE_UNITS as u64) as u32
+        });
+
+        let additional_fee = match self.priority_fee {
+            PriorityFee::None => 0,
+            PriorityFee::Additional(lamports) => lamports,
+            PriorityFee::PerUnit(micro_lamports) => {
+                let lamports = (units as u128 * micro_lamports as u128 + 999_999) / 1_000_000;
+                lamports.min(u32::MAX as u128) as u32
+            }
+        };
+
+        Some(ComputeBudgetInstruction::request_units(
+            units,
+            additional_fee,
+        ))
+    }
+
+    /// `instructions` behind their compute budget instruction, if anything needs to be requested.
+    pub fn prepend(&self, units: Option<u32>, instructions: Vec<Instruction>) -> Vec<Instruction> {
+        match self.instruction(units, instructions.len()) {
+            Some(budget) => std::iter::once(budget).chain(instructions).collect(),
+            None => instructions,
+        }
+    }
+}
+
+/// `instructions` requesting `max` units, to simulate them for `UnitLimit::Simulated`.
+pub fn simulation_instructions(max: u32, instructions: &[Instruction]) -> Vec<Instruction> {
+    let mut simulated = vec![ComputeBudgetInstruction::request_units(max, 0)];
+    simulated.extend(instructions.iter().cloned());
+    simulated
+}
+
+/// Compute budget policy of each operation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ComputeBudgets {
+    pub place_order: ComputeBudgetPolicy,
+    pub cancel_order: ComputeBudgetPolicy,
+    pub settle_order: ComputeBudgetPolicy,
+    pub other: ComputeBudgetPolicy,
+}
+
+impl Default for ComputeBudgets {
+    fn default() -> Self {
+        Self {
+            place_order: ComputeBudgetPolicy::fixed(MAX_COMPUTE_UNITS),
+            cancel_order: ComputeBudgetPolicy::fixed(MAX_COMPUTE_UNITS),
+            settle_order: ComputeBudgetPolicy::none(),
+            other: ComputeBudgetPolicy::none(),
+        }
+    }
+}
+
+impl ComputeBudgets {
+    pub fn get(&self, operation: Operation) -> &ComputeBudgetPolicy {
+        match operation {
+            Operation::PlaceOrder => &self.place_order,
+            Operation::CancelOrder => &self.cancel_order,
+            Operation::SettleOrder => &self.settle_order,
+            Operation::Other => &self.other,
+        }
+    }
+
+    pub fn set(&mut self, operation: Operation, policy: ComputeBudgetPolicy) {
+        match operation {
+            Operation::PlaceOrder => self.place_order = policy,
+            Operation::CancelOrder => self.cancel_order = policy,
+            Operation::SettleOrder => self.settle_order = policy,
+            Operation::Other => self.other = policy,
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+
+use crate::compute_budget::ComputeBudgets;
+use crate::error::OptifiClientError;
+use crate::network::{Deployment, Network};
+use crate::prelude::*;
+use crate::send::RetryPolicy;
+use crate::validation::OrderValidation;
+
+/// Events consumed by each `ConsumeEventQueue` instruction.
+pub const DEFAULT_CONSUME_EVENTS_LIMIT: u16 = 5;
+
+/// Orders `place_orders` packs into one transaction, bounded by compute rather than size.
+pub const DEFAULT_MAX_ORDERS_PER_TRANSACTION: usize = 5;
+
+/// Deployment addresses and tuning knobs a client runs with.
+#[derive(Clone, Debug)]
+pub struct OptifiConfig {
+    /// Commitment for account reads and subscriptions.
+    pub read_commitment: CommitmentConfig,
+    /// Commitment for sending and confirming transactions.
+    pub send_commitment: CommitmentConfig,
+    pub optifi_exchange: Pubkey,
+    pub optifi_program_id: Pubkey,
+    pub optifi_usdc_program_id: Pubkey,
+    pub serum_dex_program_id: Pubkey,
+    pub usdc_token_mint: Pubkey,
+    pub compute_budgets: ComputeBudgets,
+    pub consume_events_limit: u16,
+    pub max_orders_per_transaction: usize,
+    /// How orders are checked before being sent.
+    pub order_validation: OrderValidation,
+    /// Whether placing the first order on a market also creates the user's accounts on it.
+    pub auto_initialize_markets: bool,
+    /// Directory market metadata is cached in between runs, see `MarketCache`.
+    pub market_cache_dir: Option<PathBuf>,
+    pub retry_policy: RetryPolicy,
+}
+
+impl OptifiConfig {
+    /// Default commitment, compute budget and retry policy for the addresses of `network`.
+    pub fn for_network(network: &Network) -> std::result::Result<Self, OptifiClientError> {
+        Ok(Self::for_deployment(network.deployment()?))
+    }
+
+    /// Default commitment, compute budget and retry policy for `deployment`.
+    pub fn for_deployment(deployment: Deployment) -> Self {
+        Self {
+            read_commitment: CommitmentConfig::processed(),
+            send_commitment: CommitmentConfig::processed(),
+            optifi_exchange: deployment.optifi_exchange,
+            optifi_program_id: deployment.optifi_program_id,
+            optifi_usdc_program_id: deployment.optifi_usdc_program_id,
+            serum_dex_program_id: deployment.serum_dex_program_id,
+            usdc_token_mint: deployment.usdc_token_mint,
+            compute_budgets: ComputeBudgets::default(),
+            consume_events_limit: DEFAULT_CONSUME_EVENTS_LIMIT,
+            max_orders_per_transaction: DEFAULT_MAX_ORDERS_PER_TRANSACTION,
+            order_validation: OrderValidation::Reject,
+            auto_initialize_markets: true,
+            market_cache_dir: None,
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+
+    /// Mint of the optifi wrapped usdc, derived from the optifi usdc program.
+    pub fn optifi_usdc_token_mint(&self) -> Pubkey {
+        Pubkey::find_program_address(&[b"optifi_usdc"], &self.optifi_usdc_program_id).0
+    }
+}
@@ -1,8 +1,11 @@
 pub use crate::client::*;
+use crate::error::OptifiClientError;
 use crate::prelude::*;
 
 impl OptifiClient {
-    pub fn fetch_all_user_accounts(&self) -> Vec<(Pubkey, UserAccount)> {
+    pub fn fetch_all_user_accounts(
+        &self,
+    ) -> std::result::Result<Vec<(Pubkey, UserAccount)>, OptifiClientError> {
         let optifi_exchange_filter = RpcFilterType::Memcmp(Memcmp {
             offset: 8,
             bytes: MemcmpEncodedBytes::Bytes(self.optifi_exchange.to_bytes().to_vec()),
@@ -10,11 +13,10 @@ impl OptifiClient {
         });
 
         let user_accounts = self
-            .program
-            .accounts::<UserAccount>(vec![optifi_exchange_filter])
-            .unwrap();
+            .program()
+            .accounts::<UserAccount>(vec![optifi_exchange_filter])?;
 
-        user_accounts
+        Ok(user_accounts)
     }
 
     pub fn initialize_liquidation(&self) {}
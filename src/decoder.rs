@@ -0,0 +1,345 @@
+//! Serum account decoding straight from account bytes.
+//!
+//! Reads markets, order book slabs and open orders accounts in place, without building an
+//! `AccountInfo` or reloading the serum market for each of them. Account owners are not checked.
+
+use crate::error::OptifiClientError;
+use crate::prelude::*;
+
+const HEAD_PADDING: &[u8; 5] = b"serum";
+const TAIL_PADDING: &[u8; 7] = b"padding";
+
+pub const ACCOUNT_FLAG_INITIALIZED: u64 = 1 << 0;
+pub const ACCOUNT_FLAG_MARKET: u64 = 1 << 1;
+pub const ACCOUNT_FLAG_OPEN_ORDERS: u64 = 1 << 2;
+pub const ACCOUNT_FLAG_BIDS: u64 = 1 << 5;
+pub const ACCOUNT_FLAG_ASKS: u64 = 1 << 6;
+
+/// Size of a serum market account without its paddings, the `MarketStateV2` fields ignored.
+const MARKET_STATE_LEN: usize = 376;
+
+const SLAB_HEADER_LEN: usize = 32;
+const SLAB_NODE_LEN: usize = 72;
+
+const NODE_TAG_INNER: u32 = 1;
+const NODE_TAG_LEAF: u32 = 2;
+
+/// Orders an open orders account holds at most.
+pub const OPEN_ORDERS_SLOTS: usize = 128;
+
+const OPEN_ORDERS_LEN: usize = 3216;
+
+fn error(message: String) -> OptifiClientError {
+    OptifiClientError::SerumSlab(message)
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_u128(bytes: &[u8], offset: usize) -> u128 {
+    u128::from_le_bytes(bytes[offset..offset + 16].try_into().unwrap())
+}
+
+fn read_pubkey(bytes: &[u8], offset: usize) -> Pubkey {
+    Pubkey::new(&bytes[offset..offset + 32])
+}
+
+/// Strips the serum paddings of `data`, checking they are there and that the account flags
+/// include `flag`, and returns the account's bytes after its flags.
+fn account_body<'a>(
+    data: &'a [u8],
+    flag: u64,
+    min_len: usize,
+    name: &str,
+) -> std::result::Result<&'a [u8], OptifiClientError> {
+    let padded = HEAD_PADDING.len() + 8 + TAIL_PADDING.len();
+
+    if data.len() < padded + min_len
+        || !data.starts_with(HEAD_PADDING)
+        || !data.ends_with(TAIL_PADDING)
+    {
+        return Err(error(format!(
+            "invalid {} account of {} bytes",
+            name,
+            data.len()
+        )));
+    }
+
+    let body = &data[HEAD_PADDING.len()..data.len() - TAIL_PADDING.len()];
+
+    let flags = read_u64(body, 0);
+
+    if flags & (ACCOUNT_FLAG_INITIALIZED | flag) != ACCOUNT_FLAG_INITIALIZED | flag {
+        return Err(error(format!(
+            "invalid {} account flags {:#x}",
+            name, flags
+        )));
+    }
+
+    Ok(&body[8..])
+}
+
+/// Fields of a serum market account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MarketState {
+    pub own_address: Pubkey,
+    pub vault_signer_nonce: u64,
+    pub coin_mint: Pubkey,
+    pub pc_mint: Pubkey,
+    pub coin_vault: Pubkey,
+    pub pc_vault: Pubkey,
+    pub req_q: Pubkey,
+    pub event_q: Pubkey,
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
+    pub fee_rate_bps: u64,
+}
+
+impl MarketState {
+    pub fn decode(data: &[u8]) -> std::result::Result<Self, OptifiClientError> {
+        let body = account_body(data, ACCOUNT_FLAG_MARKET, MARKET_STATE_LEN - 8, "market")?;
+
+        Ok(Self {
+            own_address: read_pubkey(body, 0),
+            vault_signer_nonce: read_u64(body, 32),
+            coin_mint: read_pubkey(body, 40),
+            pc_mint: read_pubkey(body, 72),
+            coin_vault: read_pubkey(body, 104),
+            pc_vault: read_pubkey(body, 152),
+            req_q: read_pubkey(body, 208),
+            event_q: read_pubkey(body, 240),
+            bids: read_pubkey(body, 272),
+            asks: read_pubkey(body, 304),
+            coin_lot_size: read_u64(body, 336),
+            pc_lot_size: read_u64(body, 344),
+            fee_rate_bps: read_u64(body, 352),
+        })
+    }
+}
+
+/// Order resting in a slab, in native units.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SlabOrder {
+    /// Serum order id: the price in the high 64 bits, the sequence number in the low ones.
+    pub key: u128,
+    /// Slot of the order in its owner's open orders account.
+    pub owner_slot: u8,
+    /// Open orders account of the order.
+    pub owner: Pubkey,
+    pub quantity: u64,
+    pub client_order_id: u64,
+}
+
+impl SlabOrder {
+    pub fn price(&self) -> u64 {
+        (self.key >> 64) as u64
+    }
+}
+
+/// Bids or asks of a serum market, read in place.
+#[derive(Clone, Copy, Debug)]
+pub struct OrderSlab<'a> {
+    nodes: &'a [u8],
+    root: u32,
+    leaf_count: u64,
+}
+
+impl<'a> OrderSlab<'a> {
+    /// Reads the `side` slab of a serum market from its account bytes.
+    pub fn decode(data: &'a [u8], side: OrderSide) -> std::result::Result<Self, OptifiClientError> {
+        let (flag, name) = match side {
+            OrderSide::Bid => (ACCOUNT_FLAG_BIDS, "bids"),
+            OrderSide::Ask => (ACCOUNT_FLAG_ASKS, "asks"),
+        };
+
+        let body = account_body(data, flag, SLAB_HEADER_LEN, name)?;
+
+        let nodes = &body[SLAB_HEADER_LEN..];
+        let nodes = &nodes[..nodes.len() - nodes.len() % SLAB_NODE_LEN];
+
+        Ok(Self {
+            nodes,
+            root: read_u32(body, 20),
+            leaf_count: read_u64(body, 24),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaf_count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaf_count == 0
+    }
+
+    fn node(&self, handle: u32) -> std::result::Result<&'a [u8], OptifiClientError> {
+        let offset = handle as usize * SLAB_NODE_LEN;
+
+        self.nodes
+            .get(offset..offset + SLAB_NODE_LEN)
+            .ok_or_else(|| error(format!("slab node {} out of bounds", handle)))
+    }
+
+    fn leaf(node: &[u8]) -> SlabOrder {
+        SlabOrder {
+            key: read_u128(node, 8),
+            owner_slot: node[4],
+            owner: read_pubkey(node, 24),
+            quantity: read_u64(node, 56),
+            client_order_id: read_u64(node, 64),
+        }
+    }
+
+    /// Orders by ascending key, so by ascending price.
+    pub fn orders(&self) -> std::result::Result<Vec<SlabOrder>, OptifiClientError> {
+        let mut orders = Vec::with_capacity(self.len());
+
+        if self.is_empty() {
+            return Ok(orders);
+        }
+
+        let mut stack = vec![self.root];
+
+        while let Some(handle) = stack.pop() {
+            let node = self.node(handle)?;
+
+            match read_u32(node, 0) {
+                NODE_TAG_LEAF => orders.push(Self::leaf(node)),
+                // Right child first, so the left one pops first.
+                NODE_TAG_INNER => stack.extend([read_u32(node, 28), read_u32(node, 24)]),
+                tag => return Err(error(format!("slab node {} has tag {}", handle, tag))),
+            }
+
+            if orders.len() + stack.len() > self.len() {
+                return Err(error("slab has more nodes than leaves".to_owned()));
+            }
+        }
+
+        Ok(orders)
+    }
+
+    /// Order with serum order id `key`, walking down the critbit tree.
+    pub fn find(&self, key: u128) -> std::result::Result<Option<SlabOrder>, OptifiClientError> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+
+        let mut handle = self.root;
+
+        // A critbit tree is at most 128 inner nodes deep.
+        for _ in 0..=128 {
+            let node = self.node(handle)?;
+
+            match read_u32(node, 0) {
+                NODE_TAG_LEAF => {
+                    let leaf = Self::leaf(node);
+
+                    return Ok(if leaf.key == key { Some(leaf) } else { None });
+                }
+                NODE_TAG_INNER => {
+                    let prefix_len = read_u32(node, 4);
+                    let node_key = read_u128(node, 8);
+
+                    if prefix_len >= 128 || (key ^ node_key).leading_zeros() < prefix_len {
+                        return Ok(None);
+                    }
+
+                    let crit_bit = key & ((1u128 << 127) >> prefix_len) != 0;
+
+                    handle = read_u32(node, 24 + 4 * usize::from(crit_bit));
+                }
+                tag => return Err(error(format!("slab node {} has tag {}", handle, tag))),
+            }
+        }
+
+        Err(error("slab is deeper than a critbit tree".to_owned()))
+    }
+}
+
+/// Order of an open orders account.
+#[derive(Clone, Copy, Debug)]
+pub struct OpenOrder {
+    pub slot: u8,
+    pub side: OrderSide,
+    /// Serum order id, the key of the order in its slab.
+    pub order_id: u128,
+    pub client_order_id: u64,
+}
+
+/// Serum open orders account, read in place.
+#[derive(Clone, Copy, Debug)]
+pub struct OpenOrdersView<'a> {
+    body: &'a [u8],
+}
+
+impl<'a> OpenOrdersView<'a> {
+    pub fn decode(data: &'a [u8]) -> std::result::Result<Self, OptifiClientError> {
+        Ok(Self {
+            body: account_body(
+                data,
+                ACCOUNT_FLAG_OPEN_ORDERS,
+                OPEN_ORDERS_LEN - 8,
+                "open orders",
+            )?,
+        })
+    }
+
+    pub fn market(&self) -> Pubkey {
+        read_pubkey(self.body, 0)
+    }
+
+    pub fn owner(&self) -> Pubkey {
+        read_pubkey(self.body, 32)
+    }
+
+    pub fn native_coin_free(&self) -> u64 {
+        read_u64(self.body, 64)
+    }
+
+    pub fn native_coin_total(&self) -> u64 {
+        read_u64(self.body, 72)
+    }
+
+    pub fn native_pc_free(&self) -> u64 {
+        read_u64(self.body, 80)
+    }
+
+    pub fn native_pc_total(&self) -> u64 {
+        read_u64(self.body, 88)
+    }
+
+    pub fn free_slot_bits(&self) -> u128 {
+        read_u128(self.body, 96)
+    }
+
+    pub fn is_bid_bits(&self) -> u128 {
+        read_u128(self.body, 112)
+    }
+
+    /// Orders in the occupied slots, by slot.
+    pub fn orders(&self) -> Vec<OpenOrder> {
+        let free_slot_bits = self.free_slot_bits();
+        let is_bid_bits = self.is_bid_bits();
+
+        (0..OPEN_ORDERS_SLOTS)
+            .filter(|slot| free_slot_bits & (1u128 << slot) == 0)
+            .map(|slot| OpenOrder {
+                slot: slot as u8,
+                side: if is_bid_bits & (1u128 << slot) != 0 {
+                    OrderSide::Bid
+                } else {
+                    OrderSide::Ask
+                },
+                order_id: read_u128(self.body, 128 + 16 * slot),
+                client_order_id: read_u64(self.body, 2176 + 8 * slot),
+            })
+            .collect()
+    }
+}
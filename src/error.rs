@@ -0,0 +1,105 @@
+use crate::prelude::*;
+use crate::program_error::ProgramFailure;
+use crate::send::OutcomeStatus;
+use crate::validation::OrderRejection;
+
+use serum_dex::error::DexError;
+use solana_client::pubsub_client::PubsubClientError;
+use solana_sdk::signer::SignerError;
+use solana_sdk::transaction::TransactionError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum OptifiClientError {
+    #[error("rpc error: {0}")]
+    Rpc(#[from] solana_client::client_error::ClientError),
+
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("pubsub error: {0}")]
+    Pubsub(#[from] PubsubClientError),
+
+    #[error("anchor client error: {0}")]
+    Client(#[from] ClientError),
+
+    #[error("account {0} not found")]
+    AccountNotFound(Pubkey),
+
+    #[error("deserialization error: {0}")]
+    Deserialization(String),
+
+    #[error("serum slab error: {0}")]
+    SerumSlab(String),
+
+    #[error("conversion error: {0}")]
+    Conversion(String),
+
+    #[error("market {0} not found")]
+    MarketNotFound(Pubkey),
+
+    #[error("no market with symbol {0}")]
+    SymbolNotFound(String),
+
+    #[error("{0} not initialized")]
+    NotInitialized(&'static str),
+
+    #[error("keypair error: {0}")]
+    Keypair(String),
+
+    #[error("signer error: {0}")]
+    Signer(#[from] SignerError),
+
+    #[error("{0}")]
+    Program(ProgramFailure),
+
+    #[error("order rejected: {0}")]
+    OrderRejected(#[from] OrderRejection),
+
+    #[error("transaction {signature} {status:?}: {error:?}")]
+    Transaction {
+        signature: Signature,
+        status: OutcomeStatus,
+        error: Option<TransactionError>,
+    },
+
+    /// Sending or confirming a signed transaction failed, it may still land.
+    #[error("transaction {signature} may have been sent: {source}")]
+    Unconfirmed {
+        signature: Signature,
+        source: Box<OptifiClientError>,
+    },
+}
+
+impl OptifiClientError {
+    /// Signature of the transaction the error is about, to confirm or deduplicate it by.
+    pub fn signature(&self) -> Option<Signature> {
+        match self {
+            OptifiClientError::Transaction { signature, .. }
+            | OptifiClientError::Unconfirmed { signature, .. } => Some(*signature),
+            _ => None,
+        }
+    }
+}
+
+impl From<DexError> for OptifiClientError {
+    fn from(err: DexError) -> Self {
+        OptifiClientError::SerumSlab(format!("{:?}", err))
+    }
+}
+
+impl From<anchor_lang::error::Error> for OptifiClientError {
+    fn from(err: anchor_lang::error::Error) -> Self {
+        OptifiClientError::Deserialization(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for OptifiClientError {
+    fn from(err: serde_json::Error) -> Self {
+        OptifiClientError::Deserialization(err.to_string())
+    }
+}
+
+pub fn parse_pubkey(pubkey: &str) -> std::result::Result<Pubkey, OptifiClientError> {
+    Pubkey::from_str(pubkey)
+        .map_err(|err| OptifiClientError::Conversion(format!("invalid pubkey {}: {}", pubkey, err)))
+}
@@ -0,0 +1,637 @@
+use anchor_lang::{InstructionData, ToAccountMetas};
+use solana_sdk::message::Message;
+
+use crate::client::{get_order_native_amounts, Market, OptifiAccount};
+use crate::config::OptifiConfig;
+use crate::error::OptifiClientError;
+use crate::market_setup::MarketSetup;
+use crate::orders::OrderRequest;
+use crate::prelude::*;
+use crate::types::{Price, Quantity};
+
+/// Builds optifi instructions from cached accounts without touching the network.
+///
+/// Shared by `OptifiClient` and `AsyncOptifiClient`; the `*_instructions` methods on both
+/// clients go through it. None of the builders add a compute budget instruction so several of
+/// them can be combined into one transaction.
+pub struct InstructionBuilder<'a> {
+    pub config: &'a OptifiConfig,
+    pub account: &'a OptifiAccount,
+    pub payer: Pubkey,
+    pub owner: Pubkey,
+    pub user_account: Pubkey,
+}
+
+impl<'a> InstructionBuilder<'a> {
+    fn optifi_instruction(
+        &self,
+        accounts: impl ToAccountMetas,
+        args: impl InstructionData,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.config.optifi_program_id,
+            accounts: accounts.to_account_metas(None),
+            data: args.data(),
+        }
+    }
+
+    fn open_orders(&self, market: &Market) -> Pubkey {
+        get_serum_open_orders_account(
+            &self.config.optifi_exchange,
+            &self.user_account,
+            &market.optifi_market.serum_market,
+            &self.config.optifi_program_id,
+        )
+        .0
+    }
+
+    fn fee_account(&self) -> Pubkey {
+        get_user_fee_account_pda(
+            &self.config.optifi_exchange,
+            &self.user_account,
+            &self.config.optifi_program_id,
+        )
+        .0
+    }
+
+    fn margin_stress_account(&self, asset: Asset) -> Pubkey {
+        get_margin_stress_account(
+            &self.config.optifi_exchange,
+            asset as u8,
+            &self.config.optifi_program_id,
+        )
+        .0
+    }
+
+    pub fn margin_stress_calculate(
+        &self,
+        asset: Asset,
+    ) -> std::result::Result<Instruction, OptifiClientError> {
+        let exchange = self.account.get_optifi_exchange()?;
+
+        let asset_feed = exchange
+            .get_oracle(asset)
+            .spot_oracle
+            .ok_or(OptifiClientError::NotInitialized("asset spot oracle"))?;
+
+        let usdc_feed = exchange
+            .get_oracle(Asset::USDC)
+            .spot_oracle
+            .ok_or(OptifiClientError::NotInitialized("usdc spot oracle"))?;
+
+        Ok(self.optifi_instruction(
+            optifi_cpi::accounts::CalculateMarginStressContext {
+                optifi_exchange: self.config.optifi_exchange,
+                margin_stress_account: self.margin_stress_account(asset),
+                asset_feed,
+                usdc_feed,
+            },
+            optifi_cpi::instruction::MarginStressCalculate {},
+        ))
+    }
+
+    /// Consume events, settle funds and recalculate margin for `market`.
+    pub fn settlement(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        let user_account = self.user_account;
+
+        let serum_dex_program_id = self.config.serum_dex_program_id;
+
+        let serum_market = market.optifi_market.serum_market;
+
+        let open_orders = self.open_orders(market);
+
+        let (serum_market_authority, ..) =
+            get_serum_market_auth_pda(&self.config.optifi_exchange, &self.config.optifi_program_id);
+
+        let serum_market_pubkeys: &MarketPubkeys = &market.market_pubkeys;
+
+        let consume_ix = self.optifi_instruction(
+            optifi_cpi::accounts::ConsumeEventQueue {
+                optifi_exchange: self.config.optifi_exchange,
+                serum_market,
+                event_queue: *serum_market_pubkeys.event_q,
+                user_serum_open_orders: open_orders,
+                consume_events_authority: serum_market_authority,
+                serum_dex_program_id,
+            },
+            optifi_cpi::instruction::ConsumeEventQueue {
+                limit: Some(self.config.consume_events_limit),
+            },
+        );
+
+        let settle_ix = self.optifi_instruction(
+            optifi_cpi::accounts::OrderSettlement {
+                optifi_exchange: self.config.optifi_exchange,
+
+                user_account,
+                user_margin_account: self.account.get_user_account()?.user_margin_account_usdc,
+
+                optifi_market: market.optifi_market_key_data.optifi_market_pubkey,
+                serum_market,
+                user_serum_open_orders: open_orders,
+                fee_account: self.fee_account(),
+
+                pc_vault: *serum_market_pubkeys.pc_vault,
+                coin_vault: *serum_market_pubkeys.coin_vault,
+                vault_signer: *serum_market_pubkeys.vault_signer_key,
+
+                instrument_long_spl_token_mint: market.optifi_market.instrument_long_spl_token,
+                instrument_short_spl_token_mint: market.optifi_market.instrument_short_spl_token,
+                user_instrument_long_token_vault: get_associated_token_address(
+                    &user_account,
+                    &market.optifi_market.instrument_long_spl_token,
+                ),
+                user_instrument_short_token_vault: get_associated_token_address(
+                    &user_account,
+                    &market.optifi_market.instrument_short_spl_token,
+                ),
+
+                serum_dex_program_id,
+                token_program: spl_token::id(),
+            },
+            optifi_cpi::instruction::SettleOrderFunds {},
+        );
+
+        let margin_ix = self.optifi_instruction(
+            optifi_cpi::accounts::MarginContext {
+                optifi_exchange: self.config.optifi_exchange,
+                user_account,
+                margin_stress_account: self.margin_stress_account(market.instrument_common.asset),
+            },
+            optifi_cpi::instruction::UserMarginCalculate {},
+        );
+
+        Ok(vec![consume_ix, settle_ix, margin_ix])
+    }
+
+    /// Creates the owner's user account, with `user_margin_account_usdc` as a fresh token account
+    /// funded with `rent_lamports`, and its fee account.
+    pub fn initialize_user_account(
+        &self,
+        user_margin_account_usdc: Pubkey,
+        rent_lamports: u64,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        let (user_account, user_account_bump) = get_user_account_pda(
+            &self.config.optifi_exchange,
+            &self.owner,
+            &self.config.optifi_program_id,
+        );
+
+        let (liquidation_account, liquidation_account_bump) = get_user_liquidation_account_pda(
+            &self.config.optifi_exchange,
+            &user_account,
+            &self.config.optifi_program_id,
+        );
+
+        let init_ix = self.optifi_instruction(
+            optifi_cpi::accounts::InitializeUserAccount {
+                optifi_exchange: self.config.optifi_exchange,
+                user_account,
+                user_margin_account_usdc,
+                owner: self.owner,
+                payer: self.payer,
+                token_program: spl_token::id(),
+                system_program: solana_program::system_program::id(),
+                rent: solana_program::sysvar::rent::id(),
+                liquidation_account,
+            },
+            optifi_cpi::instruction::InitUserAccount {
+                bump: InitUserAccountBumpSeeds {
+                    user_account: user_account_bump,
+                    liquidation_account: liquidation_account_bump,
+                },
+            },
+        );
+
+        Ok(vec![
+            system_instruction::create_account(
+                &self.payer,
+                &user_margin_account_usdc,
+                rent_lamports,
+                spl_token::state::Account::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_account(
+                &spl_token::id(),
+                &user_margin_account_usdc,
+                &self.config.optifi_usdc_token_mint(),
+                &self.owner,
+            )
+            .map_err(|err| OptifiClientError::Client(ClientError::ProgramError(err)))?,
+            init_ix,
+            self.initialize_fee_account_for(user_account),
+        ])
+    }
+
+    fn initialize_fee_account_for(&self, user_account: Pubkey) -> Instruction {
+        let (fee_account, ..) = get_user_fee_account_pda(
+            &self.config.optifi_exchange,
+            &user_account,
+            &self.config.optifi_program_id,
+        );
+
+        self.optifi_instruction(
+            optifi_cpi::accounts::InitializeFeeAccount {
+                optifi_exchange: self.config.optifi_exchange,
+                user_account,
+                payer: self.payer,
+                fee_account,
+                system_program: solana_program::system_program::id(),
+            },
+            optifi_cpi::instruction::InitializeFeeAccount {},
+        )
+    }
+
+    /// Creates the fee account of the owner's user account.
+    pub fn initialize_fee_account(&self) -> Vec<Instruction> {
+        let (user_account, ..) = get_user_account_pda(
+            &self.config.optifi_exchange,
+            &self.owner,
+            &self.config.optifi_program_id,
+        );
+
+        vec![self.initialize_fee_account_for(user_account)]
+    }
+
+    pub fn set_delegation(&self, delegatee: Option<Pubkey>) -> Vec<Instruction> {
+        vec![self.optifi_instruction(
+            optifi_cpi::accounts::SetDelegation {
+                optifi_exchange: self.config.optifi_exchange,
+                user_account: self.user_account,
+                user: self.owner,
+            },
+            optifi_cpi::instruction::SetDelegation { delegatee },
+        )]
+    }
+
+    /// Wraps `amount` native usdc of the owner into optifi usdc and deposits it.
+    pub fn deposit(&self, amount: u64, user_margin_account_usdc: Pubkey) -> Vec<Instruction> {
+        let user = self.owner;
+
+        let usdc_token_mint = self.config.usdc_token_mint;
+
+        let optifi_usdc_token_mint = self.config.optifi_usdc_token_mint();
+
+        let (authority, ..) =
+            Pubkey::find_program_address(&[b"authority"], &self.config.optifi_usdc_program_id);
+
+        let owner_optifi_usdc = get_associated_token_address(&user, &optifi_usdc_token_mint);
+
+        let wrap_ix = Instruction {
+            program_id: self.config.optifi_usdc_program_id,
+            accounts: optifi_usdc_cpi::accounts::Wrap {
+                authority,
+                optifi_usdc: optifi_usdc_token_mint,
+                usdc_vault: get_associated_token_address(&authority, &usdc_token_mint),
+                usdc_mint: usdc_token_mint,
+                owner_usdc: get_associated_token_address(&user, &usdc_token_mint),
+                owner_optifi_usdc,
+                owner: user,
+                token_program: spl_token::id(),
+                associated_token_program: anchor_spl::associated_token::AssociatedToken::id(),
+                system_program: solana_program::system_program::id(),
+                rent: solana_program::sysvar::rent::id(),
+            }
+            .to_account_metas(None),
+            data: optifi_usdc_cpi::instruction::Wrap { _amount: amount }.data(),
+        };
+
+        let deposit_ix = self.optifi_instruction(
+            optifi_cpi::accounts::Deposit {
+                optifi_exchange: self.config.optifi_exchange,
+                user_account: self.user_account,
+                user_margin_account_usdc,
+                user,
+                deposit_source: owner_optifi_usdc,
+                token_program: spl_token::id(),
+            },
+            optifi_cpi::instruction::Deposit { amount },
+        );
+
+        vec![wrap_ix, deposit_ix]
+    }
+
+    /// Withdraws `amount` native usdc to the owner's usdc token account.
+    pub fn withdraw(&self, amount: u64, user_margin_account_usdc: Pubkey) -> Vec<Instruction> {
+        vec![self.optifi_instruction(
+            optifi_cpi::accounts::Withdraw {
+                optifi_exchange: self.config.optifi_exchange,
+                user_account: self.user_account,
+                user_margin_account_usdc,
+                user: self.owner,
+                withdraw_dest: get_associated_token_address(
+                    &self.owner,
+                    &self.config.usdc_token_mint,
+                ),
+                token_program: spl_token::id(),
+            },
+            optifi_cpi::instruction::Withdraw { amount },
+        )]
+    }
+
+    /// Creates the instrument token vaults and serum open orders of the user on `market`.
+    pub fn initialize_user_on_market(&self, market: &Market) -> Vec<Instruction> {
+        self.complete_market_setup(market, MarketSetup::default())
+    }
+
+    /// Creates the pieces of `setup` that are missing, instrument token accounts first.
+    pub fn complete_market_setup(&self, market: &Market, setup: MarketSetup) -> Vec<Instruction> {
+        let user_account = self.user_account;
+
+        let serum_market = market.optifi_market.serum_market;
+
+        let (serum_market_authority, ..) =
+            get_serum_market_auth_pda(&self.config.optifi_exchange, &self.config.optifi_program_id);
+
+        let (serum_open_orders, bump) = get_serum_open_orders_account(
+            &self.config.optifi_exchange,
+            &user_account,
+            &serum_market,
+            &self.config.optifi_program_id,
+        );
+
+        let mut instructions = vec![];
+
+        if !setup.long_token_account {
+            instructions.push(create_associated_token_account(
+                &self.payer,
+                &user_account,
+                &market.optifi_market.instrument_long_spl_token,
+            ));
+        }
+
+        if !setup.short_token_account {
+            instructions.push(create_associated_token_account(
+                &self.payer,
+                &user_account,
+                &market.optifi_market.instrument_short_spl_token,
+            ));
+        }
+
+        if !setup.open_orders {
+            instructions.push(self.optifi_instruction(
+                optifi_cpi::accounts::InitUserOnOptifiMarket {
+                    optifi_exchange: self.config.optifi_exchange,
+                    user: self.owner,
+                    user_account,
+
+                    optifi_market: market.optifi_market_key_data.optifi_market_pubkey,
+                    serum_market,
+                    serum_open_orders,
+
+                    serum_dex_program_id: self.config.serum_dex_program_id,
+                    serum_market_authority,
+
+                    payer: self.payer,
+                    system_program: solana_program::system_program::id(),
+                    rent: solana_program::sysvar::rent::id(),
+                },
+                optifi_cpi::instruction::InitUserOnOptifiMarket { bump },
+            ));
+        }
+
+        instructions
+    }
+
+    /// Margin stress, place order, then settlement of `market`.
+    pub fn place_order(
+        &self,
+        market: &Market,
+        side: OrderSide,
+        price: Price,
+        size: Quantity,
+        order_type: OrderType,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        self.place_orders(
+            market,
+            &[OrderRequest {
+                side,
+                price,
+                size,
+                order_type,
+            }],
+        )
+    }
+
+    /// Margin stress, one place order per request, then settlement of `market`.
+    pub fn place_orders(
+        &self,
+        market: &Market,
+        orders: &[OrderRequest],
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        let mut instructions = vec![self.margin_stress_calculate(market.instrument_common.asset)?];
+
+        for order in orders {
+            instructions.push(self.place_order_instruction(
+                market,
+                order.side,
+                order.price,
+                order.size,
+                order.order_type,
+            )?);
+        }
+
+        instructions.extend(self.settlement(market)?);
+
+        Ok(instructions)
+    }
+
+    /// The `PlaceOrder` instruction alone.
+    pub fn place_order_instruction(
+        &self,
+        market: &Market,
+        side: OrderSide,
+        price: Price,
+        size: Quantity,
+        order_type: OrderType,
+    ) -> std::result::Result<Instruction, OptifiClientError> {
+        let user_account = self.user_account;
+
+        let serum_market_pubkeys: &MarketPubkeys = &market.market_pubkeys;
+
+        let asset = market.instrument_common.asset;
+
+        let (instrument_token_mint_authority_pda, ..) = get_optifi_market_mint_auth_pda(
+            &self.config.optifi_exchange,
+            &self.config.optifi_program_id,
+        );
+
+        let (limit, max_coin_qty, max_pc_qty) = get_order_native_amounts(asset, price, size)?;
+
+        Ok(self.optifi_instruction(
+            optifi_cpi::accounts::PlaceOrderContext {
+                optifi_exchange: self.config.optifi_exchange,
+
+                user: self.owner,
+                user_account,
+                user_margin_account: self.account.get_user_account()?.user_margin_account_usdc,
+                fee_account: self.fee_account(),
+
+                optifi_market: market.optifi_market_key_data.optifi_market_pubkey,
+                serum_market: market.optifi_market.serum_market,
+                open_orders: self.open_orders(market),
+
+                asks: *serum_market_pubkeys.asks,
+                bids: *serum_market_pubkeys.bids,
+                pc_vault: *serum_market_pubkeys.pc_vault,
+                coin_vault: *serum_market_pubkeys.coin_vault,
+                request_queue: *serum_market_pubkeys.req_q,
+                event_queue: *serum_market_pubkeys.event_q,
+
+                coin_mint: market.optifi_market.instrument_long_spl_token,
+                instrument_short_spl_token_mint: market.optifi_market.instrument_short_spl_token,
+                instrument_token_mint_authority_pda,
+                user_instrument_long_token_vault: get_associated_token_address(
+                    &user_account,
+                    &market.optifi_market.instrument_long_spl_token,
+                ),
+                user_instrument_short_token_vault: get_associated_token_address(
+                    &user_account,
+                    &market.optifi_market.instrument_short_spl_token,
+                ),
+
+                usdc_fee_pool: self.account.get_optifi_exchange()?.usdc_fee_pool,
+
+                margin_stress_account: self.margin_stress_account(asset),
+
+                serum_dex_program_id: self.config.serum_dex_program_id,
+                token_program: spl_token::id(),
+                rent: solana_program::sysvar::rent::id(),
+            },
+            optifi_cpi::instruction::PlaceOrder {
+                side,
+                limit,
+                max_coin_qty,
+                max_pc_qty,
+                order_type: order_type as u8,
+            },
+        ))
+    }
+
+    /// Margin stress, cancel order by client order id, then settlement of `market`.
+    pub fn cancel_order(
+        &self,
+        market: &Market,
+        side: OrderSide,
+        client_order_id: u64,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        self.cancel_orders(market, &[(side, client_order_id)])
+    }
+
+    /// Margin stress, one cancel per `(side, client_order_id)`, then settlement of `market`.
+    pub fn cancel_orders(
+        &self,
+        market: &Market,
+        orders: &[(OrderSide, u64)],
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        let mut instructions = vec![self.margin_stress_calculate(market.instrument_common.asset)?];
+
+        for (side, client_order_id) in orders {
+            instructions.push(self.cancel_order_instruction(market, *side, *client_order_id)?);
+        }
+
+        instructions.extend(self.settlement(market)?);
+
+        Ok(instructions)
+    }
+
+    /// Margin stress, cancel of `old_client_order_id`, place of a limit order on the same
+    /// side, then settlement of `market`.
+    pub fn replace_order(
+        &self,
+        market: &Market,
+        side: OrderSide,
+        old_client_order_id: u64,
+        new_price: Price,
+        new_size: Quantity,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        let mut instructions = vec![
+            self.margin_stress_calculate(market.instrument_common.asset)?,
+            self.cancel_order_instruction(market, side, old_client_order_id)?,
+            self.place_order_instruction(market, side, new_price, new_size, OrderType::Limit)?,
+        ];
+
+        instructions.extend(self.settlement(market)?);
+
+        Ok(instructions)
+    }
+
+    /// The `CancelOrderByClientOrderId` instruction alone.
+    pub fn cancel_order_instruction(
+        &self,
+        market: &Market,
+        side: OrderSide,
+        client_order_id: u64,
+    ) -> std::result::Result<Instruction, OptifiClientError> {
+        let serum_market_pubkeys: &MarketPubkeys = &market.market_pubkeys;
+
+        let asset = market.instrument_common.asset;
+
+        let (central_usdc_pool_auth, ..) = get_central_usdc_pool_auth_pda(
+            &self.config.optifi_exchange,
+            &self.config.optifi_program_id,
+        );
+
+        Ok(self.optifi_instruction(
+            optifi_cpi::accounts::CancelOrderContext {
+                optifi_exchange: self.config.optifi_exchange,
+
+                user: self.owner,
+                user_account: self.user_account,
+                user_margin_account: self.account.get_user_account()?.user_margin_account_usdc,
+                fee_account: self.fee_account(),
+
+                serum_market: market.optifi_market.serum_market,
+                open_orders: self.open_orders(market),
+
+                asks: *serum_market_pubkeys.asks,
+                bids: *serum_market_pubkeys.bids,
+                event_queue: *serum_market_pubkeys.event_q,
+
+                usdc_fee_pool: self.account.get_optifi_exchange()?.usdc_fee_pool,
+                central_usdc_pool_auth,
+
+                margin_stress_account: self.margin_stress_account(asset),
+
+                serum_dex_program_id: self.config.serum_dex_program_id,
+                token_program: spl_token::id(),
+            },
+            optifi_cpi::instruction::CancelOrderByClientOrderId {
+                side,
+                client_order_id,
+            },
+        ))
+    }
+
+    /// Margin stress, then settlement of `market`.
+    pub fn settle_order(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        let mut instructions = vec![self.margin_stress_calculate(market.instrument_common.asset)?];
+        instructions.extend(self.settlement(market)?);
+
+        Ok(instructions)
+    }
+}
+
+/// Size in bytes of a transaction made of `instructions`, paid by `payer`, once signed.
+pub fn transaction_size(instructions: &[Instruction], payer: &Pubkey) -> usize {
+    let message = Message::new(instructions, Some(payer));
+    let signatures = message.header.num_required_signatures as usize;
+
+    // Compact length prefix of the signatures, one byte below 128 signatures.
+    1 + signatures * 64 + message.serialize().len()
+}
+
+/// Whether any of `instructions` needs `signer` to sign.
+pub fn requires_signature(instructions: &[Instruction], signer: &Pubkey) -> bool {
+    instructions.iter().any(|ix| {
+        ix.accounts
+            .iter()
+            .any(|meta| meta.is_signer && &meta.pubkey == signer)
+    })
+}
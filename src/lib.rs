@@ -1,5 +1,27 @@
+pub mod async_client;
+pub mod builder;
 pub mod client;
+pub mod compute_budget;
+pub mod config;
 pub mod cranker;
+pub mod decoder;
+pub mod error;
+pub mod instructions;
+pub mod market_cache;
+pub mod market_diff;
+pub mod market_loader;
+pub mod market_setup;
+pub mod network;
+pub mod nonblocking;
+pub mod option_chain;
+pub mod orders;
+pub mod program_error;
+pub mod send;
+pub mod signer;
+pub mod simulation;
+pub mod symbol;
+pub mod types;
+pub mod validation;
 
 pub mod prelude {
     pub use anchor_client::solana_client::rpc_request::RpcRequest;
@@ -0,0 +1,311 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use solana_sdk::bs58;
+
+use crate::client::{get_market_pubkeys, Market, OptifiClient};
+use crate::error::{parse_pubkey, OptifiClientError};
+use crate::market_loader::{complete_markets, pending_markets, MarketLoadFailure, PendingMarket};
+use crate::prelude::*;
+
+/// Format version of market cache files; files of other versions are ignored.
+pub const MARKET_CACHE_VERSION: u64 = 1;
+
+fn invalid(field: &str) -> OptifiClientError {
+    OptifiClientError::Deserialization(format!("invalid market cache field {}", field))
+}
+
+fn encode<T: AnchorSerialize>(value: &T) -> std::result::Result<String, OptifiClientError> {
+    Ok(bs58::encode(value.try_to_vec()?).into_string())
+}
+
+fn decode<T: AnchorDeserialize>(
+    value: &Value,
+    field: &str,
+) -> std::result::Result<T, OptifiClientError> {
+    let bytes = bs58::decode(value[field].as_str().ok_or_else(|| invalid(field))?)
+        .into_vec()
+        .map_err(|_| invalid(field))?;
+
+    T::try_from_slice(&bytes).map_err(|_| invalid(field))
+}
+
+fn decode_u64(value: &Value, field: &str) -> std::result::Result<u64, OptifiClientError> {
+    value[field].as_u64().ok_or_else(|| invalid(field))
+}
+
+fn decode_pubkey(value: &Value, field: &str) -> std::result::Result<Pubkey, OptifiClientError> {
+    parse_pubkey(value[field].as_str().ok_or_else(|| invalid(field))?)
+}
+
+fn market_to_json(market: &Market) -> std::result::Result<Value, OptifiClientError> {
+    let serum_account = &market.serum_account;
+
+    Ok(json!({
+        "optifi_market_key_data": encode(&market.optifi_market_key_data)?,
+        "optifi_market": encode(&market.optifi_market)?,
+        "instrument_common": encode(&market.instrument_common)?,
+        "strike": market.strike,
+        "instrument_type": encode(&market.instrument_type)?,
+        "serum_account": {
+            "lamports": serum_account.lamports,
+            "data": bs58::encode(&serum_account.data).into_string(),
+            "owner": serum_account.owner.to_string(),
+            "executable": serum_account.executable,
+            "rent_epoch": serum_account.rent_epoch,
+        },
+    }))
+}
+
+fn market_from_json(value: &Value) -> std::result::Result<Market, OptifiClientError> {
+    let optifi_market: OptifiMarket = decode(value, "optifi_market")?;
+
+    let account = &value["serum_account"];
+
+    let serum_account = solana_sdk::account::Account {
+        lamports: decode_u64(account, "lamports")?,
+        data: bs58::decode(account["data"].as_str().ok_or_else(|| invalid("data"))?)
+            .into_vec()
+            .map_err(|_| invalid("data"))?,
+        owner: decode_pubkey(account, "owner")?,
+        executable: account["executable"]
+            .as_bool()
+            .ok_or_else(|| invalid("executable"))?,
+        rent_epoch: decode_u64(account, "rent_epoch")?,
+    };
+
+    let market_pubkeys = get_market_pubkeys(&optifi_market.serum_market, &serum_account)?;
+
+    Ok(Market {
+        optifi_market_key_data: decode(value, "optifi_market_key_data")?,
+        optifi_market,
+        instrument_common: decode(value, "instrument_common")?,
+        strike: value["strike"]
+            .as_u64()
+            .and_then(|strike| u32::try_from(strike).ok())
+            .ok_or_else(|| invalid("strike"))?,
+        instrument_type: decode(value, "instrument_type")?,
+        market_pubkeys,
+        serum_account,
+    })
+}
+
+/// Market metadata of one exchange persisted between runs: instrument data, optifi and serum
+/// market accounts. Serum pubkeys are derived again when reading it.
+#[derive(Clone, Debug)]
+pub struct MarketCache {
+    pub optifi_exchange: Pubkey,
+    pub markets: Vec<Market>,
+}
+
+impl MarketCache {
+    /// Cache file of `optifi_exchange` in `dir`.
+    pub fn path(dir: &Path, optifi_exchange: &Pubkey) -> PathBuf {
+        dir.join(format!("markets-{}.json", optifi_exchange))
+    }
+
+    /// Reads the cache at `path`, `None` if there is none or it has another version or exchange.
+    pub fn read(
+        path: &Path,
+        optifi_exchange: &Pubkey,
+    ) -> std::result::Result<Option<Self>, OptifiClientError> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let value: Value = serde_json::from_str(&contents)?;
+
+        if value["version"].as_u64() != Some(MARKET_CACHE_VERSION)
+            || decode_pubkey(&value, "optifi_exchange")? != *optifi_exchange
+        {
+            return Ok(None);
+        }
+
+        let markets = value["markets"]
+            .as_array()
+            .ok_or_else(|| invalid("markets"))?
+            .iter()
+            .map(market_from_json)
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(Some(Self {
+            optifi_exchange: *optifi_exchange,
+            markets,
+        }))
+    }
+
+    /// Writes the cache to `path`, replacing the previous file atomically.
+    pub fn write(&self, path: &Path) -> std::result::Result<(), OptifiClientError> {
+        let markets = self
+            .markets
+            .iter()
+            .map(market_to_json)
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let contents = serde_json::to_string(&json!({
+            "version": MARKET_CACHE_VERSION,
+            "optifi_exchange": self.optifi_exchange.to_string(),
+            "markets": markets,
+        }))?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let tmp = path.with_extension("json.tmp");
+
+        fs::write(&tmp, contents)?;
+        fs::rename(&tmp, path)?;
+
+        Ok(())
+    }
+}
+
+impl OptifiClient {
+    /// Cache file of this client's exchange, if `config.market_cache_dir` is set.
+    pub fn market_cache_path(&self) -> Option<PathBuf> {
+        self.config
+            .market_cache_dir
+            .as_ref()
+            .map(|dir| MarketCache::path(dir, &self.optifi_exchange))
+    }
+
+    /// Replaces the cached markets with the cache file's, without touching the network.
+    ///
+    /// Returns whether there was a usable cache file.
+    pub fn load_market_cache(&self) -> std::result::Result<bool, OptifiClientError> {
+        let cache = match self.market_cache_path() {
+            Some(path) => MarketCache::read(&path, &self.optifi_exchange)?,
+            None => None,
+        };
+
+        Ok(match cache {
+            Some(cache) => {
+                self.update_account(|account| account.markets = cache.markets);
+                true
+            }
+            None => false,
+        })
+    }
+
+    /// Writes the cached markets to the cache file, if one is configured.
+    pub fn save_market_cache(&self) -> std::result::Result<(), OptifiClientError> {
+        if let Some(path) = self.market_cache_path() {
+            MarketCache {
+                optifi_exchange: self.optifi_exchange,
+                markets: self.account().markets.clone(),
+            }
+            .write(&path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Loads the markets of the cached exchange, only fetching those missing from the cache file
+    /// when one is configured, and saves the result to it.
+    ///
+    /// Saving is best-effort: a cache that can not be written is logged and only costs the next
+    /// startup a full load.
+    pub fn load_markets_cached(
+        &self,
+    ) -> std::result::Result<Vec<MarketLoadFailure>, OptifiClientError> {
+        if self.market_cache_path().is_none() {
+            return self.load_markets();
+        }
+
+        // An unreadable cache is rebuilt rather than failing startup.
+        let failures = if matches!(self.load_market_cache(), Ok(true)) {
+            self.update_markets()?
+        } else {
+            self.load_markets()?
+        };
+
+        if let Err(err) = self.save_market_cache() {
+            log::warn!("could not save the market cache: {}", err);
+        }
+
+        Ok(failures)
+    }
+
+    /// Reconciles the cached markets with the live markets of the cached exchange: markets no
+    /// longer listed or stopped are dropped, and only the new ones and those whose instrument
+    /// the exchange no longer lists are fetched.
+    pub fn update_markets(&self) -> std::result::Result<Vec<MarketLoadFailure>, OptifiClientError> {
+        let account = self.account();
+
+        let optifi_exchange = account.get_optifi_exchange()?;
+
+        let cached = |pubkey: &Pubkey| {
+            account.markets.iter().find(|market| {
+                market.optifi_market_key_data.optifi_market_pubkey == *pubkey
+                    && optifi_exchange
+                        .get_instrument_data(&market.optifi_market.instrument)
+                        .is_some()
+            })
+        };
+
+        let new_keys: Vec<OptifiMarketKeyData> = optifi_exchange
+            .markets
+            .iter()
+            .filter(|key_data| {
+                !key_data.is_stopped && cached(&key_data.optifi_market_pubkey).is_none()
+            })
+            .cloned()
+            .collect();
+
+        let new_pubkeys: Vec<Pubkey> = new_keys
+            .iter()
+            .map(|key_data| key_data.optifi_market_pubkey)
+            .collect();
+
+        let optifi_markets: Vec<(Pubkey, OptifiMarket)> = new_pubkeys
+            .iter()
+            .zip(self.fetch_multiple_accounts(&new_pubkeys)?)
+            .filter_map(|(pubkey, account)| {
+                Some((
+                    *pubkey,
+                    OptifiMarket::try_deserialize(&mut (&account?.data as &[u8])).ok()?,
+                ))
+            })
+            .collect();
+
+        let (pending, mut failures) = pending_markets(optifi_exchange, &new_keys, &optifi_markets);
+
+        let serum_markets: Vec<Pubkey> = pending.iter().map(PendingMarket::serum_market).collect();
+
+        let mut new_markets = complete_markets(
+            pending,
+            self.fetch_multiple_accounts(&serum_markets)?,
+            &mut failures,
+        );
+
+        // Keep the exchange's order, refreshing the stopped flag of cached markets.
+        let markets = optifi_exchange
+            .markets
+            .iter()
+            .filter(|key_data| !key_data.is_stopped)
+            .filter_map(|key_data| {
+                let pubkey = key_data.optifi_market_pubkey;
+
+                let refreshed = cached(&pubkey).cloned().map(|market| Market {
+                    optifi_market_key_data: key_data.clone(),
+                    ..market
+                });
+
+                refreshed.or_else(|| {
+                    let index = new_markets.iter().position(|market| {
+                        market.optifi_market_key_data.optifi_market_pubkey == pubkey
+                    })?;
+
+                    Some(new_markets.swap_remove(index))
+                })
+            })
+            .collect();
+
+        self.update_account(|account| account.markets = markets);
+
+        Ok(failures)
+    }
+}
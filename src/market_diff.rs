@@ -0,0 +1,188 @@
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::client::{Market, OptifiAccount, OptifiClient};
+use crate::error::OptifiClientError;
+use crate::market_loader::MarketLoadFailure;
+use crate::prelude::*;
+use crate::validation::unix_timestamp;
+
+/// Changes to the cached markets made by `refresh_markets`.
+#[derive(Debug, Default)]
+pub struct MarketDiff {
+    /// Markets listed for the first time.
+    pub added: Vec<Market>,
+    /// Known markets listed again, after being stopped or with a new instrument.
+    pub relisted: Vec<Market>,
+    /// Markets stopped or no longer listed by the exchange.
+    pub stopped: Vec<Market>,
+    /// Markets whose instrument expired or was replaced.
+    pub expired: Vec<Market>,
+    /// Live markets that could not be loaded.
+    pub failures: Vec<MarketLoadFailure>,
+}
+
+impl MarketDiff {
+    /// Whether the set of tradable markets changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.relisted.is_empty()
+            && self.stopped.is_empty()
+            && self.expired.is_empty()
+    }
+}
+
+/// Background task started by `spawn_market_refresh`; dropping it stops the task.
+pub struct MarketRefreshHandle {
+    stop: Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl MarketRefreshHandle {
+    /// Stops the task, waiting for a refresh in progress to finish.
+    pub fn stop(self) {
+        let _ = self.stop.send(());
+        let _ = self.thread.join();
+    }
+}
+
+fn optifi_market_pubkey(market: &Market) -> Pubkey {
+    market.optifi_market_key_data.optifi_market_pubkey
+}
+
+fn is_expired(market: &Market, now: u64) -> bool {
+    market.instrument_common.expiry_date <= now
+}
+
+/// Classifies the change from `previous`, the account before a refresh, to `markets`, the
+/// markets loaded for `exchange` after it, at unix timestamp `now`.
+///
+/// Markets of `markets` past their expiry are not tradable and count as expired. The diff's
+/// `failures` are left empty.
+pub fn diff_markets(
+    previous: &OptifiAccount,
+    exchange: &Exchange,
+    markets: &[Market],
+    now: u64,
+) -> MarketDiff {
+    let markets: Vec<&Market> = markets
+        .iter()
+        .filter(|market| !is_expired(market, now))
+        .collect();
+
+    let was_listed = |pubkey: &Pubkey| {
+        previous.optifi_exchange.as_ref().map_or(false, |exchange| {
+            exchange
+                .markets
+                .iter()
+                .any(|key_data| key_data.optifi_market_pubkey == *pubkey)
+        })
+    };
+
+    let mut diff = MarketDiff::default();
+
+    for market in previous.markets.iter() {
+        let pubkey = optifi_market_pubkey(market);
+
+        let is_live = exchange
+            .markets
+            .iter()
+            .any(|key_data| key_data.optifi_market_pubkey == pubkey && !key_data.is_stopped);
+
+        let current = markets
+            .iter()
+            .find(|current| optifi_market_pubkey(current) == pubkey);
+
+        if !is_live {
+            diff.stopped.push(market.clone());
+        } else if current.map_or(true, |current| {
+            current.optifi_market.instrument != market.optifi_market.instrument
+        }) {
+            // Past its expiry, or its instrument was replaced by a new listing.
+            diff.expired.push(market.clone());
+        }
+    }
+
+    for market in markets {
+        let pubkey = optifi_market_pubkey(market);
+
+        let previous_market = previous
+            .markets
+            .iter()
+            .find(|previous| optifi_market_pubkey(previous) == pubkey);
+
+        match previous_market {
+            Some(previous_market)
+                if previous_market.optifi_market.instrument == market.optifi_market.instrument => {}
+            Some(_) => diff.relisted.push(market.clone()),
+            None if was_listed(&pubkey) => diff.relisted.push(market.clone()),
+            None => diff.added.push(market.clone()),
+        }
+    }
+
+    diff
+}
+
+impl OptifiClient {
+    /// Reloads the exchange and reconciles the cached markets with it, see `update_markets`,
+    /// returning what changed since the previous load, see `diff_markets`.
+    ///
+    /// Expired markets are dropped from the cache, so each change is reported once. The market
+    /// cache file is saved if one is configured; a failed save is logged and does not fail the
+    /// refresh.
+    pub fn refresh_markets(&self) -> std::result::Result<MarketDiff, OptifiClientError> {
+        let previous = self.account();
+
+        self.load_optifi_exchange()?;
+
+        let failures = self.update_markets()?;
+
+        let now = unix_timestamp();
+
+        let account = self.account();
+
+        let diff = MarketDiff {
+            failures,
+            ..diff_markets(
+                &previous,
+                account.get_optifi_exchange()?,
+                &account.markets,
+                now,
+            )
+        };
+
+        if account.markets.iter().any(|market| is_expired(market, now)) {
+            self.update_account(|account| {
+                account.markets.retain(|market| !is_expired(market, now))
+            });
+        }
+
+        if let Err(err) = self.save_market_cache() {
+            log::warn!("could not save the market cache: {}", err);
+        }
+
+        Ok(diff)
+    }
+
+    /// Calls `refresh_markets` every `interval` on a background thread, passing each result to
+    /// `on_refresh`, until the returned handle is stopped or dropped.
+    pub fn spawn_market_refresh(
+        self: &Arc<Self>,
+        interval: Duration,
+        mut on_refresh: impl FnMut(std::result::Result<MarketDiff, OptifiClientError>) + Send + 'static,
+    ) -> MarketRefreshHandle {
+        let client = self.clone();
+        let (stop, stopped) = mpsc::channel();
+
+        let thread = std::thread::spawn(move || loop {
+            match stopped.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => on_refresh(client.refresh_markets()),
+                _ => break,
+            }
+        });
+
+        MarketRefreshHandle { stop, thread }
+    }
+}
@@ -0,0 +1,187 @@
+use crate::client::{get_market_pubkeys, Market, OptifiClient};
+use crate::error::OptifiClientError;
+use crate::prelude::*;
+
+/// Most accounts a `getMultipleAccounts` call returns.
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// A market of the exchange that could not be loaded; the others still are.
+#[derive(Debug)]
+pub struct MarketLoadFailure {
+    pub optifi_market: Pubkey,
+    pub error: OptifiClientError,
+}
+
+/// Market whose optifi accounts are decoded, waiting for its serum market account.
+pub(crate) struct PendingMarket {
+    optifi_market_key_data: OptifiMarketKeyData,
+    optifi_market: OptifiMarket,
+    instrument_common: InstrumentCommon,
+    strike: u32,
+    instrument_type: InstrumentType,
+}
+
+impl PendingMarket {
+    pub(crate) fn serum_market(&self) -> Pubkey {
+        self.optifi_market.serum_market
+    }
+
+    /// Completes the market with its serum market account, deriving the serum pubkeys from it.
+    pub(crate) fn into_market(
+        self,
+        serum_account: Option<solana_sdk::account::Account>,
+    ) -> std::result::Result<Market, MarketLoadFailure> {
+        let optifi_market = self.optifi_market_key_data.optifi_market_pubkey;
+        let serum_market = self.optifi_market.serum_market;
+
+        let fail = |error| MarketLoadFailure {
+            optifi_market,
+            error,
+        };
+
+        let serum_account =
+            serum_account.ok_or_else(|| fail(OptifiClientError::AccountNotFound(serum_market)))?;
+
+        let market_pubkeys = get_market_pubkeys(&serum_market, &serum_account).map_err(fail)?;
+
+        Ok(Market {
+            optifi_market: self.optifi_market,
+            optifi_market_key_data: self.optifi_market_key_data,
+            instrument_common: self.instrument_common,
+            strike: self.strike,
+            instrument_type: self.instrument_type,
+            market_pubkeys,
+            serum_account,
+        })
+    }
+}
+
+/// Matches the live markets among `market_keys` with their decoded `optifi_markets` and the
+/// instrument data of `optifi_exchange`.
+pub(crate) fn pending_markets(
+    optifi_exchange: &Exchange,
+    market_keys: &[OptifiMarketKeyData],
+    optifi_markets: &[(Pubkey, OptifiMarket)],
+) -> (Vec<PendingMarket>, Vec<MarketLoadFailure>) {
+    let mut pending = vec![];
+    let mut failures = vec![];
+
+    for optifi_market_key_data in market_keys.iter() {
+        if optifi_market_key_data.is_stopped {
+            continue;
+        }
+
+        let optifi_market_pubkey = optifi_market_key_data.optifi_market_pubkey;
+
+        let optifi_market = match optifi_markets
+            .iter()
+            .find(|(pubkey, ..)| pubkey == &optifi_market_pubkey)
+        {
+            Some((_, optifi_market)) => optifi_market.clone(),
+            None => {
+                failures.push(MarketLoadFailure {
+                    optifi_market: optifi_market_pubkey,
+                    error: OptifiClientError::MarketNotFound(optifi_market_pubkey),
+                });
+                continue;
+            }
+        };
+
+        let instrument_pubkey = optifi_market.instrument;
+
+        let (instrument_common, strike, is_call) =
+            match optifi_exchange.get_instrument_data(&instrument_pubkey) {
+                Some(instrument_data) => instrument_data,
+                None => {
+                    failures.push(MarketLoadFailure {
+                        optifi_market: optifi_market_pubkey,
+                        error: OptifiClientError::AccountNotFound(instrument_pubkey),
+                    });
+                    continue;
+                }
+            };
+
+        let instrument_type = if is_call {
+            InstrumentType::Call
+        } else {
+            InstrumentType::Put
+        };
+
+        pending.push(PendingMarket {
+            optifi_market_key_data: optifi_market_key_data.clone(),
+            optifi_market,
+            instrument_common,
+            strike,
+            instrument_type,
+        });
+    }
+
+    (pending, failures)
+}
+
+/// Completes `pending` with their serum market accounts, in the same order.
+pub(crate) fn complete_markets(
+    pending: Vec<PendingMarket>,
+    serum_accounts: Vec<Option<solana_sdk::account::Account>>,
+    failures: &mut Vec<MarketLoadFailure>,
+) -> Vec<Market> {
+    pending
+        .into_iter()
+        .zip(serum_accounts)
+        .filter_map(
+            |(pending, serum_account)| match pending.into_market(serum_account) {
+                Ok(market) => Some(market),
+                Err(failure) => {
+                    failures.push(failure);
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+impl OptifiClient {
+    /// Fetches `pubkeys` with as few `getMultipleAccounts` calls as possible, in order.
+    pub fn fetch_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+    ) -> std::result::Result<Vec<Option<solana_sdk::account::Account>>, OptifiClientError> {
+        let mut accounts = Vec::with_capacity(pubkeys.len());
+
+        for chunk in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            accounts.extend(
+                self.rpc
+                    .get_multiple_accounts_with_commitment(chunk, self.config.read_commitment)?
+                    .value,
+            );
+        }
+
+        Ok(accounts)
+    }
+
+    /// Loads the live markets of the cached exchange, fetching their serum market accounts in
+    /// batches and deriving the serum pubkeys from them.
+    ///
+    /// Markets that fail to load are left out of the cache and returned; an error means none
+    /// could be loaded.
+    pub fn load_markets(&self) -> std::result::Result<Vec<MarketLoadFailure>, OptifiClientError> {
+        let account = self.account();
+
+        let optifi_exchange = account.get_optifi_exchange()?;
+
+        let optifi_markets = self.custom_accounts::<OptifiMarket>(vec![])?;
+
+        let (pending, mut failures) =
+            pending_markets(optifi_exchange, &optifi_exchange.markets, &optifi_markets);
+
+        let serum_markets: Vec<Pubkey> = pending.iter().map(PendingMarket::serum_market).collect();
+
+        let serum_accounts = self.fetch_multiple_accounts(&serum_markets)?;
+
+        let markets = complete_markets(pending, serum_accounts, &mut failures);
+
+        self.update_account(|account| account.markets = markets);
+
+        Ok(failures)
+    }
+}
@@ -0,0 +1,191 @@
+use std::sync::PoisonError;
+
+use crate::client::{Market, OptifiClient};
+use crate::compute_budget::Operation;
+use crate::config::OptifiConfig;
+use crate::error::OptifiClientError;
+use crate::instructions::InstructionBuilder;
+use crate::prelude::*;
+use crate::send::TransactionOutcome;
+use crate::validation::{OrderRejection, OrderValidation};
+
+/// Which of the accounts `initialize_user_on_market` creates for a market exist.
+///
+/// The default has none of them, as for a market the user never traded on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MarketSetup {
+    /// The user's serum open orders account, a PDA of the optifi program.
+    pub open_orders: bool,
+    /// The user account's associated token account of the long instrument.
+    pub long_token_account: bool,
+    /// The user account's associated token account of the short instrument.
+    pub short_token_account: bool,
+}
+
+impl MarketSetup {
+    /// Reads which of the `market_setup_accounts` exist from their fetched `accounts`.
+    pub fn from_accounts(accounts: &[Option<solana_sdk::account::Account>]) -> Self {
+        let exists = |i: usize| accounts.get(i).map_or(false, Option::is_some);
+
+        Self {
+            open_orders: exists(0),
+            long_token_account: exists(1),
+            short_token_account: exists(2),
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.open_orders && self.long_token_account && self.short_token_account
+    }
+}
+
+/// The user's open orders and instrument token accounts on `market`, in `MarketSetup`'s order.
+pub fn market_setup_accounts(
+    config: &OptifiConfig,
+    user_account: &Pubkey,
+    market: &Market,
+) -> [Pubkey; 3] {
+    let (open_orders, ..) = get_serum_open_orders_account(
+        &config.optifi_exchange,
+        user_account,
+        &market.optifi_market.serum_market,
+        &config.optifi_program_id,
+    );
+
+    [
+        open_orders,
+        get_associated_token_address(
+            user_account,
+            &market.optifi_market.instrument_long_spl_token,
+        ),
+        get_associated_token_address(
+            user_account,
+            &market.optifi_market.instrument_short_spl_token,
+        ),
+    ]
+}
+
+/// Whether orders need the user's `MarketSetup`, to complete it or to reject them without it.
+pub fn checks_market_setup(config: &OptifiConfig) -> bool {
+    config.auto_initialize_markets || config.order_validation != OrderValidation::Off
+}
+
+/// Setup instructions to prepend to the first order on `market`, given the user's `setup`.
+///
+/// Without `config.auto_initialize_markets`, a market without the user's open orders account
+/// rejects the order instead, unless order validation is off.
+pub fn order_setup(
+    builder: &InstructionBuilder,
+    market: &Market,
+    setup: MarketSetup,
+) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+    if setup.is_ready() {
+        return Ok(vec![]);
+    }
+
+    if builder.config.auto_initialize_markets {
+        return Ok(builder.complete_market_setup(market, setup));
+    }
+
+    if builder.config.order_validation != OrderValidation::Off && !setup.open_orders {
+        return Err(OrderRejection::NotInitialized(
+            market.optifi_market_key_data.optifi_market_pubkey,
+        )
+        .into());
+    }
+
+    Ok(vec![])
+}
+
+impl OptifiClient {
+    /// Checks which of the user's accounts on `market` exist, in one RPC call.
+    pub fn load_market_setup(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<MarketSetup, OptifiClientError> {
+        let accounts = self.rpc.get_multiple_accounts_with_commitment(
+            &market_setup_accounts(&self.config, &self.user_account, market),
+            self.config.read_commitment,
+        )?;
+
+        let setup = MarketSetup::from_accounts(&accounts.value);
+
+        if setup.is_ready() {
+            self.mark_market_ready(market);
+        }
+
+        Ok(setup)
+    }
+
+    /// Whether `market` is known to be set up, without touching the network.
+    pub fn is_market_ready(&self, market: &Market) -> bool {
+        self.ready_markets
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains(&market.optifi_market_key_data.optifi_market_pubkey)
+    }
+
+    pub(crate) fn mark_market_ready(&self, market: &Market) {
+        self.ready_markets
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(market.optifi_market_key_data.optifi_market_pubkey);
+    }
+
+    /// Instructions creating the user's accounts missing on `market`, empty once it is set up.
+    ///
+    /// Markets found set up are cached, so only the first call per market reads the chain.
+    pub fn ensure_market_ready_instructions(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        if self.is_market_ready(market) {
+            return Ok(vec![]);
+        }
+
+        let setup = self.load_market_setup(market)?;
+
+        if setup.is_ready() {
+            return Ok(vec![]);
+        }
+
+        Ok(self
+            .instruction_builder(&self.account())
+            .complete_market_setup(market, setup))
+    }
+
+    /// Creates the user's accounts missing on `market`, returning `None` if there were none.
+    pub fn ensure_market_ready(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<Option<TransactionOutcome>, OptifiClientError> {
+        let instructions = self.ensure_market_ready_instructions(market)?;
+
+        if instructions.is_empty() {
+            return Ok(None);
+        }
+
+        let outcome =
+            self.send_instructions(self.with_compute_budget(Operation::Other, instructions)?)?;
+
+        if outcome.is_confirmed() {
+            self.mark_market_ready(market);
+        }
+
+        Ok(Some(outcome))
+    }
+
+    /// Setup instructions to prepend to the first order on `market`, see `order_setup`.
+    pub(crate) fn order_setup_instructions(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        if !checks_market_setup(&self.config) || self.is_market_ready(market) {
+            return Ok(vec![]);
+        }
+
+        let setup = self.load_market_setup(market)?;
+
+        order_setup(&self.instruction_builder(&self.account()), market, setup)
+    }
+}
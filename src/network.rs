@@ -0,0 +1,137 @@
+use crate::error::{parse_pubkey, OptifiClientError};
+use crate::prelude::*;
+
+/// Addresses of the OptiFi mainnet deployment, the ones `optifi-cpi` and `optifi-usdc-cpi`
+/// compile in by default.
+pub mod mainnet {
+    pub const OPTIFI_EXCHANGE: &str = "575NEMoeiqA3moqAgNy9iqxDwUfjGCm92NZoCW8xS9C9";
+    pub const OPTIFI_PROGRAM_ID: &str = "optF8UCDznwL6mJ2UPF4jnHaHbJe5g9RY8CaU846noM";
+    pub const OPTIFI_USDC_PROGRAM_ID: &str = "opucrGSP5hyYAEfQMjYrchfLtwowjiAjAMwUmmjoYGP";
+    pub const SERUM_DEX_PROGRAM_ID: &str = "9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin";
+    pub const USDC_TOKEN_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+}
+
+/// Addresses of the OptiFi devnet deployment, the ones `optifi-cpi` and `optifi-usdc-cpi`
+/// compile in with their `devnet` feature.
+pub mod devnet {
+    pub const OPTIFI_EXCHANGE: &str = "EFvhNA6C6Dd9CaGHoeyBPFHpHBYjmSyuxJ8WN8FjVTRp";
+    pub const OPTIFI_PROGRAM_ID: &str = "opDV2tLVsRPGk9aYqm4gdtGotiRwjuYKUmWzWB7NfCR";
+    pub const OPTIFI_USDC_PROGRAM_ID: &str = "oudvxi2FrrgAS8boTYg4XWVkNsDE8ASK4AveVZGEjQ4";
+    pub const SERUM_DEX_PROGRAM_ID: &str = "DESVgJVGajEgKGXhb6XmqDHGz3VjdgP7rEVESBgxmroY";
+    pub const USDC_TOKEN_MINT: &str = "8FRFC6MoGGkMFQwngccyu69VnYbzykGeez7ignHVAFSN";
+}
+
+/// Addresses of an OptiFi deployment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Deployment {
+    pub optifi_exchange: Pubkey,
+    pub optifi_program_id: Pubkey,
+    pub optifi_usdc_program_id: Pubkey,
+    pub serum_dex_program_id: Pubkey,
+    pub usdc_token_mint: Pubkey,
+}
+
+impl Deployment {
+    pub fn mainnet() -> std::result::Result<Self, OptifiClientError> {
+        Ok(Self {
+            optifi_exchange: parse_pubkey(mainnet::OPTIFI_EXCHANGE)?,
+            optifi_program_id: parse_pubkey(mainnet::OPTIFI_PROGRAM_ID)?,
+            optifi_usdc_program_id: parse_pubkey(mainnet::OPTIFI_USDC_PROGRAM_ID)?,
+            serum_dex_program_id: parse_pubkey(mainnet::SERUM_DEX_PROGRAM_ID)?,
+            usdc_token_mint: parse_pubkey(mainnet::USDC_TOKEN_MINT)?,
+        })
+    }
+
+    pub fn devnet() -> std::result::Result<Self, OptifiClientError> {
+        Ok(Self {
+            optifi_exchange: parse_pubkey(devnet::OPTIFI_EXCHANGE)?,
+            optifi_program_id: parse_pubkey(devnet::OPTIFI_PROGRAM_ID)?,
+            optifi_usdc_program_id: parse_pubkey(devnet::OPTIFI_USDC_PROGRAM_ID)?,
+            serum_dex_program_id: parse_pubkey(devnet::SERUM_DEX_PROGRAM_ID)?,
+            usdc_token_mint: parse_pubkey(devnet::USDC_TOKEN_MINT)?,
+        })
+    }
+
+    /// Addresses compiled into `optifi-cpi` and `optifi-usdc-cpi`.
+    pub fn compiled() -> std::result::Result<Self, OptifiClientError> {
+        Ok(Self {
+            optifi_exchange: parse_pubkey(OPTIFI_EXCHANGE)?,
+            optifi_program_id: optifi_cpi::id(),
+            optifi_usdc_program_id: optifi_usdc_cpi::id(),
+            serum_dex_program_id: parse_pubkey(SERUM_DEX_PROGRAM_ID)?,
+            usdc_token_mint: parse_pubkey(USDC_TOKEN_MINT)?,
+        })
+    }
+}
+
+/// OptiFi deployment a client talks to, independent of the rpc endpoint used to reach it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Network {
+    Mainnet,
+    Devnet,
+    /// Local validator with the programs loaded at their compiled ids.
+    Localnet,
+    Custom(Deployment),
+}
+
+impl Default for Network {
+    /// The network selected by the `devnet` cargo feature, the only thing the feature changes.
+    fn default() -> Self {
+        if cfg!(feature = "devnet") {
+            Network::Devnet
+        } else {
+            Network::Mainnet
+        }
+    }
+}
+
+impl Network {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Network::Mainnet => "mainnet",
+            Network::Devnet => "devnet",
+            Network::Localnet => "localnet",
+            Network::Custom(..) => "custom",
+        }
+    }
+
+    /// Rpc cluster usually used to reach this network.
+    pub fn cluster(&self) -> Cluster {
+        match self {
+            Network::Mainnet => Cluster::Mainnet,
+            Network::Devnet => Cluster::Devnet,
+            Network::Localnet | Network::Custom(..) => Cluster::Localnet,
+        }
+    }
+
+    pub fn deployment(&self) -> std::result::Result<Deployment, OptifiClientError> {
+        match self {
+            Network::Mainnet => Deployment::mainnet(),
+            Network::Devnet => Deployment::devnet(),
+            Network::Localnet => Deployment::compiled(),
+            Network::Custom(deployment) => Ok(deployment.clone()),
+        }
+    }
+}
+
+impl std::fmt::Display for Network {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Network {
+    type Err = OptifiClientError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "mainnet" | "mainnet-beta" | "m" => Ok(Network::Mainnet),
+            "devnet" | "d" => Ok(Network::Devnet),
+            "localnet" | "l" => Ok(Network::Localnet),
+            _ => Err(OptifiClientError::Conversion(format!(
+                "unknown network {}",
+                s
+            ))),
+        }
+    }
+}
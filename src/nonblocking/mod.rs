@@ -0,0 +1,10 @@
+//! Tokio counterparts of the `solana_client` RPC and pubsub clients, used by
+//! `AsyncOptifiClient`.
+//!
+//! `solana_client` 1.9, which anchor 0.24 pins, has no `nonblocking` module yet. The requests
+//! `AsyncOptifiClient` makes are implemented here under the module paths, method names and
+//! error types of the later `solana_client::nonblocking` clients, so moving to those once the
+//! pin allows is an import change.
+
+pub mod pubsub_client;
+pub mod rpc_client;
@@ -0,0 +1,110 @@
+use futures::stream::{self, BoxStream};
+use futures::{SinkExt, StreamExt};
+use solana_client::pubsub_client::PubsubClientError;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+use crate::prelude::*;
+
+type WebSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Account updates of one subscription, ending when its connection closes or fails.
+pub type AccountStream =
+    BoxStream<'static, std::result::Result<Response<UiAccount>, PubsubClientError>>;
+
+/// Websocket subscriptions on the tokio runtime.
+///
+/// Each subscription owns its connection, so dropping its stream closes it and ends the
+/// subscription without a thread or task to stop.
+#[derive(Clone, Debug)]
+pub struct PubsubClient {
+    url: String,
+}
+
+impl PubsubClient {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_owned(),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Subscribes to updates of `pubkey`, returning once the node confirmed the subscription.
+    pub async fn account_subscribe(
+        &self,
+        pubkey: &Pubkey,
+        config: Option<RpcAccountInfoConfig>,
+    ) -> std::result::Result<AccountStream, PubsubClientError> {
+        let (mut socket, _) = connect_async(self.url.as_str()).await?;
+
+        socket
+            .send(Message::Text(
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "accountSubscribe",
+                    "params": [pubkey.to_string(), config],
+                })
+                .to_string(),
+            ))
+            .await?;
+
+        let subscription = loop {
+            let message = next_message(&mut socket).await?.ok_or_else(|| {
+                PubsubClientError::UnexpectedMessageError(
+                    "connection closed before the subscription was confirmed".to_owned(),
+                )
+            })?;
+
+            if message["id"] == 1 {
+                break message["result"].as_u64().ok_or_else(|| {
+                    PubsubClientError::UnexpectedMessageError(message.to_string())
+                })?;
+            }
+        };
+
+        Ok(stream::unfold(Some(socket), move |socket| async move {
+            let mut socket = socket?;
+
+            loop {
+                match next_message(&mut socket).await {
+                    Ok(Some(mut message)) => {
+                        if message["params"]["subscription"] != subscription {
+                            continue;
+                        }
+
+                        let update: std::result::Result<Response<UiAccount>, _> =
+                            serde_json::from_value(message["params"]["result"].take())
+                                .map_err(PubsubClientError::from);
+
+                        return Some((update, Some(socket)));
+                    }
+                    Ok(None) => return None,
+                    Err(err) => return Some((Err(err), None)),
+                }
+            }
+        })
+        .boxed())
+    }
+}
+
+/// Next JSON message of `socket`, `None` once it is closed.
+///
+/// Pings are answered by the socket itself while it is read.
+async fn next_message(
+    socket: &mut WebSocket,
+) -> std::result::Result<Option<Value>, PubsubClientError> {
+    while let Some(message) = socket.next().await {
+        match message? {
+            Message::Text(text) => return Ok(Some(serde_json::from_str(&text)?)),
+            Message::Close(_) => return Ok(None),
+            _ => {}
+        }
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,388 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use reqwest::header::{CONTENT_TYPE, RETRY_AFTER};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use solana_client::client_error::{ClientError as RpcClientError, Result as ClientResult};
+use solana_client::rpc_config::{
+    RpcProgramAccountsConfig, RpcSendTransactionConfig, RpcSimulateTransactionConfig,
+    RpcTransactionConfig,
+};
+use solana_client::rpc_custom_error::{
+    NodeUnhealthyErrorData, JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY,
+    JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE,
+};
+use solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+use solana_client::rpc_response::{
+    RpcBlockhash, RpcKeyedAccount, RpcResult, RpcSimulateTransactionResult,
+};
+use solana_sdk::account::Account;
+use solana_sdk::hash::Hash;
+use solana_sdk::transaction::Transaction;
+use solana_transaction_status::{
+    EncodedConfirmedTransaction, TransactionStatus, UiTransactionEncoding,
+};
+
+use crate::prelude::*;
+
+/// Times a rate limited request is retried before its 429 is returned.
+const TOO_MANY_REQUESTS_RETRIES: usize = 5;
+
+/// JSON RPC client over an async `reqwest::Client`.
+///
+/// Mirrors the methods of the blocking `solana_client::rpc_client::RpcClient` the optifi
+/// clients use, with the same requests, results and `ClientError`s, so errors are classified
+/// the same way for both, see `send::is_transient`.
+pub struct RpcClient {
+    client: reqwest::Client,
+    url: String,
+    commitment: CommitmentConfig,
+    request_id: AtomicU64,
+}
+
+impl RpcClient {
+    /// Requests time out after 30 seconds, as with the blocking client.
+    pub fn new_with_commitment(url: String, commitment: CommitmentConfig) -> Self {
+        Self::new_with_timeout_and_commitment(url, Duration::from_secs(30), commitment)
+    }
+
+    pub fn new_with_timeout_and_commitment(
+        url: String,
+        timeout: Duration,
+        commitment: CommitmentConfig,
+    ) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(timeout)
+                .build()
+                .expect("build rpc client"),
+            url,
+            commitment,
+            request_id: AtomicU64::new(0),
+        }
+    }
+
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    pub fn commitment(&self) -> CommitmentConfig {
+        self.commitment
+    }
+
+    /// Sends `request` with `params` and deserializes its result.
+    pub async fn send<T: DeserializeOwned>(
+        &self,
+        request: RpcRequest,
+        params: Value,
+    ) -> ClientResult<T> {
+        let result = self
+            .send_request(request, params)
+            .await
+            .map_err(|err| err.into_with_request(request))?;
+
+        serde_json::from_value(result)
+            .map_err(|err| RpcClientError::new_with_request(err.into(), request))
+    }
+
+    async fn send_request(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+        let request_json = json!({
+            "jsonrpc": "2.0",
+            "id": self.request_id.fetch_add(1, Ordering::Relaxed),
+            "method": request.to_string(),
+            "params": params,
+        })
+        .to_string();
+
+        let mut too_many_requests_retries = TOO_MANY_REQUESTS_RETRIES;
+
+        loop {
+            let response = self
+                .client
+                .post(&self.url)
+                .header(CONTENT_TYPE, "application/json")
+                .body(request_json.clone())
+                .send()
+                .await?;
+
+            if response.status() == StatusCode::TOO_MANY_REQUESTS && too_many_requests_retries > 0 {
+                let retry_after = response
+                    .headers()
+                    .get(RETRY_AFTER)
+                    .and_then(|retry_after| retry_after.to_str().ok())
+                    .and_then(|retry_after| retry_after.parse::<u64>().ok())
+                    .filter(|retry_after| *retry_after < 120)
+                    .map_or(Duration::from_millis(500), Duration::from_secs);
+
+                too_many_requests_retries -= 1;
+
+                log::debug!(
+                    "too many requests, {} retries left, pausing for {:?}",
+                    too_many_requests_retries,
+                    retry_after
+                );
+
+                tokio::time::sleep(retry_after).await;
+                continue;
+            }
+
+            let mut json: Value = response.error_for_status()?.json().await?;
+
+            if json["error"].is_object() {
+                return Err(rpc_error(&json["error"]).into());
+            }
+
+            return Ok(json["result"].take());
+        }
+    }
+
+    pub async fn get_account_with_commitment(
+        &self,
+        pubkey: &Pubkey,
+        commitment: CommitmentConfig,
+    ) -> RpcResult<Option<Account>> {
+        let response: Response<Option<UiAccount>> = self
+            .send(
+                RpcRequest::GetAccountInfo,
+                json!([pubkey.to_string(), account_info_config(commitment)]),
+            )
+            .await?;
+
+        Ok(Response {
+            context: response.context,
+            value: response.value.and_then(|account| account.decode()),
+        })
+    }
+
+    pub async fn get_multiple_accounts_with_commitment(
+        &self,
+        pubkeys: &[Pubkey],
+        commitment: CommitmentConfig,
+    ) -> RpcResult<Vec<Option<Account>>> {
+        let pubkeys: Vec<String> = pubkeys.iter().map(Pubkey::to_string).collect();
+
+        let response: Response<Vec<Option<UiAccount>>> = self
+            .send(
+                RpcRequest::GetMultipleAccounts,
+                json!([pubkeys, account_info_config(commitment)]),
+            )
+            .await?;
+
+        Ok(Response {
+            context: response.context,
+            value: response
+                .value
+                .into_iter()
+                .map(|account| account.and_then(|account| account.decode()))
+                .collect(),
+        })
+    }
+
+    pub async fn get_program_accounts_with_config(
+        &self,
+        program_id: &Pubkey,
+        config: RpcProgramAccountsConfig,
+    ) -> ClientResult<Vec<(Pubkey, Account)>> {
+        let config = RpcProgramAccountsConfig {
+            account_config: RpcAccountInfoConfig {
+                commitment: config.account_config.commitment.or(Some(self.commitment)),
+                ..config.account_config
+            },
+            ..config
+        };
+
+        let request = RpcRequest::GetProgramAccounts;
+
+        let accounts: Vec<RpcKeyedAccount> = self
+            .send(request, json!([program_id.to_string(), config]))
+            .await?;
+
+        accounts
+            .into_iter()
+            .map(|RpcKeyedAccount { pubkey, account }| {
+                let parse_error = |what: &str| {
+                    RpcClientError::new_with_request(
+                        RpcError::ParseError(what.to_owned()).into(),
+                        request,
+                    )
+                };
+
+                Ok((
+                    pubkey.parse().map_err(|_| parse_error("Pubkey"))?,
+                    account
+                        .decode()
+                        .ok_or_else(|| parse_error("Account from rpc"))?,
+                ))
+            })
+            .collect()
+    }
+
+    pub async fn get_latest_blockhash_with_commitment(
+        &self,
+        commitment: CommitmentConfig,
+    ) -> ClientResult<(Hash, u64)> {
+        let request = RpcRequest::GetLatestBlockhash;
+
+        let RpcBlockhash {
+            blockhash,
+            last_valid_block_height,
+        } = self
+            .send::<Response<RpcBlockhash>>(request, json!([commitment]))
+            .await?
+            .value;
+
+        let blockhash = blockhash.parse().map_err(|_| {
+            RpcClientError::new_with_request(
+                RpcError::ParseError("Hash".to_owned()).into(),
+                request,
+            )
+        })?;
+
+        Ok((blockhash, last_valid_block_height))
+    }
+
+    pub async fn get_latest_blockhash(&self) -> ClientResult<Hash> {
+        Ok(self
+            .get_latest_blockhash_with_commitment(self.commitment)
+            .await?
+            .0)
+    }
+
+    pub async fn get_block_height_with_commitment(
+        &self,
+        commitment: CommitmentConfig,
+    ) -> ClientResult<u64> {
+        self.send(RpcRequest::GetBlockHeight, json!([commitment]))
+            .await
+    }
+
+    /// Sends `transaction` base64 encoded, failing if the node returns another signature.
+    pub async fn send_transaction_with_config(
+        &self,
+        transaction: &Transaction,
+        config: RpcSendTransactionConfig,
+    ) -> ClientResult<Signature> {
+        let config = RpcSendTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            preflight_commitment: Some(config.preflight_commitment.unwrap_or_default()),
+            ..config
+        };
+
+        let signature: String = self
+            .send(
+                RpcRequest::SendTransaction,
+                json!([encode_transaction(transaction)?, config]),
+            )
+            .await?;
+
+        let signature = signature
+            .parse::<Signature>()
+            .map_err(|err| RpcClientError::from(RpcError::ParseError(err.to_string())))?;
+
+        // The transaction may or may not have been submitted, its own signature is what to
+        // confirm.
+        if signature != transaction.signatures[0] {
+            return Err(RpcError::RpcRequestError(format!(
+                "RPC node returned mismatched signature {:?}, expected {:?}",
+                signature, transaction.signatures[0]
+            ))
+            .into());
+        }
+
+        Ok(signature)
+    }
+
+    pub async fn simulate_transaction_with_config(
+        &self,
+        transaction: &Transaction,
+        config: RpcSimulateTransactionConfig,
+    ) -> RpcResult<RpcSimulateTransactionResult> {
+        let config = RpcSimulateTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(config.commitment.unwrap_or(self.commitment)),
+            ..config
+        };
+
+        self.send(
+            RpcRequest::SimulateTransaction,
+            json!([encode_transaction(transaction)?, config]),
+        )
+        .await
+    }
+
+    pub async fn get_signature_statuses(
+        &self,
+        signatures: &[Signature],
+    ) -> RpcResult<Vec<Option<TransactionStatus>>> {
+        let signatures: Vec<String> = signatures.iter().map(Signature::to_string).collect();
+
+        self.send(RpcRequest::GetSignatureStatuses, json!([signatures]))
+            .await
+    }
+
+    pub async fn get_transaction_with_config(
+        &self,
+        signature: &Signature,
+        config: RpcTransactionConfig,
+    ) -> ClientResult<EncodedConfirmedTransaction> {
+        self.send(
+            RpcRequest::GetTransaction,
+            json!([signature.to_string(), config]),
+        )
+        .await
+    }
+}
+
+fn account_info_config(commitment: CommitmentConfig) -> RpcAccountInfoConfig {
+    RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        data_slice: None,
+        commitment: Some(commitment),
+    }
+}
+
+fn encode_transaction(transaction: &Transaction) -> ClientResult<String> {
+    let serialized = bincode::serialize(transaction)
+        .map_err(|err| RpcClientError::from(RpcError::ParseError(err.to_string())))?;
+
+    Ok(base64::encode(serialized))
+}
+
+/// The `RpcError` of a JSON RPC error object, with the preflight simulation of a rejected
+/// transaction as its data.
+fn rpc_error(error: &Value) -> RpcError {
+    let (code, message) = match (error["code"].as_i64(), error["message"].as_str()) {
+        (Some(code), Some(message)) => (code, message.to_owned()),
+        _ => {
+            return RpcError::RpcRequestError(format!(
+                "Failed to deserialize RPC error response: {}",
+                error
+            ))
+        }
+    };
+
+    let data = match code {
+        JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE => {
+            serde_json::from_value::<RpcSimulateTransactionResult>(error["data"].clone()).map_or(
+                RpcResponseErrorData::Empty,
+                RpcResponseErrorData::SendTransactionPreflightFailure,
+            )
+        }
+        JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY => {
+            serde_json::from_value::<NodeUnhealthyErrorData>(error["data"].clone()).map_or(
+                RpcResponseErrorData::Empty,
+                |data| RpcResponseErrorData::NodeUnhealthy {
+                    num_slots_behind: data.num_slots_behind,
+                },
+            )
+        }
+        _ => RpcResponseErrorData::Empty,
+    };
+
+    RpcError::RpcResponseError {
+        code,
+        message,
+        data,
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+
+use crate::client::{Market, OptifiClient};
+use crate::prelude::*;
+use crate::types::Price;
+
+/// Call and put markets of one strike.
+#[derive(Clone, Debug)]
+pub struct StrikeMarkets {
+    pub strike: u32,
+    pub call: Option<Market>,
+    pub put: Option<Market>,
+}
+
+impl StrikeMarkets {
+    pub fn get(&self, instrument_type: InstrumentType) -> Option<&Market> {
+        match instrument_type {
+            InstrumentType::Call => self.call.as_ref(),
+            InstrumentType::Put => self.put.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// Markets of one expiry, by strike.
+#[derive(Clone, Debug)]
+pub struct ExpiryChain {
+    /// Unix timestamp the instruments expire at.
+    pub expiry_date: u64,
+    pub strikes: BTreeMap<u32, StrikeMarkets>,
+}
+
+impl ExpiryChain {
+    pub fn strikes(&self) -> Vec<u32> {
+        self.strikes.keys().copied().collect()
+    }
+
+    /// Strike closest to `spot`, the lower one on a tie.
+    pub fn nearest_strike(&self, spot: Price) -> Option<&StrikeMarkets> {
+        self.strikes
+            .values()
+            .min_by_key(|strike| (Decimal::from(strike.strike) - spot.value()).abs())
+    }
+}
+
+/// Markets of one asset, by expiry.
+#[derive(Clone, Debug)]
+pub struct AssetChain {
+    pub asset: Asset,
+    pub expiries: BTreeMap<u64, ExpiryChain>,
+}
+
+impl AssetChain {
+    pub fn expiries(&self) -> Vec<u64> {
+        self.expiries.keys().copied().collect()
+    }
+}
+
+/// Option markets indexed by asset, expiry, strike and call or put.
+///
+/// A snapshot of the cached markets; build a new one after `load_markets`.
+#[derive(Clone, Debug, Default)]
+pub struct OptionChain {
+    pub assets: Vec<AssetChain>,
+}
+
+impl OptionChain {
+    /// Indexes the call and put markets among `markets`, ignoring other instruments.
+    pub fn new(markets: &[Market]) -> Self {
+        let mut chain = Self::default();
+
+        for market in markets {
+            let is_call = match market.instrument_type {
+                InstrumentType::Call => true,
+                InstrumentType::Put => false,
+                _ => continue,
+            };
+
+            let asset = market.instrument_common.asset;
+            let expiry_date = market.instrument_common.expiry_date;
+
+            let index = match chain.assets.iter().position(|chain| chain.asset == asset) {
+                Some(index) => index,
+                None => {
+                    chain.assets.push(AssetChain {
+                        asset,
+                        expiries: BTreeMap::new(),
+                    });
+                    chain.assets.len() - 1
+                }
+            };
+
+            let strike = chain.assets[index]
+                .expiries
+                .entry(expiry_date)
+                .or_insert_with(|| ExpiryChain {
+                    expiry_date,
+                    strikes: BTreeMap::new(),
+                })
+                .strikes
+                .entry(market.strike)
+                .or_insert_with(|| StrikeMarkets {
+                    strike: market.strike,
+                    call: None,
+                    put: None,
+                });
+
+            if is_call {
+                strike.call = Some(market.clone());
+            } else {
+                strike.put = Some(market.clone());
+            }
+        }
+
+        chain
+    }
+
+    pub fn asset(&self, asset: Asset) -> Option<&AssetChain> {
+        self.assets.iter().find(|chain| chain.asset == asset)
+    }
+
+    pub fn expiry(&self, asset: Asset, expiry_date: u64) -> Option<&ExpiryChain> {
+        self.asset(asset)?.expiries.get(&expiry_date)
+    }
+
+    /// Expiries listed for `asset`, earliest first.
+    pub fn expiries(&self, asset: Asset) -> Vec<u64> {
+        self.asset(asset)
+            .map(AssetChain::expiries)
+            .unwrap_or_default()
+    }
+
+    /// Strikes listed for `asset` at `expiry_date`, lowest first.
+    pub fn strikes(&self, asset: Asset, expiry_date: u64) -> Vec<u32> {
+        self.expiry(asset, expiry_date)
+            .map(ExpiryChain::strikes)
+            .unwrap_or_default()
+    }
+
+    pub fn get(
+        &self,
+        asset: Asset,
+        expiry_date: u64,
+        strike: u32,
+        instrument_type: InstrumentType,
+    ) -> Option<&Market> {
+        self.expiry(asset, expiry_date)?
+            .strikes
+            .get(&strike)?
+            .get(instrument_type)
+    }
+
+    /// Strike of `asset` at `expiry_date` closest to `spot`, the lower one on a tie.
+    pub fn nearest_strike(
+        &self,
+        asset: Asset,
+        expiry_date: u64,
+        spot: Price,
+    ) -> Option<&StrikeMarkets> {
+        self.expiry(asset, expiry_date)?.nearest_strike(spot)
+    }
+}
+
+impl OptifiClient {
+    /// Option chain of the cached markets.
+    pub fn option_chain(&self) -> OptionChain {
+        OptionChain::new(&self.account().markets)
+    }
+}
@@ -0,0 +1,569 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use solana_sdk::packet::PACKET_DATA_SIZE;
+
+use crate::client::{Market, OptifiClient};
+use crate::compute_budget::Operation;
+use crate::decoder::{OpenOrder, OpenOrdersView};
+use crate::error::OptifiClientError;
+use crate::instructions::transaction_size;
+use crate::prelude::*;
+use crate::send::TransactionOutcome;
+use crate::types::{Price, Quantity};
+
+/// One order of a `place_orders` call.
+#[derive(Clone, Copy, Debug)]
+pub struct OrderRequest {
+    pub side: OrderSide,
+    pub price: Price,
+    pub size: Quantity,
+    pub order_type: OrderType,
+}
+
+/// Orders packed into one transaction.
+///
+/// A batch without orders only holds market setup that does not fit with the first order, to
+/// be sent on its own before it.
+#[derive(Clone, Debug)]
+pub struct OrderBatch {
+    /// Indices of the requested orders in this transaction.
+    pub orders: Range<usize>,
+    /// Market setup instructions before the margin stress, see `ensure_market_ready`.
+    pub setup: usize,
+    /// Setup, margin stress, the orders' instructions and settlement, without a compute budget
+    /// instruction.
+    pub instructions: Vec<Instruction>,
+}
+
+/// Outcome of the transaction an order was sent in, or why it could not be sent, shared by its
+/// whole batch.
+pub type BatchOutcome = std::result::Result<TransactionOutcome, Arc<OptifiClientError>>;
+
+#[derive(Clone, Debug)]
+pub struct OrderResult {
+    pub request: OrderRequest,
+    /// Client order id the optifi program assigned to the order, see `match_client_order_ids`.
+    ///
+    /// `None` when the transaction did not confirm, the order did not rest on the book or the
+    /// open orders account could not be read around the transaction.
+    pub client_order_id: Option<u64>,
+    /// Index of the order's place instruction within its transaction.
+    pub instruction_index: u8,
+    pub outcome: BatchOutcome,
+}
+
+impl OrderResult {
+    pub fn is_placed(&self) -> bool {
+        is_confirmed(&self.outcome)
+    }
+
+    /// Whether this order's instruction is the one its transaction failed on.
+    pub fn caused_failure(&self) -> bool {
+        failed_on(&self.outcome, self.instruction_index)
+    }
+}
+
+/// Outcome of `place_order` or `replace_order`.
+#[derive(Clone, Debug)]
+pub struct PlacedOrder {
+    /// Client order id the optifi program assigned to the new order, to cancel it by.
+    ///
+    /// `None` when the transaction did not confirm, the order did not rest on the book, e.g.
+    /// because it filled immediately, or the open orders account could not be read after it.
+    pub client_order_id: Option<u64>,
+    pub outcome: TransactionOutcome,
+}
+
+#[derive(Clone, Debug)]
+pub struct CancelResult {
+    pub optifi_market: Pubkey,
+    pub side: OrderSide,
+    pub client_order_id: u64,
+    /// Index of the order's cancel instruction within its transaction.
+    pub instruction_index: u8,
+    pub outcome: BatchOutcome,
+}
+
+impl CancelResult {
+    pub fn is_cancelled(&self) -> bool {
+        is_confirmed(&self.outcome)
+    }
+
+    /// Whether this order's instruction is the one its transaction failed on.
+    pub fn caused_failure(&self) -> bool {
+        failed_on(&self.outcome, self.instruction_index)
+    }
+}
+
+fn is_confirmed(outcome: &BatchOutcome) -> bool {
+    outcome
+        .as_ref()
+        .map_or(false, TransactionOutcome::is_confirmed)
+}
+
+/// Whether the transaction of `outcome` failed on its instruction `instruction_index`.
+fn failed_on(outcome: &BatchOutcome, instruction_index: u8) -> bool {
+    outcome
+        .as_ref()
+        .ok()
+        .and_then(|outcome| outcome.failure.as_ref())
+        .map_or(false, |failure| {
+            failure.instruction_index == instruction_index
+        })
+}
+
+/// Matches the orders that appeared in the open orders account, `placed`, to the `requests`
+/// that placed them, by side and native price, the lowest client order id first.
+///
+/// Requests whose order did not rest on the book, e.g. because it filled, get `None`.
+pub fn match_client_order_ids(
+    asset: Asset,
+    requests: &[OrderRequest],
+    mut placed: Vec<OpenOrder>,
+) -> Vec<Option<u64>> {
+    placed.sort_by_key(|order| order.client_order_id);
+
+    requests
+        .iter()
+        .map(|request| {
+            let price = request.price.to_native(asset).ok()?;
+
+            let index = placed.iter().position(|order| {
+                matches!(
+                    (order.side, request.side),
+                    (OrderSide::Bid, OrderSide::Bid) | (OrderSide::Ask, OrderSide::Ask)
+                ) && (order.order_id >> 64) as u64 == price
+            })?;
+
+            Some(placed.remove(index).client_order_id)
+        })
+        .collect()
+}
+
+/// Whether `instructions` fit in one transaction, leaving room for a compute budget instruction.
+fn fits_in_transaction(instructions: &[Instruction], payer: &Pubkey) -> bool {
+    let mut with_budget = vec![ComputeBudgetInstruction::request_units(0, 0)];
+    with_budget.extend(instructions.iter().cloned());
+
+    transaction_size(&with_budget, payer) <= PACKET_DATA_SIZE
+}
+
+/// Packs `count` orders into as few transactions paid by `payer` as the packet size and
+/// `max_orders` per transaction allow, `build` giving the instructions of a range and `setup`
+/// going first in the first transaction, or in one of its own when it does not fit with the
+/// first order.
+pub fn pack_orders(
+    max_orders: usize,
+    payer: &Pubkey,
+    count: usize,
+    mut setup: Vec<Instruction>,
+    build: impl Fn(Range<usize>) -> std::result::Result<Vec<Instruction>, OptifiClientError>,
+) -> std::result::Result<Vec<OrderBatch>, OptifiClientError> {
+    let max_orders = max_orders.max(1);
+
+    let mut batches = vec![];
+    let mut start = 0;
+
+    while start < count {
+        let batch_instructions =
+            |setup: &[Instruction], range| -> std::result::Result<_, OptifiClientError> {
+                let mut instructions = setup.to_vec();
+                instructions.extend(build(range)?);
+                Ok(instructions)
+            };
+
+        let mut end = start + 1;
+        let mut instructions = batch_instructions(&setup, start..end)?;
+
+        if !setup.is_empty() && !fits_in_transaction(&instructions, payer) {
+            batches.push(OrderBatch {
+                orders: start..start,
+                setup: setup.len(),
+                instructions: std::mem::take(&mut setup),
+            });
+
+            instructions = batch_instructions(&setup, start..end)?;
+        }
+
+        while end < count && end - start < max_orders {
+            let packed = batch_instructions(&setup, start..end + 1)?;
+
+            if !fits_in_transaction(&packed, payer) {
+                break;
+            }
+
+            instructions = packed;
+            end += 1;
+        }
+
+        batches.push(OrderBatch {
+            orders: start..end,
+            setup: setup.len(),
+            instructions,
+        });
+
+        setup.clear();
+        start = end;
+    }
+
+    Ok(batches)
+}
+
+/// The `orders` whose client order id is not in `before`, those placed since it was read.
+pub fn new_open_orders(orders: Vec<OpenOrder>, before: &[u64]) -> Vec<OpenOrder> {
+    orders
+        .into_iter()
+        .filter(|order| !before.contains(&order.client_order_id))
+        .collect()
+}
+
+impl OptifiClient {
+    /// Orders resting in the user's open orders account of `market`, none if it does not exist.
+    pub fn load_user_open_orders(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<Vec<OpenOrder>, OptifiClientError> {
+        let (open_orders, ..) = get_serum_open_orders_account(
+            &self.optifi_exchange,
+            &self.user_account,
+            &market.optifi_market.serum_market,
+            &self.config.optifi_program_id,
+        );
+
+        let orders_account = match self.fetch_account(&open_orders) {
+            Ok(account) => account,
+            Err(OptifiClientError::AccountNotFound(_)) => return Ok(vec![]),
+            Err(err) => return Err(err),
+        };
+
+        Ok(OpenOrdersView::decode(&orders_account.data)?.orders())
+    }
+
+    /// Like `load_user_open_orders`, logging the error instead of returning it, for reads
+    /// around a transaction whose outcome must be reported regardless.
+    fn try_load_user_open_orders(&self, market: &Market) -> Option<Vec<OpenOrder>> {
+        self.load_user_open_orders(market)
+            .map_err(|err| {
+                log::warn!(
+                    "could not read the open orders on market {}: {}",
+                    market.optifi_market_key_data.optifi_market_pubkey,
+                    err
+                )
+            })
+            .ok()
+    }
+
+    /// Orders resting in the user's open orders account of `market` whose client order id is not
+    /// in `before`, `None` if they can not be read.
+    fn load_new_open_orders(&self, market: &Market, before: &[u64]) -> Option<Vec<OpenOrder>> {
+        Some(new_open_orders(
+            self.try_load_user_open_orders(market)?,
+            before,
+        ))
+    }
+
+    /// Client order ids of the orders resting in the user's open orders account of `market`.
+    pub fn load_client_order_ids(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<Vec<u64>, OptifiClientError> {
+        Ok(self
+            .load_user_open_orders(market)?
+            .iter()
+            .map(|order| order.client_order_id)
+            .collect())
+    }
+
+    /// Sends `instructions`, which place one order on `market`, and finds the client order id
+    /// the program gave it by diffing the open orders account before and after.
+    ///
+    /// Once sent, the outcome is returned even if the open orders account can not be read after
+    /// it, without a client order id. Orders placed concurrently on the same market by the same
+    /// user can be mistaken for it.
+    pub(crate) fn send_tracking_client_order_id(
+        &self,
+        market: &Market,
+        instructions: Vec<Instruction>,
+    ) -> std::result::Result<PlacedOrder, OptifiClientError> {
+        let before = self.load_client_order_ids(market)?;
+
+        let outcome = self.send_instructions(instructions)?;
+
+        let client_order_id = if outcome.is_confirmed() {
+            self.load_new_open_orders(market, &before)
+                .and_then(|placed| placed.first().map(|order| order.client_order_id))
+        } else {
+            None
+        };
+
+        Ok(PlacedOrder {
+            client_order_id,
+            outcome,
+        })
+    }
+
+    /// Sends the setup-only batches of `batches`, see `OrderBatch`, returning the others.
+    ///
+    /// Fails unless the setup confirms, since the orders need it.
+    pub(crate) fn send_setup_batches(
+        &self,
+        market: &Market,
+        batches: Vec<OrderBatch>,
+    ) -> std::result::Result<Vec<OrderBatch>, OptifiClientError> {
+        let (setup, batches): (Vec<_>, Vec<_>) = batches
+            .into_iter()
+            .partition(|batch| batch.orders.is_empty());
+
+        for batch in setup {
+            self.send_instructions(
+                self.with_compute_budget(Operation::Other, batch.instructions)?,
+            )?
+            .into_result()?;
+
+            self.mark_market_ready(market);
+        }
+
+        Ok(batches)
+    }
+
+    /// Sends `batch` with the compute budget of `operation`, returning its outcome, or why it
+    /// could not be sent, and the index of the first order's instruction, right after the setup
+    /// and margin stress.
+    fn send_order_batch(&self, operation: Operation, batch: OrderBatch) -> (BatchOutcome, usize) {
+        let len = batch.instructions.len();
+
+        match self.with_compute_budget(operation, batch.instructions) {
+            Ok(instructions) => {
+                let first_order = instructions.len() - len + batch.setup + 1;
+
+                (
+                    self.send_instructions(instructions).map_err(Arc::new),
+                    first_order,
+                )
+            }
+            Err(err) => (Err(Arc::new(err)), batch.setup + 1),
+        }
+    }
+
+    /// Validates `orders`, see `validate_orders`, then packs them into as few transactions as
+    /// the packet size and `config.max_orders_per_transaction` allow, keeping their order.
+    ///
+    /// The first transaction starts with the user's missing accounts on `market`, see
+    /// `ensure_market_ready`, or they come in a transaction of their own when they do not fit
+    /// with the first order.
+    pub fn place_orders_instructions(
+        &self,
+        market: &Market,
+        orders: &[OrderRequest],
+    ) -> std::result::Result<Vec<OrderBatch>, OptifiClientError> {
+        self.pack_place_orders(market, &self.validate_orders(market, orders)?)
+    }
+
+    pub(crate) fn pack_place_orders(
+        &self,
+        market: &Market,
+        orders: &[OrderRequest],
+    ) -> std::result::Result<Vec<OrderBatch>, OptifiClientError> {
+        let setup = self.order_setup_instructions(market)?;
+
+        let account = self.account();
+        let builder = self.instruction_builder(&account);
+
+        pack_orders(
+            self.config.max_orders_per_transaction,
+            &self.payer.pubkey(),
+            orders.len(),
+            setup,
+            |range| builder.place_orders(market, &orders[range]),
+        )
+    }
+
+    /// Places `orders` on `market` with one margin stress and settlement per transaction,
+    /// splitting them across transactions when they do not fit in one.
+    ///
+    /// Setup that does not fit with the first order is sent first and must confirm. The order
+    /// transactions are then sent in order; one that fails or can not be sent, e.g. on an RPC
+    /// error, does not stop the next ones, its orders carry the error instead. The open
+    /// orders account is read around each of them to report the client order ids the program
+    /// assigned, see `match_client_order_ids`; orders placed concurrently on the same market by
+    /// the same user can be mistaken for them. A failed read only leaves those ids out.
+    pub fn place_orders(
+        &self,
+        market: &Market,
+        orders: &[OrderRequest],
+    ) -> std::result::Result<Vec<OrderResult>, OptifiClientError> {
+        let orders = self.validate_orders(market, orders)?;
+
+        let asset = market.instrument_common.asset;
+
+        let mut results = vec![];
+
+        let batches = self.send_setup_batches(market, self.pack_place_orders(market, &orders)?)?;
+
+        for batch in batches {
+            let range = batch.orders.clone();
+
+            let before: Option<Vec<u64>> = self
+                .try_load_user_open_orders(market)
+                .map(|orders| orders.iter().map(|order| order.client_order_id).collect());
+
+            let (outcome, first_place) = self.send_order_batch(Operation::PlaceOrder, batch);
+
+            let placed = if is_confirmed(&outcome) {
+                self.mark_market_ready(market);
+
+                before.and_then(|before| self.load_new_open_orders(market, &before))
+            } else {
+                None
+            };
+
+            let client_order_ids = match placed {
+                Some(placed) => match_client_order_ids(asset, &orders[range.clone()], placed),
+                None => vec![None; range.len()],
+            };
+
+            for ((i, index), client_order_id) in range.enumerate().zip(client_order_ids) {
+                results.push(OrderResult {
+                    request: orders[index],
+                    client_order_id,
+                    instruction_index: (first_place + i) as u8,
+                    outcome: outcome.clone(),
+                });
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Packs cancels of `orders`, given as `(side, client_order_id)`, like
+    /// `place_orders_instructions`.
+    pub fn cancel_orders_instructions(
+        &self,
+        market: &Market,
+        orders: &[(OrderSide, u64)],
+    ) -> std::result::Result<Vec<OrderBatch>, OptifiClientError> {
+        let account = self.account();
+        let builder = self.instruction_builder(&account);
+
+        pack_orders(
+            self.config.max_orders_per_transaction,
+            &self.payer.pubkey(),
+            orders.len(),
+            vec![],
+            |range| builder.cancel_orders(market, &orders[range]),
+        )
+    }
+
+    /// Cancels `orders`, given as `(side, client_order_id)`, with one margin stress and
+    /// settlement per transaction.
+    ///
+    /// Transactions are sent in order; one that fails or can not be sent, e.g. on an RPC error,
+    /// does not stop the next ones, its orders carry the error instead.
+    pub fn cancel_orders(
+        &self,
+        market: &Market,
+        orders: &[(OrderSide, u64)],
+    ) -> std::result::Result<Vec<CancelResult>, OptifiClientError> {
+        let optifi_market = market.optifi_market_key_data.optifi_market_pubkey;
+
+        let mut results = vec![];
+
+        for batch in self.cancel_orders_instructions(market, orders)? {
+            let range = batch.orders.clone();
+
+            let (outcome, first_cancel) = self.send_order_batch(Operation::CancelOrder, batch);
+
+            for (i, index) in range.enumerate() {
+                let (side, client_order_id) = orders[index];
+
+                results.push(CancelResult {
+                    optifi_market,
+                    side,
+                    client_order_id,
+                    instruction_index: (first_cancel + i) as u8,
+                    outcome: outcome.clone(),
+                });
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Cancels every open order on every cached market, e.g. as a kill switch.
+    ///
+    /// Markets are cancelled one after the other, in batches. A market that fails, e.g. on an
+    /// RPC error, does not stop the next ones; each optifi market comes with its own results.
+    pub fn cancel_all_markets(
+        &self,
+    ) -> Vec<(
+        Pubkey,
+        std::result::Result<Vec<CancelResult>, OptifiClientError>,
+    )> {
+        self.account()
+            .markets
+            .iter()
+            .map(|market| {
+                (
+                    market.optifi_market_key_data.optifi_market_pubkey,
+                    self.cancel_all_order(market),
+                )
+            })
+            .collect()
+    }
+
+    /// Margin stress, cancel, place and settlement, without a compute budget instruction.
+    ///
+    /// The new order is validated first, see `validate_orders`.
+    pub fn replace_order_instructions(
+        &self,
+        market: &Market,
+        side: OrderSide,
+        old_client_order_id: u64,
+        new_price: Price,
+        new_size: Quantity,
+    ) -> std::result::Result<Vec<Instruction>, OptifiClientError> {
+        let order = self.validate_order(
+            market,
+            OrderRequest {
+                side,
+                price: new_price,
+                size: new_size,
+                order_type: OrderType::Limit,
+            },
+        )?;
+
+        self.instruction_builder(&self.account()).replace_order(
+            market,
+            side,
+            old_client_order_id,
+            order.price,
+            order.size,
+        )
+    }
+
+    /// Cancels `old_client_order_id` and places a limit order at `new_price` for `new_size` on
+    /// the same side in one transaction, so either both happen or neither does.
+    pub fn replace_order(
+        &self,
+        market: &Market,
+        side: OrderSide,
+        old_client_order_id: u64,
+        new_price: Price,
+        new_size: Quantity,
+    ) -> std::result::Result<PlacedOrder, OptifiClientError> {
+        self.send_tracking_client_order_id(
+            market,
+            self.with_compute_budget(
+                Operation::PlaceOrder,
+                self.replace_order_instructions(
+                    market,
+                    side,
+                    old_client_order_id,
+                    new_price,
+                    new_size,
+                )?,
+            )?,
+        )
+    }
+}
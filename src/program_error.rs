@@ -0,0 +1,446 @@
+use std::fmt;
+
+use anchor_lang::error::ErrorCode as AnchorErrorCode;
+use serum_dex::error::DexErrorCode;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+
+use crate::config::OptifiConfig;
+use crate::prelude::*;
+use crate::simulation::{parse_anchor_error, AnchorErrorLog};
+
+/// Anchor reserves custom error numbers below this one for the framework.
+pub const ANCHOR_ERROR_CODE_OFFSET: u32 = 6000;
+
+/// Anchor framework errors start at this number, serum dex errors are all below it.
+pub const ANCHOR_FRAMEWORK_ERROR_CODE_START: u32 = 100;
+
+/// Errors of the optifi program, in declaration order, numbered from `ANCHOR_ERROR_CODE_OFFSET`.
+pub const OPTIFI_ERRORS: [OptifiErrorCode; 58] = [
+    OptifiErrorCode::AccountCannotInit,
+    OptifiErrorCode::InvalidAccount,
+    OptifiErrorCode::InvalidMarginAccount,
+    OptifiErrorCode::UnauthorizedAccount,
+    OptifiErrorCode::InsufficientFund,
+    OptifiErrorCode::TokenTransferFailed,
+    OptifiErrorCode::UnauthorizedTokenVault,
+    OptifiErrorCode::InvalidPDA,
+    OptifiErrorCode::UuidMustBeExactly6Length,
+    OptifiErrorCode::NumericalOverflowError,
+    OptifiErrorCode::InsufficientMargin,
+    OptifiErrorCode::IncorrectCoinMint,
+    OptifiErrorCode::CannotRecordPnLBeforeMarketsExpired,
+    OptifiErrorCode::CannotSettleFundBeforeMarketsStopped,
+    OptifiErrorCode::CannotRecordPnLForStoppedMarket,
+    OptifiErrorCode::InstrumetIsDelisted,
+    OptifiErrorCode::IncorrectOracleAccount,
+    OptifiErrorCode::WrongState,
+    OptifiErrorCode::WrongInstrument,
+    OptifiErrorCode::NoEnoughOrdersInProposal,
+    OptifiErrorCode::CannotRemoveInstrumentForAMM,
+    OptifiErrorCode::DuplicateInstrumentForAMM,
+    OptifiErrorCode::UserNotInLiquidation,
+    OptifiErrorCode::UserNotInCancelOrder,
+    OptifiErrorCode::UserAlreadyInLiquidation,
+    OptifiErrorCode::InstrumentAlreadyRegisteredForLiquidation,
+    OptifiErrorCode::CannotPlaceOrdersInLiquidation,
+    OptifiErrorCode::CannotCancelOrdersInLiquidation,
+    OptifiErrorCode::PoolNotCentralUSDCPool,
+    OptifiErrorCode::InvalidSerumAuthority,
+    OptifiErrorCode::WithdrawRequestInvalid,
+    OptifiErrorCode::UserIsMarketMaker,
+    OptifiErrorCode::MMWithdrawNotInWindow,
+    OptifiErrorCode::WrongAsset,
+    OptifiErrorCode::TimeOut,
+    OptifiErrorCode::OrderFailed,
+    OptifiErrorCode::WrongFeeAccount,
+    OptifiErrorCode::InvalidMangoAccount,
+    OptifiErrorCode::InvalidMangoToken,
+    OptifiErrorCode::IncorrectOpenOrdersAccountsLength,
+    OptifiErrorCode::WrongPerpMarketIndex,
+    OptifiErrorCode::MismatchedAsset,
+    OptifiErrorCode::InstrumentExpired,
+    OptifiErrorCode::MismatchedDuration,
+    OptifiErrorCode::DontNeedGenerateNextInstrument,
+    OptifiErrorCode::InsufficientWithdrawableLPAmount,
+    OptifiErrorCode::WithdrawRequestQueueIsFull,
+    OptifiErrorCode::WithdrawRequestQueueIsEmpty,
+    OptifiErrorCode::MismatchedWithdrawUserId,
+    OptifiErrorCode::AmmWithdrawNotInWindow,
+    OptifiErrorCode::WithdrawWouldBreachAmmDelta,
+    OptifiErrorCode::NoOGNftFound,
+    OptifiErrorCode::InvalidOGNftVault,
+    OptifiErrorCode::InvalidOGNftMint,
+    OptifiErrorCode::UnauthorizedOperation,
+    OptifiErrorCode::InsufficientAmmCapacity,
+    OptifiErrorCode::CannotDepositMoreThanLimit,
+    OptifiErrorCode::MismatchedInstrument,
+];
+
+/// Errors of the anchor framework, see `anchor_lang::error::ErrorCode`.
+pub const ANCHOR_ERRORS: [AnchorErrorCode; 52] = [
+    AnchorErrorCode::InstructionMissing,
+    AnchorErrorCode::InstructionFallbackNotFound,
+    AnchorErrorCode::InstructionDidNotDeserialize,
+    AnchorErrorCode::InstructionDidNotSerialize,
+    AnchorErrorCode::IdlInstructionStub,
+    AnchorErrorCode::IdlInstructionInvalidProgram,
+    AnchorErrorCode::ConstraintMut,
+    AnchorErrorCode::ConstraintHasOne,
+    AnchorErrorCode::ConstraintSigner,
+    AnchorErrorCode::ConstraintRaw,
+    AnchorErrorCode::ConstraintOwner,
+    AnchorErrorCode::ConstraintRentExempt,
+    AnchorErrorCode::ConstraintSeeds,
+    AnchorErrorCode::ConstraintExecutable,
+    AnchorErrorCode::ConstraintState,
+    AnchorErrorCode::ConstraintAssociated,
+    AnchorErrorCode::ConstraintAssociatedInit,
+    AnchorErrorCode::ConstraintClose,
+    AnchorErrorCode::ConstraintAddress,
+    AnchorErrorCode::ConstraintZero,
+    AnchorErrorCode::ConstraintTokenMint,
+    AnchorErrorCode::ConstraintTokenOwner,
+    AnchorErrorCode::ConstraintMintMintAuthority,
+    AnchorErrorCode::ConstraintMintFreezeAuthority,
+    AnchorErrorCode::ConstraintMintDecimals,
+    AnchorErrorCode::ConstraintSpace,
+    AnchorErrorCode::RequireViolated,
+    AnchorErrorCode::RequireEqViolated,
+    AnchorErrorCode::RequireKeysEqViolated,
+    AnchorErrorCode::RequireNeqViolated,
+    AnchorErrorCode::RequireKeysNeqViolated,
+    AnchorErrorCode::RequireGtViolated,
+    AnchorErrorCode::RequireGteViolated,
+    AnchorErrorCode::AccountDiscriminatorAlreadySet,
+    AnchorErrorCode::AccountDiscriminatorNotFound,
+    AnchorErrorCode::AccountDiscriminatorMismatch,
+    AnchorErrorCode::AccountDidNotDeserialize,
+    AnchorErrorCode::AccountDidNotSerialize,
+    AnchorErrorCode::AccountNotEnoughKeys,
+    AnchorErrorCode::AccountNotMutable,
+    AnchorErrorCode::AccountOwnedByWrongProgram,
+    AnchorErrorCode::InvalidProgramId,
+    AnchorErrorCode::InvalidProgramExecutable,
+    AnchorErrorCode::AccountNotSigner,
+    AnchorErrorCode::AccountNotSystemOwned,
+    AnchorErrorCode::AccountNotInitialized,
+    AnchorErrorCode::AccountNotProgramData,
+    AnchorErrorCode::AccountNotAssociatedTokenAccount,
+    AnchorErrorCode::AccountSysvarMismatch,
+    AnchorErrorCode::StateInvalidAddress,
+    AnchorErrorCode::DeclaredProgramIdMismatch,
+    AnchorErrorCode::Deprecated,
+];
+
+/// Errors of the optifi usdc program, numbered from `ANCHOR_ERROR_CODE_OFFSET`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum OptifiUsdcErrorCode {
+    #[error("the wrap/unwrap is currently locked")]
+    Locked,
+}
+
+impl OptifiUsdcErrorCode {
+    pub fn code(&self) -> u32 {
+        ANCHOR_ERROR_CODE_OFFSET + *self as u32
+    }
+
+    pub fn from_code(code: u32) -> Option<Self> {
+        [OptifiUsdcErrorCode::Locked]
+            .into_iter()
+            .find(|error| error.code() == code)
+    }
+}
+
+pub fn optifi_error(code: u32) -> Option<OptifiErrorCode> {
+    OPTIFI_ERRORS
+        .into_iter()
+        .find(|error| u32::from(*error) == code)
+}
+
+pub fn anchor_error(code: u32) -> Option<AnchorErrorCode> {
+    ANCHOR_ERRORS
+        .into_iter()
+        .find(|error| u32::from(*error) == code)
+}
+
+/// Why an instruction failed.
+///
+/// Custom errors are attributed to the program that raised them and named from its error table,
+/// `log` adds what the program logged about it when logs are available.
+#[derive(Clone, Debug)]
+pub enum FailureReason {
+    /// Error declared by the optifi program, e.g. insufficient margin or a stopped market.
+    Optifi {
+        error: OptifiErrorCode,
+        log: Option<AnchorErrorLog>,
+    },
+    /// Error declared by the optifi usdc program.
+    OptifiUsdc {
+        error: OptifiUsdcErrorCode,
+        log: Option<AnchorErrorLog>,
+    },
+    /// Anchor framework error, e.g. a failed account constraint.
+    Anchor {
+        error: AnchorErrorCode,
+        log: Option<AnchorErrorLog>,
+    },
+    /// Serum dex error raised in the nested CPI, e.g. a lot size violation.
+    SerumDex { code: u32, name: String },
+    /// Custom error missing from the error tables, e.g. of another program.
+    Custom {
+        code: u32,
+        log: Option<AnchorErrorLog>,
+    },
+    /// Runtime error, e.g. missing signature or insufficient funds.
+    Instruction(InstructionError),
+}
+
+impl FailureReason {
+    /// Custom error number, `None` for runtime errors.
+    pub fn code(&self) -> Option<u32> {
+        match self {
+            FailureReason::Optifi { error, .. } => Some(u32::from(*error)),
+            FailureReason::OptifiUsdc { error, .. } => Some(error.code()),
+            FailureReason::Anchor { error, .. } => Some(u32::from(*error)),
+            FailureReason::SerumDex { code, .. } | FailureReason::Custom { code, .. } => {
+                Some(*code)
+            }
+            FailureReason::Instruction(..) => None,
+        }
+    }
+}
+
+// The anchor error codes do not implement `PartialEq`, they are compared by number.
+impl PartialEq for FailureReason {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                FailureReason::Optifi { error, log },
+                FailureReason::Optifi {
+                    error: other_error,
+                    log: other_log,
+                },
+            ) => u32::from(*error) == u32::from(*other_error) && log == other_log,
+            (
+                FailureReason::OptifiUsdc { error, log },
+                FailureReason::OptifiUsdc {
+                    error: other_error,
+                    log: other_log,
+                },
+            ) => error == other_error && log == other_log,
+            (
+                FailureReason::Anchor { error, log },
+                FailureReason::Anchor {
+                    error: other_error,
+                    log: other_log,
+                },
+            ) => u32::from(*error) == u32::from(*other_error) && log == other_log,
+            (
+                FailureReason::SerumDex { code, name },
+                FailureReason::SerumDex {
+                    code: other_code,
+                    name: other_name,
+                },
+            ) => code == other_code && name == other_name,
+            (
+                FailureReason::Custom { code, log },
+                FailureReason::Custom {
+                    code: other_code,
+                    log: other_log,
+                },
+            ) => code == other_code && log == other_log,
+            (FailureReason::Instruction(error), FailureReason::Instruction(other_error)) => {
+                error == other_error
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Decoded failure of a transaction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProgramFailure {
+    /// Index of the failing instruction within the transaction, compute budget included.
+    pub instruction_index: u8,
+    /// Innermost program that failed, if known from the logs or the failing instruction.
+    pub program_id: Option<Pubkey>,
+    pub reason: FailureReason,
+}
+
+/// Writes `program error <name> (<code>): <message>`, with the logged message when it adds to it.
+fn write_named_error(
+    f: &mut fmt::Formatter<'_>,
+    program: &str,
+    name: &str,
+    code: u32,
+    message: &str,
+    log: &Option<AnchorErrorLog>,
+) -> fmt::Result {
+    write!(f, "{} error {} ({}): {}", program, name, code, message)?;
+
+    match log {
+        Some(log) if log.message != message => write!(f, " ({})", log.message),
+        _ => Ok(()),
+    }
+}
+
+impl fmt::Display for FailureReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailureReason::Optifi { error, log } => write_named_error(
+                f,
+                "optifi",
+                &error.name(),
+                u32::from(*error),
+                &error.to_string(),
+                log,
+            ),
+            FailureReason::OptifiUsdc { error, log } => write_named_error(
+                f,
+                "optifi usdc",
+                &format!("{:?}", error),
+                error.code(),
+                &error.to_string(),
+                log,
+            ),
+            FailureReason::Anchor { error, log } => write_named_error(
+                f,
+                "anchor",
+                &error.name(),
+                u32::from(*error),
+                &error.to_string(),
+                log,
+            ),
+            FailureReason::SerumDex { code, name } => {
+                write!(f, "serum dex error {} ({})", name, code)
+            }
+            FailureReason::Custom { code, log } => {
+                write!(f, "custom program error {:#x}", code)?;
+
+                match log {
+                    Some(log) => write!(f, ": {}: {}", log.code, log.message),
+                    None => Ok(()),
+                }
+            }
+            FailureReason::Instruction(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl fmt::Display for ProgramFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "instruction {} failed", self.instruction_index)?;
+
+        if let Some(program_id) = self.program_id {
+            write!(f, " in program {}", program_id)?;
+        }
+
+        write!(f, ": {}", self.reason)
+    }
+}
+
+/// Innermost program that failed, from the first `Program <id> failed: ...` log.
+pub fn parse_failed_program(logs: &[String]) -> Option<Pubkey> {
+    logs.iter().find_map(|log| {
+        let mut words = log.split_whitespace();
+
+        match (words.next(), words.next(), words.next()) {
+            (Some("Program"), Some(program_id), Some("failed:")) => {
+                Pubkey::from_str(program_id).ok()
+            }
+            _ => None,
+        }
+    })
+}
+
+/// Decodes `error` of a transaction made of `instructions`.
+///
+/// Custom errors are attributed to the innermost failing program from `logs`. Without logs,
+/// they are attributed to the failing instruction's program, except for codes below the anchor
+/// framework range in an optifi instruction, which come from its serum CPI. They are then named
+/// from that program's error table, or reported as `FailureReason::Custom` when missing from it.
+///
+/// Returns `None` for errors not raised by an instruction, such as an expired blockhash.
+pub fn decode_failure(
+    config: &OptifiConfig,
+    instructions: &[Instruction],
+    error: &TransactionError,
+    logs: &[String],
+) -> Option<ProgramFailure> {
+    let (instruction_index, error) = match error {
+        TransactionError::InstructionError(index, error) => (*index, error),
+        _ => return None,
+    };
+
+    let instruction_program = instructions
+        .get(instruction_index as usize)
+        .map(|ix| ix.program_id);
+
+    let code = match error {
+        InstructionError::Custom(code) => *code,
+        error => {
+            return Some(ProgramFailure {
+                instruction_index,
+                program_id: parse_failed_program(logs).or(instruction_program),
+                reason: FailureReason::Instruction(error.clone()),
+            })
+        }
+    };
+
+    let program_id = parse_failed_program(logs).or_else(|| match instruction_program {
+        Some(program_id)
+            if program_id == config.optifi_program_id
+                && code < ANCHOR_FRAMEWORK_ERROR_CODE_START =>
+        {
+            Some(config.serum_dex_program_id)
+        }
+        program_id => program_id,
+    });
+
+    let log = parse_anchor_error(logs).filter(|anchor_error| anchor_error.number == code);
+
+    let is_anchor_program = program_id.map_or(false, |program_id| {
+        program_id == config.optifi_program_id || program_id == config.optifi_usdc_program_id
+    });
+
+    let named = match program_id {
+        Some(program_id) if program_id == config.serum_dex_program_id => {
+            Some(FailureReason::SerumDex {
+                code,
+                name: format!("{:?}", DexErrorCode::from(code)),
+            })
+        }
+        Some(program_id)
+            if program_id == config.optifi_program_id && code >= ANCHOR_ERROR_CODE_OFFSET =>
+        {
+            optifi_error(code).map(|error| FailureReason::Optifi {
+                error,
+                log: log.clone(),
+            })
+        }
+        Some(program_id)
+            if program_id == config.optifi_usdc_program_id && code >= ANCHOR_ERROR_CODE_OFFSET =>
+        {
+            OptifiUsdcErrorCode::from_code(code).map(|error| FailureReason::OptifiUsdc {
+                error,
+                log: log.clone(),
+            })
+        }
+        _ if code < ANCHOR_ERROR_CODE_OFFSET && (is_anchor_program || log.is_some()) => {
+            anchor_error(code).map(|error| FailureReason::Anchor {
+                error,
+                log: log.clone(),
+            })
+        }
+        _ => None,
+    };
+
+    let reason = named.unwrap_or(FailureReason::Custom { code, log });
+
+    Some(ProgramFailure {
+        instruction_index,
+        program_id,
+        reason,
+    })
+}
@@ -0,0 +1,447 @@
+use std::thread::sleep;
+use std::time::Duration;
+
+use solana_client::client_error::{ClientError as RpcClientError, ClientErrorKind};
+use solana_client::rpc_config::{RpcSendTransactionConfig, RpcTransactionConfig};
+use solana_client::rpc_custom_error::{
+    JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE, JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY,
+};
+use solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+use solana_sdk::transaction::{Transaction, TransactionError};
+use solana_transaction_status::{EncodedConfirmedTransaction, TransactionStatus};
+
+use crate::client::OptifiClient;
+use crate::config::OptifiConfig;
+use crate::error::OptifiClientError;
+use crate::instructions::requires_signature;
+use crate::prelude::*;
+use crate::program_error::{decode_failure, ProgramFailure};
+
+/// How transactions are retried, re-signed and confirmed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Times a transaction is re-signed with a fresh blockhash once its blockhash expired.
+    pub max_resigns: u32,
+    /// Retries of an RPC call failing with a transient error.
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Interval between signature status polls while confirming.
+    pub poll_interval: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_resigns: 3,
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(4),
+            poll_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `retry`, doubling from `initial_backoff` up to `max_backoff`.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .checked_mul(2u32.saturating_pow(retry))
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutcomeStatus {
+    /// Landed without error and reached the send commitment.
+    Confirmed,
+    /// Landed and reached the send commitment, but failed. Fees were paid.
+    Failed,
+    /// Rejected by the preflight simulation, never submitted.
+    Rejected,
+    /// Never landed before the blockhash of the last attempt expired.
+    Expired,
+}
+
+/// Result of sending a transaction through `OptifiClient::send_and_confirm`.
+#[derive(Clone, Debug)]
+pub struct TransactionOutcome {
+    /// Signature of the last attempt.
+    pub signature: Signature,
+    /// Slot the transaction landed in.
+    pub slot: Option<u64>,
+    pub status: OutcomeStatus,
+    pub error: Option<TransactionError>,
+    /// `error` decoded, for transactions that failed or were rejected in an instruction.
+    pub failure: Option<ProgramFailure>,
+    /// Times the transaction was signed, 1 unless its blockhash expired.
+    pub attempts: u32,
+}
+
+impl TransactionOutcome {
+    pub fn is_confirmed(&self) -> bool {
+        self.status == OutcomeStatus::Confirmed
+    }
+
+    /// Whether the transaction made it into a block, successfully or not.
+    pub fn landed(&self) -> bool {
+        matches!(
+            self.status,
+            OutcomeStatus::Confirmed | OutcomeStatus::Failed
+        )
+    }
+
+    /// The outcome if confirmed, an error describing why not otherwise.
+    pub fn into_result(self) -> std::result::Result<Self, OptifiClientError> {
+        if self.is_confirmed() {
+            return Ok(self);
+        }
+
+        Err(match self.failure {
+            Some(failure) => OptifiClientError::Program(failure),
+            None => OptifiClientError::Transaction {
+                signature: self.signature,
+                status: self.status,
+                error: self.error,
+            },
+        })
+    }
+}
+
+/// Whether `err` is worth retrying: connection failures, timeouts, rate limits and
+/// unhealthy nodes.
+pub fn is_transient(err: &RpcClientError) -> bool {
+    match err.kind() {
+        ClientErrorKind::Io(_) => true,
+        ClientErrorKind::Reqwest(err) => {
+            err.is_timeout()
+                || err.is_connect()
+                || err.status().map_or(false, |status| {
+                    status.as_u16() == 429 || status.is_server_error()
+                })
+        }
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+            *code == JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY
+                || *code == JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE
+        }
+        _ => false,
+    }
+}
+
+/// Logs of the preflight simulation that rejected a transaction.
+pub fn preflight_logs(err: &RpcClientError) -> Vec<String> {
+    match err.kind() {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            data: RpcResponseErrorData::SendTransactionPreflightFailure(result),
+            ..
+        }) => result.logs.clone().unwrap_or_default(),
+        _ => vec![],
+    }
+}
+
+/// Transaction error the preflight simulation rejected a transaction with.
+pub fn preflight_error(err: &RpcClientError) -> Option<TransactionError> {
+    match err.kind() {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            data: RpcResponseErrorData::SendTransactionPreflightFailure(result),
+            ..
+        }) => result.err.clone(),
+        ClientErrorKind::TransactionError(err) => Some(err.clone()),
+        _ => None,
+    }
+}
+
+/// Where a sent transaction stands once it reached the send commitment or expired.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Confirmation {
+    /// Landed in `slot`, with the error it failed with, if any.
+    Landed {
+        slot: u64,
+        error: Option<TransactionError>,
+    },
+    /// The block height passed the last valid height of its blockhash without it landing.
+    Expired,
+}
+
+impl Confirmation {
+    /// Whether the transaction landed but failed, so its logs are worth fetching.
+    pub fn failed(&self) -> bool {
+        matches!(self, Confirmation::Landed { error: Some(_), .. })
+    }
+}
+
+/// Config `send_and_confirm` sends transactions with, preflight at the read commitment.
+pub fn send_config(config: &OptifiConfig) -> RpcSendTransactionConfig {
+    RpcSendTransactionConfig {
+        preflight_commitment: Some(config.read_commitment.commitment),
+        ..RpcSendTransactionConfig::default()
+    }
+}
+
+/// Config to fetch a landed transaction with, at least confirmed as `getTransaction` requires.
+pub fn transaction_logs_config(config: &OptifiConfig) -> RpcTransactionConfig {
+    let commitment = if config.send_commitment.is_at_least_confirmed() {
+        config.send_commitment
+    } else {
+        CommitmentConfig::confirmed()
+    };
+
+    RpcTransactionConfig {
+        commitment: Some(commitment),
+        ..RpcTransactionConfig::default()
+    }
+}
+
+/// Logs of a fetched transaction, empty if the node did not record them.
+pub fn transaction_logs(transaction: EncodedConfirmedTransaction) -> Vec<String> {
+    transaction
+        .transaction
+        .meta
+        .and_then(|meta| meta.log_messages)
+        .unwrap_or_default()
+}
+
+/// Signers of a transaction of `instructions` paid by `payer`: `owner` too when it is another
+/// wallet one of them requires the signature of, then `signers`.
+pub fn transaction_signers<'a>(
+    payer: &'a dyn Signer,
+    owner: &'a dyn Signer,
+    instructions: &[Instruction],
+    signers: &[&'a dyn Signer],
+) -> Vec<&'a dyn Signer> {
+    let mut all_signers = vec![payer];
+
+    if owner.pubkey() != payer.pubkey() && requires_signature(instructions, &owner.pubkey()) {
+        all_signers.push(owner);
+    }
+
+    all_signers.extend(signers);
+
+    all_signers
+}
+
+/// Classifies one poll of a transaction's `status`, read after `block_height`: `None` while it
+/// has neither reached `commitment` nor expired.
+pub fn poll_confirmation(
+    status: Option<TransactionStatus>,
+    commitment: CommitmentConfig,
+    block_height: u64,
+    last_valid_block_height: u64,
+) -> Option<Confirmation> {
+    match status {
+        Some(status) if status.satisfies_commitment(commitment) => Some(Confirmation::Landed {
+            slot: status.slot,
+            error: status.err,
+        }),
+        None if block_height > last_valid_block_height => Some(Confirmation::Expired),
+        _ => None,
+    }
+}
+
+/// Outcome of attempt `attempts` of `signature`, of `instructions`, whose send failed with
+/// `err`: `None` to re-sign it with a fresh blockhash, the outcome when the preflight
+/// simulation rejected it.
+///
+/// Any other error is `OptifiClientError::Unconfirmed`, as the transaction may still land.
+pub fn send_failed(
+    config: &OptifiConfig,
+    instructions: &[Instruction],
+    signature: Signature,
+    attempts: u32,
+    err: RpcClientError,
+) -> std::result::Result<Option<TransactionOutcome>, OptifiClientError> {
+    match preflight_error(&err) {
+        Some(TransactionError::BlockhashNotFound)
+            if attempts <= config.retry_policy.max_resigns =>
+        {
+            Ok(None)
+        }
+        Some(error) => Ok(Some(TransactionOutcome {
+            signature,
+            slot: None,
+            status: OutcomeStatus::Rejected,
+            failure: decode_failure(config, instructions, &error, &preflight_logs(&err)),
+            error: Some(error),
+            attempts,
+        })),
+        None => Err(OptifiClientError::Unconfirmed {
+            signature,
+            source: Box::new(err.into()),
+        }),
+    }
+}
+
+/// Outcome of attempt `attempts` of `signature`, of `instructions`, once `confirmation` is
+/// known: `None` to re-sign an expired transaction while re-signs are left.
+///
+/// `logs` are those of a failed transaction, see `Confirmation::failed`.
+pub fn confirmed_outcome(
+    config: &OptifiConfig,
+    instructions: &[Instruction],
+    signature: Signature,
+    attempts: u32,
+    confirmation: Confirmation,
+    logs: &[String],
+) -> Option<TransactionOutcome> {
+    match confirmation {
+        Confirmation::Landed { slot, error: None } => Some(TransactionOutcome {
+            signature,
+            slot: Some(slot),
+            status: OutcomeStatus::Confirmed,
+            error: None,
+            failure: None,
+            attempts,
+        }),
+        Confirmation::Landed {
+            slot,
+            error: Some(error),
+        } => Some(TransactionOutcome {
+            signature,
+            slot: Some(slot),
+            status: OutcomeStatus::Failed,
+            failure: decode_failure(config, instructions, &error, logs),
+            error: Some(error),
+            attempts,
+        }),
+        Confirmation::Expired if attempts <= config.retry_policy.max_resigns => None,
+        Confirmation::Expired => Some(TransactionOutcome {
+            signature,
+            slot: None,
+            status: OutcomeStatus::Expired,
+            error: None,
+            failure: None,
+            attempts,
+        }),
+    }
+}
+
+impl OptifiClient {
+    /// Calls `request` until it succeeds or fails with a non transient error, backing off
+    /// between attempts.
+    fn retry_transient<T>(
+        &self,
+        mut request: impl FnMut() -> std::result::Result<T, RpcClientError>,
+    ) -> std::result::Result<T, RpcClientError> {
+        let policy = self.config.retry_policy;
+        let mut retry = 0;
+
+        loop {
+            match request() {
+                Err(err) if retry < policy.max_retries && is_transient(&err) => {
+                    sleep(policy.backoff(retry));
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Sends `instructions` in one transaction paid by `payer`, also signed by `owner` when one
+    /// of them requires it and by `signers`, and waits for the send commitment.
+    ///
+    /// The transaction is re-signed with a fresh blockhash when it expires before landing.
+    /// Failed and rejected transactions are reported in the outcome rather than as an error.
+    /// Once signed, a transaction whose send or confirmation fails is reported as
+    /// `OptifiClientError::Unconfirmed` with its signature, as it may still land.
+    pub fn send_and_confirm(
+        &self,
+        instructions: &[Instruction],
+        signers: &[&dyn Signer],
+    ) -> std::result::Result<TransactionOutcome, OptifiClientError> {
+        let all_signers = transaction_signers(
+            self.payer.as_ref(),
+            self.owner.as_ref(),
+            instructions,
+            signers,
+        );
+
+        let mut attempts = 0;
+
+        loop {
+            attempts += 1;
+
+            let (blockhash, last_valid_block_height) = self.retry_transient(|| {
+                self.rpc
+                    .get_latest_blockhash_with_commitment(self.config.send_commitment)
+            })?;
+
+            let mut tx = Transaction::new_with_payer(instructions, Some(&self.payer.pubkey()));
+            tx.try_sign(&all_signers, blockhash)?;
+
+            let signature = tx.signatures[0];
+
+            let sent = self.retry_transient(|| {
+                self.rpc
+                    .send_transaction_with_config(&tx, send_config(&self.config))
+            });
+
+            if let Err(err) = sent {
+                match send_failed(&self.config, instructions, signature, attempts, err)? {
+                    Some(outcome) => return Ok(outcome),
+                    None => continue,
+                }
+            }
+
+            let confirmation =
+                self.confirm(&signature, last_valid_block_height)
+                    .map_err(|err| OptifiClientError::Unconfirmed {
+                        signature,
+                        source: Box::new(err),
+                    })?;
+
+            let logs = if confirmation.failed() {
+                self.transaction_logs(&signature)
+            } else {
+                vec![]
+            };
+
+            if let Some(outcome) = confirmed_outcome(
+                &self.config,
+                instructions,
+                signature,
+                attempts,
+                confirmation,
+                &logs,
+            ) {
+                return Ok(outcome);
+            }
+        }
+    }
+
+    /// Logs of a landed transaction, empty if they can not be fetched (yet).
+    fn transaction_logs(&self, signature: &Signature) -> Vec<String> {
+        self.retry_transient(|| {
+            self.rpc
+                .get_transaction_with_config(signature, transaction_logs_config(&self.config))
+        })
+        .map(transaction_logs)
+        .unwrap_or_default()
+    }
+
+    /// Polls the status of `signature` until it reaches the send commitment or the block height
+    /// passed `last_valid_block_height` without it.
+    fn confirm(
+        &self,
+        signature: &Signature,
+        last_valid_block_height: u64,
+    ) -> std::result::Result<Confirmation, OptifiClientError> {
+        let commitment = self.config.send_commitment;
+
+        loop {
+            // Read the height before the status, so a missing status past it means expired.
+            let block_height =
+                self.retry_transient(|| self.rpc.get_block_height_with_commitment(commitment))?;
+
+            let status = self
+                .retry_transient(|| self.rpc.get_signature_statuses(&[*signature]))?
+                .value
+                .pop()
+                .flatten();
+
+            match poll_confirmation(status, commitment, block_height, last_valid_block_height) {
+                Some(confirmation) => return Ok(confirmation),
+                None => sleep(self.config.retry_policy.poll_interval),
+            }
+        }
+    }
+}
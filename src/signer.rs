@@ -0,0 +1,130 @@
+#[cfg(unix)]
+use std::io::{BufRead, BufReader, Write};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+#[cfg(unix)]
+use std::path::PathBuf;
+
+use solana_sdk::signer::SignerError;
+
+use crate::error::OptifiClientError;
+use crate::prelude::*;
+
+/// Reads a keypair from its 64 secret key bytes.
+pub fn keypair_from_bytes(bytes: &[u8]) -> std::result::Result<Keypair, OptifiClientError> {
+    Keypair::from_bytes(bytes).map_err(|err| OptifiClientError::Keypair(err.to_string()))
+}
+
+/// Reads a keypair from an environment variable holding either the `solana-keygen` JSON byte
+/// array or a base58 encoded secret key.
+pub fn keypair_from_env(var: &str) -> std::result::Result<Keypair, OptifiClientError> {
+    let value = std::env::var(var)
+        .map_err(|err| OptifiClientError::Keypair(format!("{}: {}", var, err)))?;
+
+    let value = value.trim();
+
+    let bytes: Vec<u8> = if value.starts_with('[') {
+        serde_json::from_str(value)
+            .map_err(|err| OptifiClientError::Keypair(format!("{}: {}", var, err)))?
+    } else {
+        solana_sdk::bs58::decode(value)
+            .into_vec()
+            .map_err(|err| OptifiClientError::Keypair(format!("{}: {}", var, err)))?
+    };
+
+    keypair_from_bytes(&bytes)
+}
+
+enum RemoteEndpoint {
+    Http(reqwest::blocking::Client, String),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+/// Signer that delegates signing to an external process.
+///
+/// Each request is the JSON object `{"pubkey": <base58>, "message": <base58>}`, answered with
+/// `{"signature": <base58>}`. Over HTTP it is POSTed to the endpoint; over a Unix socket it is
+/// written as a single line and the response is read back as a single line.
+pub struct RemoteSigner {
+    pubkey: Pubkey,
+    endpoint: RemoteEndpoint,
+}
+
+impl RemoteSigner {
+    pub fn http(url: impl Into<String>, pubkey: Pubkey) -> Self {
+        Self {
+            pubkey,
+            endpoint: RemoteEndpoint::Http(reqwest::blocking::Client::new(), url.into()),
+        }
+    }
+
+    #[cfg(unix)]
+    pub fn unix(path: impl Into<PathBuf>, pubkey: Pubkey) -> Self {
+        Self {
+            pubkey,
+            endpoint: RemoteEndpoint::Unix(path.into()),
+        }
+    }
+
+    fn request_signature(&self, message: &[u8]) -> std::result::Result<Signature, String> {
+        let request = json!({
+            "pubkey": self.pubkey.to_string(),
+            "message": solana_sdk::bs58::encode(message).into_string(),
+        });
+
+        let response: Value = match &self.endpoint {
+            RemoteEndpoint::Http(client, url) => client
+                .post(url)
+                .json(&request)
+                .send()
+                .and_then(|response| response.error_for_status())
+                .and_then(|response| response.json())
+                .map_err(|err| err.to_string())?,
+            #[cfg(unix)]
+            RemoteEndpoint::Unix(path) => {
+                let mut stream = UnixStream::connect(path).map_err(|err| err.to_string())?;
+
+                writeln!(stream, "{}", request).map_err(|err| err.to_string())?;
+
+                let mut line = String::new();
+                BufReader::new(stream)
+                    .read_line(&mut line)
+                    .map_err(|err| err.to_string())?;
+
+                serde_json::from_str(&line).map_err(|err| err.to_string())?
+            }
+        };
+
+        let signature = response["signature"]
+            .as_str()
+            .ok_or_else(|| format!("missing signature in response: {}", response))?;
+
+        Signature::from_str(signature).map_err(|err| err.to_string())
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn try_pubkey(&self) -> std::result::Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> std::result::Result<Signature, SignerError> {
+        let signature = self
+            .request_signature(message)
+            .map_err(SignerError::Connection)?;
+
+        if !signature.verify(self.pubkey.as_ref(), message) {
+            return Err(SignerError::Custom(format!(
+                "remote signature does not match {}",
+                self.pubkey
+            )));
+        }
+
+        Ok(signature)
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,352 @@
+use std::ops::Range;
+
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_sdk::transaction::{Transaction, TransactionError};
+
+use crate::client::{Market, OptifiClient};
+use crate::compute_budget::Operation;
+use crate::config::OptifiConfig;
+use crate::error::OptifiClientError;
+use crate::orders::{OrderBatch, OrderRequest};
+use crate::prelude::*;
+use crate::program_error::{decode_failure, ProgramFailure};
+use crate::types::{Price, Quantity, UsdcAmount};
+
+/// Error an anchor program logged before failing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnchorErrorLog {
+    pub code: String,
+    pub number: u32,
+    pub message: String,
+}
+
+/// Outcome of `simulateTransaction` for an instruction sequence that was not submitted.
+#[derive(Clone, Debug)]
+pub struct SimulationResult {
+    /// Slot the simulation ran against.
+    pub slot: u64,
+    pub err: Option<TransactionError>,
+    pub logs: Vec<String>,
+    /// Compute units consumed by the top level instructions.
+    pub units_consumed: u64,
+    pub anchor_error: Option<AnchorErrorLog>,
+    /// `err` decoded, if an instruction failed.
+    pub failure: Option<ProgramFailure>,
+}
+
+impl SimulationResult {
+    pub fn is_ok(&self) -> bool {
+        self.err.is_none()
+    }
+}
+
+/// Simulation of one transaction of a batched call such as `place_orders`.
+#[derive(Clone, Debug)]
+pub struct BatchSimulation {
+    /// Indices of the requested orders in this transaction.
+    pub orders: Range<usize>,
+    pub simulation: SimulationResult,
+}
+
+/// Sums the compute units consumed by top level instructions, as reported in `logs`.
+pub fn parse_units_consumed(logs: &[String]) -> u64 {
+    let mut depths: Vec<usize> = vec![];
+    let mut units_consumed = 0;
+
+    for log in logs {
+        let mut words = log.split_whitespace();
+
+        if words.next() != Some("Program") {
+            continue;
+        }
+
+        let _program_id = words.next();
+
+        match words.next() {
+            Some("invoke") => {
+                let depth = words
+                    .next()
+                    .and_then(|depth| depth.trim_matches(|c| c == '[' || c == ']').parse().ok())
+                    .unwrap_or(depths.len() + 1);
+                depths.push(depth);
+            }
+            Some("consumed") => {
+                if depths.last() == Some(&1) {
+                    units_consumed += words
+                        .next()
+                        .and_then(|units| units.parse::<u64>().ok())
+                        .unwrap_or(0);
+                }
+            }
+            Some("success") | Some("failed:") => {
+                depths.pop();
+            }
+            _ => {}
+        }
+    }
+
+    units_consumed
+}
+
+/// Parses the last `AnchorError ... Error Code: X. Error Number: N. Error Message: M.` log.
+pub fn parse_anchor_error(logs: &[String]) -> Option<AnchorErrorLog> {
+    logs.iter().rev().find_map(|log| {
+        let log = &log[log.find("AnchorError")?..];
+
+        let code = log.split("Error Code: ").nth(1)?.split(". ").next()?;
+
+        let number = log
+            .split("Error Number: ")
+            .nth(1)?
+            .split(". ")
+            .next()?
+            .parse()
+            .ok()?;
+
+        let message = log.split("Error Message: ").nth(1)?.trim_end_matches('.');
+
+        Some(AnchorErrorLog {
+            code: code.to_owned(),
+            number,
+            message: message.to_owned(),
+        })
+    })
+}
+
+/// Config `simulate_instructions` simulates with: unsigned, at the read commitment.
+pub fn simulation_config(config: &OptifiConfig) -> RpcSimulateTransactionConfig {
+    RpcSimulateTransactionConfig {
+        sig_verify: false,
+        commitment: Some(config.read_commitment),
+        ..RpcSimulateTransactionConfig::default()
+    }
+}
+
+/// Reads the simulation `response` of `instructions`, decoding the instruction that failed.
+pub fn simulation_result(
+    config: &OptifiConfig,
+    instructions: &[Instruction],
+    response: Response<RpcSimulateTransactionResult>,
+) -> SimulationResult {
+    let logs = response.value.logs.unwrap_or_default();
+
+    let failure = response
+        .value
+        .err
+        .as_ref()
+        .and_then(|err| decode_failure(config, instructions, err, &logs));
+
+    SimulationResult {
+        slot: response.context.slot,
+        err: response.value.err,
+        units_consumed: parse_units_consumed(&logs),
+        anchor_error: parse_anchor_error(&logs),
+        failure,
+        logs,
+    }
+}
+
+impl OptifiClient {
+    /// Simulates `instructions` as one transaction paid by `payer`, without submitting it.
+    ///
+    /// Signatures are not verified, so neither `payer` nor `owner` is asked to sign.
+    pub fn simulate_instructions(
+        &self,
+        instructions: Vec<Instruction>,
+    ) -> std::result::Result<SimulationResult, OptifiClientError> {
+        let mut tx = Transaction::new_with_payer(&instructions, Some(&self.payer.pubkey()));
+
+        tx.message.recent_blockhash = self.rpc.get_latest_blockhash()?;
+
+        let response = self
+            .rpc
+            .simulate_transaction_with_config(&tx, simulation_config(&self.config))?;
+
+        Ok(simulation_result(&self.config, &instructions, response))
+    }
+
+    /// Simulates `initialize_user_account` with a throwaway margin account.
+    pub fn simulate_initialize_user_account(
+        &self,
+    ) -> std::result::Result<SimulationResult, OptifiClientError> {
+        self.simulate_instructions(self.with_compute_budget(
+            Operation::Other,
+            self.initialize_user_account_instructions(&Keypair::new().pubkey())?,
+        )?)
+    }
+
+    pub fn simulate_initialize_fee_account(
+        &self,
+    ) -> std::result::Result<SimulationResult, OptifiClientError> {
+        self.simulate_instructions(self.with_compute_budget(
+            Operation::Other,
+            self.initialize_fee_account_instructions()?,
+        )?)
+    }
+
+    pub fn simulate_set_delegation(
+        &self,
+        delegatee: Option<Pubkey>,
+    ) -> std::result::Result<SimulationResult, OptifiClientError> {
+        self.simulate_instructions(self.with_compute_budget(
+            Operation::Other,
+            self.set_delegation_instructions(delegatee)?,
+        )?)
+    }
+
+    pub fn simulate_deposit(
+        &self,
+        amount: UsdcAmount,
+    ) -> std::result::Result<SimulationResult, OptifiClientError> {
+        self.simulate_instructions(
+            self.with_compute_budget(Operation::Other, self.deposit_instructions(amount)?)?,
+        )
+    }
+
+    pub fn simulate_withdraw(
+        &self,
+        amount: UsdcAmount,
+    ) -> std::result::Result<SimulationResult, OptifiClientError> {
+        self.simulate_instructions(
+            self.with_compute_budget(Operation::Other, self.withdraw_instructions(amount)?)?,
+        )
+    }
+
+    pub fn simulate_initialize_user_on_market(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<SimulationResult, OptifiClientError> {
+        self.simulate_instructions(self.with_compute_budget(
+            Operation::Other,
+            self.initialize_user_on_market_instructions(market)?,
+        )?)
+    }
+
+    /// Simulates each transaction `place_order` sends, the setup-only one included when the
+    /// market setup does not fit with the order, see `simulate_batches`.
+    pub fn simulate_place_order(
+        &self,
+        market: &Market,
+        side: OrderSide,
+        price: Price,
+        size: Quantity,
+        order_type: OrderType,
+    ) -> std::result::Result<Vec<BatchSimulation>, OptifiClientError> {
+        self.simulate_batches(
+            Operation::PlaceOrder,
+            self.place_order_instructions(market, side, price, size, order_type)?,
+        )
+    }
+
+    pub fn simulate_settle_order(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<SimulationResult, OptifiClientError> {
+        self.simulate_instructions(self.with_compute_budget(
+            Operation::SettleOrder,
+            self.settle_order_instructions(market)?,
+        )?)
+    }
+
+    /// Simulates the exact sequence `cancel_order` sends.
+    pub fn simulate_cancel_order(
+        &self,
+        market: &Market,
+        side: OrderSide,
+        client_order_id: u64,
+    ) -> std::result::Result<SimulationResult, OptifiClientError> {
+        self.simulate_instructions(self.with_compute_budget(
+            Operation::CancelOrder,
+            self.cancel_order_instructions(market, side, client_order_id)?,
+        )?)
+    }
+
+    /// Simulates each of `batches` with the compute budget of `operation`, or of
+    /// `Operation::Other` for setup-only batches.
+    ///
+    /// Batches are simulated independently against the current state, so one does not see the
+    /// accounts created or the orders placed or cancelled by the ones before it.
+    fn simulate_batches(
+        &self,
+        operation: Operation,
+        batches: Vec<OrderBatch>,
+    ) -> std::result::Result<Vec<BatchSimulation>, OptifiClientError> {
+        batches
+            .into_iter()
+            .map(|batch| -> std::result::Result<_, OptifiClientError> {
+                let operation = if batch.orders.is_empty() {
+                    Operation::Other
+                } else {
+                    operation
+                };
+
+                Ok(BatchSimulation {
+                    orders: batch.orders,
+                    simulation: self.simulate_instructions(
+                        self.with_compute_budget(operation, batch.instructions)?,
+                    )?,
+                })
+            })
+            .collect()
+    }
+
+    /// Simulates each transaction `place_orders` sends.
+    pub fn simulate_place_orders(
+        &self,
+        market: &Market,
+        orders: &[OrderRequest],
+    ) -> std::result::Result<Vec<BatchSimulation>, OptifiClientError> {
+        self.simulate_batches(
+            Operation::PlaceOrder,
+            self.place_orders_instructions(market, orders)?,
+        )
+    }
+
+    /// Simulates each transaction `cancel_orders` sends.
+    pub fn simulate_cancel_orders(
+        &self,
+        market: &Market,
+        orders: &[(OrderSide, u64)],
+    ) -> std::result::Result<Vec<BatchSimulation>, OptifiClientError> {
+        self.simulate_batches(
+            Operation::CancelOrder,
+            self.cancel_orders_instructions(market, orders)?,
+        )
+    }
+
+    /// Simulates each transaction `cancel_all_order` sends for the orders open right now.
+    pub fn simulate_cancel_all_order(
+        &self,
+        market: &Market,
+    ) -> std::result::Result<Vec<BatchSimulation>, OptifiClientError> {
+        let orders: Vec<(OrderSide, u64)> = self
+            .load_open_orders(market)?
+            .iter()
+            .map(|order| (order.side, order.client_order_id))
+            .collect();
+
+        self.simulate_cancel_orders(market, &orders)
+    }
+
+    /// Simulates the exact sequence `replace_order` sends.
+    pub fn simulate_replace_order(
+        &self,
+        market: &Market,
+        side: OrderSide,
+        old_client_order_id: u64,
+        new_price: Price,
+        new_size: Quantity,
+    ) -> std::result::Result<SimulationResult, OptifiClientError> {
+        self.simulate_instructions(self.with_compute_budget(
+            Operation::PlaceOrder,
+            self.replace_order_instructions(
+                market,
+                side,
+                old_client_order_id,
+                new_price,
+                new_size,
+            )?,
+        )?)
+    }
+}
@@ -0,0 +1,215 @@
+use std::fmt;
+
+use crate::client::{Market, OptifiClient};
+use crate::error::OptifiClientError;
+use crate::prelude::*;
+
+const MONTHS: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+
+/// Ticker of each underlying asset in symbols.
+const ASSET_CODES: [(Asset, &str); 3] = [
+    (Asset::Bitcoin, "BTC"),
+    (Asset::Ethereum, "ETH"),
+    (Asset::Solana, "SOL"),
+];
+
+/// UTC calendar day an instrument expires on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ExpiryDate {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl ExpiryDate {
+    /// Day of the unix timestamp `timestamp`, ignoring the time of day.
+    pub fn from_unix_timestamp(timestamp: u64) -> Self {
+        // Civil from days, http://howardhinnant.github.io/date_algorithms.html
+        let z = (timestamp / 86_400) as i64 + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        Self {
+            year: year as i32,
+            month: month as u8,
+            day: day as u8,
+        }
+    }
+}
+
+impl fmt::Display for ExpiryDate {
+    /// `28OCT22`, the day unpadded.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{:02}",
+            self.day,
+            MONTHS[usize::from(self.month - 1)],
+            self.year.rem_euclid(100)
+        )
+    }
+}
+
+impl FromStr for ExpiryDate {
+    type Err = OptifiClientError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || OptifiClientError::Conversion(format!("invalid expiry date {}", s));
+
+        if !s.is_ascii() {
+            return Err(invalid());
+        }
+
+        let month_start = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+
+        if !(1..=2).contains(&month_start) || s.len() != month_start + 5 {
+            return Err(invalid());
+        }
+
+        let day: u8 = s[..month_start].parse().map_err(|_| invalid())?;
+
+        let month = MONTHS
+            .iter()
+            .position(|month| s[month_start..month_start + 3].eq_ignore_ascii_case(month))
+            .ok_or_else(invalid)? as u8
+            + 1;
+
+        let year: i32 = s[month_start + 3..].parse().map_err(|_| invalid())?;
+
+        if day == 0 || day > 31 {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            year: 2000 + year,
+            month,
+            day,
+        })
+    }
+}
+
+/// Canonical name of an option, e.g. `BTC-28OCT22-20000-C`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InstrumentSymbol {
+    pub asset: Asset,
+    pub expiry: ExpiryDate,
+    pub strike: u32,
+    pub is_call: bool,
+}
+
+impl InstrumentSymbol {
+    /// Symbol of `market`, `None` for instruments other than calls and puts.
+    pub fn from_market(market: &Market) -> Option<Self> {
+        let is_call = match market.instrument_type {
+            InstrumentType::Call => true,
+            InstrumentType::Put => false,
+            _ => return None,
+        };
+
+        Some(Self {
+            asset: market.instrument_common.asset,
+            expiry: ExpiryDate::from_unix_timestamp(market.instrument_common.expiry_date),
+            strike: market.strike,
+            is_call,
+        })
+    }
+
+    pub fn instrument_type(&self) -> InstrumentType {
+        if self.is_call {
+            InstrumentType::Call
+        } else {
+            InstrumentType::Put
+        }
+    }
+}
+
+impl fmt::Display for InstrumentSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match ASSET_CODES.iter().find(|(asset, _)| *asset == self.asset) {
+            Some((_, code)) => write!(f, "{}", code)?,
+            None => write!(f, "{:?}", self.asset)?,
+        }
+
+        write!(
+            f,
+            "-{}-{}-{}",
+            self.expiry,
+            self.strike,
+            if self.is_call { "C" } else { "P" }
+        )
+    }
+}
+
+impl FromStr for InstrumentSymbol {
+    type Err = OptifiClientError;
+
+    /// Parses symbols formatted by `Display`, case insensitively.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || OptifiClientError::Conversion(format!("invalid instrument symbol {}", s));
+
+        let parts: Vec<&str> = s.split('-').collect();
+
+        let (asset, expiry, strike, kind) = match parts.as_slice() {
+            [asset, expiry, strike, kind] => (*asset, *expiry, *strike, *kind),
+            _ => return Err(invalid()),
+        };
+
+        let asset = ASSET_CODES
+            .iter()
+            .find(|(_, code)| code.eq_ignore_ascii_case(asset))
+            .map(|(asset, _)| *asset)
+            .ok_or_else(invalid)?;
+
+        let is_call = match kind {
+            "C" | "c" => true,
+            "P" | "p" => false,
+            _ => return Err(invalid()),
+        };
+
+        Ok(Self {
+            asset,
+            expiry: expiry.parse()?,
+            strike: strike.parse().map_err(|_| invalid())?,
+            is_call,
+        })
+    }
+}
+
+impl Market {
+    /// Symbol of this market, see `InstrumentSymbol`.
+    pub fn symbol(&self) -> Option<InstrumentSymbol> {
+        InstrumentSymbol::from_market(self)
+    }
+}
+
+impl fmt::Display for Market {
+    /// The symbol, or the optifi market address for instruments without one.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.symbol() {
+            Some(symbol) => write!(f, "{}", symbol),
+            None => write!(f, "{}", self.optifi_market_key_data.optifi_market_pubkey),
+        }
+    }
+}
+
+impl OptifiClient {
+    /// Cached market named `symbol`, e.g. `BTC-28OCT22-20000-C`.
+    pub fn market_by_symbol(&self, symbol: &str) -> std::result::Result<Market, OptifiClientError> {
+        let symbol: InstrumentSymbol = symbol.parse()?;
+
+        self.account()
+            .markets
+            .iter()
+            .find(|market| market.symbol() == Some(symbol))
+            .cloned()
+            .ok_or_else(|| OptifiClientError::SymbolNotFound(symbol.to_string()))
+    }
+}
@@ -0,0 +1,162 @@
+use std::fmt;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+
+use crate::error::OptifiClientError;
+use crate::prelude::*;
+
+/// Multiplies `value` by `10^scale`, failing unless the result is a whole `u64`.
+fn to_native(
+    value: Decimal,
+    scale: u32,
+    name: &str,
+) -> std::result::Result<u64, OptifiClientError> {
+    value
+        .checked_mul(Decimal::from(10_u64.pow(scale)))
+        .filter(|native| native.fract().is_zero())
+        .and_then(|native| native.to_u64())
+        .ok_or_else(|| {
+            OptifiClientError::Conversion(format!(
+                "{} {} is not a whole number of native units with {} decimals",
+                name, value, scale
+            ))
+        })
+}
+
+fn from_native(native: u64, scale: u32) -> Decimal {
+    Decimal::from_i128_with_scale(native as i128, scale).normalize()
+}
+
+macro_rules! decimal_newtype {
+    ($name:ident, $label:literal) => {
+        impl $name {
+            pub fn new(value: Decimal) -> Self {
+                Self(value)
+            }
+
+            /// Closest decimal to `value`, `None` for NaN and infinities.
+            pub fn from_f64(value: f64) -> Option<Self> {
+                Decimal::from_f64(value).map(Self)
+            }
+
+            pub fn value(&self) -> Decimal {
+                self.0
+            }
+
+            /// Lossy conversion for display and arithmetic outside the client.
+            pub fn to_f64(&self) -> f64 {
+                self.0.to_f64().unwrap_or_default()
+            }
+        }
+
+        impl From<Decimal> for $name {
+            fn from(value: Decimal) -> Self {
+                Self(value)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = OptifiClientError;
+
+            fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+                Decimal::from_str(s).map(Self).map_err(|err| {
+                    OptifiClientError::Conversion(format!("invalid {} {}: {}", $label, s, err))
+                })
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+
+            fn add(self, other: Self) -> Self {
+                Self(self.0 + other.0)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+
+            fn sub(self, other: Self) -> Self {
+                Self(self.0 - other.0)
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, other: Self) {
+                self.0 += other.0;
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, other: Self) {
+                self.0 -= other.0;
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.0, f)
+            }
+        }
+    };
+}
+
+/// Price of one contract in USDC.
+///
+/// Serum prices it in USDC native units per contract native unit, so its native precision
+/// depends on the asset's decimals.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Price(Decimal);
+
+/// Number of contracts, in units of the instrument token.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Quantity(Decimal);
+
+/// Amount of USDC.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UsdcAmount(Decimal);
+
+decimal_newtype!(Price, "price");
+decimal_newtype!(Quantity, "quantity");
+decimal_newtype!(UsdcAmount, "usdc amount");
+
+impl Price {
+    /// Decimals of the serum native price of `asset`.
+    pub fn decimals(asset: Asset) -> u32 {
+        USDC_DECIMALS - asset.get_decimal()
+    }
+
+    pub fn from_native(asset: Asset, native: u64) -> Self {
+        Self(from_native(native, Self::decimals(asset)))
+    }
+
+    /// Serum native price, failing if it is finer than one native unit.
+    pub fn to_native(&self, asset: Asset) -> std::result::Result<u64, OptifiClientError> {
+        to_native(self.0, Self::decimals(asset), "price")
+    }
+}
+
+impl Quantity {
+    pub fn decimals(asset: Asset) -> u32 {
+        asset.get_decimal()
+    }
+
+    pub fn from_native(asset: Asset, native: u64) -> Self {
+        Self(from_native(native, Self::decimals(asset)))
+    }
+
+    /// Serum native quantity, failing if it is finer than one native unit.
+    pub fn to_native(&self, asset: Asset) -> std::result::Result<u64, OptifiClientError> {
+        to_native(self.0, Self::decimals(asset), "quantity")
+    }
+}
+
+impl UsdcAmount {
+    pub fn from_native(native: u64) -> Self {
+        Self(from_native(native, USDC_DECIMALS))
+    }
+
+    /// Native USDC amount, failing if it is finer than one native unit.
+    pub fn to_native(&self) -> std::result::Result<u64, OptifiClientError> {
+        to_native(self.0, USDC_DECIMALS, "usdc amount")
+    }
+}
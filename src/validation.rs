@@ -0,0 +1,221 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::client::{Market, OptifiClient};
+use crate::decoder::MarketState;
+use crate::error::OptifiClientError;
+use crate::orders::OrderRequest;
+use crate::prelude::*;
+use crate::types::{Price, Quantity};
+
+/// What to do with orders that do not fit a market's tick and lot sizes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrderValidation {
+    /// Send orders unchecked.
+    Off,
+    /// Reject orders off tick or lot.
+    Reject,
+    /// Round prices to the passive side of the tick and sizes down to the lot.
+    Round,
+}
+
+/// Why an order was rejected before being sent.
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+pub enum OrderRejection {
+    #[error("market {0} is stopped")]
+    MarketStopped(Pubkey),
+
+    #[error("instrument expired at {0}")]
+    Expired(u64),
+
+    #[error("user is not initialized on market {0}")]
+    NotInitialized(Pubkey),
+
+    #[error("price {0} is not positive")]
+    InvalidPrice(Price),
+
+    #[error("size {size} is below the lot size {lot_size}")]
+    InvalidSize { size: Quantity, lot_size: Quantity },
+
+    #[error("price {price} is not a multiple of the tick size {tick_size}")]
+    OffTick { price: Price, tick_size: Price },
+
+    #[error("size {size} is not a multiple of the lot size {lot_size}")]
+    OffLot { size: Quantity, lot_size: Quantity },
+}
+
+/// Trading rules of a market, read from its cached optifi and serum accounts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MarketRules {
+    pub optifi_market: Pubkey,
+    /// Smallest price increment serum accepts.
+    pub tick_size: Price,
+    /// Smallest size increment, the serum coin lot.
+    pub lot_size: Quantity,
+    pub is_stopped: bool,
+    /// Unix timestamp the instrument expires at.
+    pub expiry_date: u64,
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Serum `(coin_lot_size, pc_lot_size)` of `market`.
+pub fn get_lot_sizes(market: &Market) -> std::result::Result<(u64, u64), OptifiClientError> {
+    let market_state = MarketState::decode(&market.serum_account.data)?;
+
+    Ok((market_state.coin_lot_size, market_state.pc_lot_size))
+}
+
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+impl MarketRules {
+    pub fn new(market: &Market) -> std::result::Result<Self, OptifiClientError> {
+        let asset = market.instrument_common.asset;
+
+        let (coin_lot_size, pc_lot_size) = get_lot_sizes(market)?;
+
+        if coin_lot_size == 0 || pc_lot_size == 0 {
+            return Err(OptifiClientError::Deserialization(format!(
+                "serum market {} has no lot size",
+                market.optifi_market.serum_market
+            )));
+        }
+
+        // Serum needs `price * coin_lot_size` to be a whole number of pc lots, and the native
+        // price itself to be whole.
+        let tick_size = pc_lot_size / gcd(pc_lot_size, coin_lot_size);
+
+        Ok(Self {
+            optifi_market: market.optifi_market_key_data.optifi_market_pubkey,
+            tick_size: Price::from_native(asset, tick_size),
+            lot_size: Quantity::from_native(asset, coin_lot_size),
+            is_stopped: market.optifi_market_key_data.is_stopped,
+            expiry_date: market.instrument_common.expiry_date,
+        })
+    }
+
+    /// Checks `order` at unix timestamp `now`, returning it as it should be sent.
+    pub fn check(
+        &self,
+        order: OrderRequest,
+        validation: OrderValidation,
+        now: u64,
+    ) -> std::result::Result<OrderRequest, OrderRejection> {
+        if validation == OrderValidation::Off {
+            return Ok(order);
+        }
+
+        if self.is_stopped {
+            return Err(OrderRejection::MarketStopped(self.optifi_market));
+        }
+
+        if now >= self.expiry_date {
+            return Err(OrderRejection::Expired(self.expiry_date));
+        }
+
+        let tick_size = self.tick_size.value();
+        let lot_size = self.lot_size.value();
+
+        let ticks = order.price.value() / tick_size;
+        let lots = order.size.value() / lot_size;
+
+        let ticks = if ticks.fract().is_zero() {
+            ticks
+        } else if validation == OrderValidation::Round {
+            match order.side {
+                OrderSide::Bid => ticks.floor(),
+                OrderSide::Ask => ticks.ceil(),
+            }
+        } else {
+            return Err(OrderRejection::OffTick {
+                price: order.price,
+                tick_size: self.tick_size,
+            });
+        };
+
+        let lots = if lots.fract().is_zero() {
+            lots
+        } else if validation == OrderValidation::Round {
+            lots.floor()
+        } else {
+            return Err(OrderRejection::OffLot {
+                size: order.size,
+                lot_size: self.lot_size,
+            });
+        };
+
+        if ticks <= Decimal::ZERO {
+            return Err(OrderRejection::InvalidPrice(order.price));
+        }
+
+        if lots <= Decimal::ZERO {
+            return Err(OrderRejection::InvalidSize {
+                size: order.size,
+                lot_size: self.lot_size,
+            });
+        }
+
+        Ok(OrderRequest {
+            price: Price::new(ticks * tick_size),
+            size: Quantity::new(lots * lot_size),
+            ..order
+        })
+    }
+}
+
+/// Checks `orders` against the rules of `market` with `validation` at unix timestamp `now`,
+/// returning them as they should be sent.
+pub fn check_orders(
+    validation: OrderValidation,
+    market: &Market,
+    orders: &[OrderRequest],
+    now: u64,
+) -> std::result::Result<Vec<OrderRequest>, OptifiClientError> {
+    if validation == OrderValidation::Off {
+        return Ok(orders.to_vec());
+    }
+
+    let rules = MarketRules::new(market)?;
+
+    Ok(orders
+        .iter()
+        .map(|order| rules.check(*order, validation, now))
+        .collect::<std::result::Result<Vec<_>, _>>()?)
+}
+
+impl OptifiClient {
+    /// Checks `orders` against the rules of `market` and `config.order_validation`, returning
+    /// them as they should be sent.
+    ///
+    /// Whether the user is set up on `market` is checked when the orders are built, see
+    /// `ensure_market_ready`.
+    pub fn validate_orders(
+        &self,
+        market: &Market,
+        orders: &[OrderRequest],
+    ) -> std::result::Result<Vec<OrderRequest>, OptifiClientError> {
+        check_orders(
+            self.config.order_validation,
+            market,
+            orders,
+            unix_timestamp(),
+        )
+    }
+
+    pub fn validate_order(
+        &self,
+        market: &Market,
+        order: OrderRequest,
+    ) -> std::result::Result<OrderRequest, OptifiClientError> {
+        Ok(self.validate_orders(market, &[order])?[0])
+    }
+}
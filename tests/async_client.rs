@@ -0,0 +1,98 @@
+#[cfg(test)]
+mod tests {
+
+    use futures::StreamExt;
+    use optifi_client::async_client::AsyncOptifiClient;
+    use optifi_client::network::Network;
+    use optifi_client::prelude::*;
+
+    const RPC: &str = "https://devnet.genesysgo.net";
+
+    const WALLET_PATH: &str = "~/.config/solana/optifi.json";
+
+    #[tokio::test]
+    async fn test_load_markets() {
+        let optifi_client = AsyncOptifiClient::new(
+            Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
+            Some(WALLET_PATH.to_owned()),
+            None,
+        )
+        .await
+        .unwrap();
+
+        optifi_client.load_optifi_exchange().await.unwrap();
+
+        let failures = optifi_client.load_markets().await.unwrap();
+
+        println!("markets: {:#?}", optifi_client.account().markets);
+        println!("failures: {:#?}", failures);
+    }
+
+    #[tokio::test]
+    async fn test_place_order() {
+        let optifi_client = AsyncOptifiClient::initialize(
+            Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
+            Some(WALLET_PATH.to_owned()),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let placed = optifi_client
+            .place_order(
+                &optifi_client.account().markets[0],
+                OrderSide::Bid,
+                "1".parse().unwrap(),
+                "0.01".parse().unwrap(),
+                OrderType::Limit,
+            )
+            .await
+            .unwrap();
+
+        println!("placed: {:#?}", placed);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_asks() {
+        let optifi_client = AsyncOptifiClient::initialize(
+            Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
+            Some(WALLET_PATH.to_owned()),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let mut asks = optifi_client
+            .subscribe_asks(&optifi_client.account().markets[0])
+            .await
+            .unwrap();
+
+        while let Some(levels) = asks.next().await {
+            println!("{:#?}", levels.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_open_orders() {
+        let optifi_client = AsyncOptifiClient::initialize(
+            Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
+            Some(WALLET_PATH.to_owned()),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let mut open_orders = optifi_client
+            .subscribe_open_orders(&optifi_client.account().markets[0])
+            .await
+            .unwrap();
+
+        while let Some(orders) = open_orders.next().await {
+            println!("{:#?}", orders.unwrap());
+        }
+    }
+}
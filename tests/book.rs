@@ -0,0 +1,72 @@
+#[cfg(test)]
+mod tests {
+
+    use optifi_client::client::{book_levels, Book, BookLevel};
+    use optifi_client::decoder::SlabOrder;
+    use optifi_client::prelude::*;
+    use optifi_client::types::{Price, Quantity};
+
+    const ASSET: Asset = Asset::Bitcoin;
+
+    /// Orders by ascending key, as a slab returns them.
+    fn orders(levels: &[(u64, u64)]) -> Vec<SlabOrder> {
+        levels
+            .iter()
+            .enumerate()
+            .map(|(seq_num, &(price, quantity))| SlabOrder {
+                key: (u128::from(price) << 64) | seq_num as u128,
+                owner_slot: 0,
+                owner: Pubkey::default(),
+                quantity,
+                client_order_id: seq_num as u64,
+            })
+            .collect()
+    }
+
+    fn level(price: u64, size: u64) -> BookLevel {
+        BookLevel {
+            price: Price::from_native(ASSET, price),
+            size: Quantity::from_native(ASSET, size),
+        }
+    }
+
+    #[test]
+    fn test_book_levels() {
+        let orders = orders(&[(100, 1), (100, 2), (101, 5), (105, 1), (105, 1), (105, 1)]);
+
+        assert_eq!(
+            book_levels(ASSET, OrderSide::Ask, &orders, None),
+            vec![level(100, 3), level(101, 5), level(105, 3)]
+        );
+        assert_eq!(
+            book_levels(ASSET, OrderSide::Bid, &orders, None),
+            vec![level(105, 3), level(101, 5), level(100, 3)]
+        );
+
+        assert_eq!(
+            book_levels(ASSET, OrderSide::Ask, &orders, Some(2)),
+            vec![level(100, 3), level(101, 5)]
+        );
+        assert_eq!(
+            book_levels(ASSET, OrderSide::Bid, &orders, Some(1)),
+            vec![level(105, 3)]
+        );
+        assert!(book_levels(ASSET, OrderSide::Bid, &orders, Some(0)).is_empty());
+        assert!(book_levels(ASSET, OrderSide::Ask, &[], None).is_empty());
+    }
+
+    #[test]
+    fn test_best_bid_ask() {
+        let book = Book {
+            bids: vec![level(99, 1), level(98, 2)],
+            asks: vec![level(101, 1)],
+            slot: 42,
+        };
+
+        assert_eq!(book.best_bid(), Some(level(99, 1)));
+        assert_eq!(book.best_ask(), Some(level(101, 1)));
+
+        assert_eq!(Book::default().best_bid(), None);
+        assert_eq!(Book::default().best_ask(), None);
+    }
+}
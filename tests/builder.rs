@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use optifi_client::builder::OptifiClientBuilder;
+    use optifi_client::compute_budget::{
+        ComputeBudgetPolicy, ComputeBudgets, Operation, PriorityFee, UnitLimit,
+    };
+    use optifi_client::config::DEFAULT_CONSUME_EVENTS_LIMIT;
+    use optifi_client::network::Network;
+    use optifi_client::prelude::*;
+
+    const RPC: &str = "https://devnet.genesysgo.net";
+
+    const WALLET_PATH: &str = "~/.config/solana/optifi.json";
+
+    #[test]
+    fn test_builder_defaults() {
+        let optifi_client = OptifiClientBuilder::new(Cluster::from_str(RPC).unwrap())
+            .signer(Arc::new(Keypair::new()))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            optifi_client.config.optifi_exchange,
+            Pubkey::from_str(OPTIFI_EXCHANGE).unwrap()
+        );
+        assert_eq!(optifi_client.config.optifi_program_id, optifi_cpi::id());
+        assert_eq!(
+            optifi_client.config.compute_budgets,
+            ComputeBudgets::default()
+        );
+        assert_eq!(
+            optifi_client.config.consume_events_limit,
+            DEFAULT_CONSUME_EVENTS_LIMIT
+        );
+        assert_eq!(optifi_client.payer.pubkey(), optifi_client.owner.pubkey());
+    }
+
+    #[test]
+    fn test_builder_overrides() {
+        let payer = Keypair::new();
+        let owner = Keypair::new();
+        let owner_pubkey = owner.pubkey();
+
+        let optifi_exchange = Pubkey::new_unique();
+        let optifi_program_id = Pubkey::new_unique();
+
+        let optifi_client = OptifiClientBuilder::new(Cluster::from_str(RPC).unwrap())
+            .ws_url("wss://devnet.genesysgo.net/ws")
+            .payer(Arc::new(payer))
+            .owner(Arc::new(owner))
+            .read_commitment(CommitmentConfig::confirmed())
+            .send_commitment(CommitmentConfig::finalized())
+            .optifi_exchange(optifi_exchange)
+            .optifi_program_id(optifi_program_id)
+            .compute_units(600_000)
+            .compute_budget(
+                Operation::SettleOrder,
+                ComputeBudgetPolicy::simulated(0.2).with_priority_fee(PriorityFee::PerUnit(10)),
+            )
+            .consume_events_limit(10)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            optifi_client.cluster.ws_url(),
+            "wss://devnet.genesysgo.net/ws"
+        );
+        assert_eq!(
+            optifi_client.config.read_commitment,
+            CommitmentConfig::confirmed()
+        );
+        assert_eq!(
+            optifi_client.config.send_commitment,
+            CommitmentConfig::finalized()
+        );
+        assert_eq!(optifi_client.optifi_exchange, optifi_exchange);
+        assert_eq!(
+            optifi_client.config.compute_budgets.place_order.unit_limit,
+            UnitLimit::Fixed(600_000)
+        );
+        assert_eq!(
+            optifi_client.config.compute_budgets.cancel_order.unit_limit,
+            UnitLimit::Fixed(600_000)
+        );
+        assert_eq!(
+            optifi_client
+                .config
+                .compute_budgets
+                .settle_order
+                .priority_fee,
+            PriorityFee::PerUnit(10)
+        );
+        assert_eq!(optifi_client.config.consume_events_limit, 10);
+
+        let (user_account, ..) =
+            get_user_account_pda(&optifi_exchange, &owner_pubkey, &optifi_program_id);
+
+        assert_eq!(optifi_client.user_account, user_account);
+    }
+
+    #[test]
+    fn test_builder_load_markets() {
+        let optifi_client = OptifiClientBuilder::new(Cluster::from_str(RPC).unwrap())
+            .network(Network::Devnet)
+            .wallet_path(WALLET_PATH)
+            .commitment(CommitmentConfig::confirmed())
+            .build()
+            .unwrap();
+
+        optifi_client.load_optifi_exchange().unwrap();
+        optifi_client.load_markets().unwrap();
+
+        println!("markets: {}", optifi_client.get_markets(None).len());
+    }
+
+    #[tokio::test]
+    async fn test_builder_async() {
+        let optifi_client = OptifiClientBuilder::new(Cluster::from_str(RPC).unwrap())
+            .network(Network::Devnet)
+            .wallet_path(WALLET_PATH)
+            .build_async()
+            .await
+            .unwrap();
+
+        println!("user account: {}", optifi_client.user_account);
+    }
+}
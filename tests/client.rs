@@ -2,6 +2,7 @@
 mod tests {
 
     use optifi_client::client::OptifiClient;
+    use optifi_client::network::Network;
     use optifi_client::prelude::*;
 
     // const RPC: &str = "https://solana-mainnet.g.alchemy.com/v2/OJxaca6y_6S2MJYq6WbKPIcdVwsbNS0j";
@@ -14,9 +15,11 @@ mod tests {
     fn test_initialize_user_account() {
         let optifi_client = OptifiClient::new(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
         let signature = optifi_client.initialize_user_account();
 
@@ -27,9 +30,11 @@ mod tests {
     fn test_initialize_fee_account() {
         let optifi_client = OptifiClient::new(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
         let signature = optifi_client.initialize_fee_account();
 
@@ -38,41 +43,49 @@ mod tests {
 
     #[test]
     fn test_load_optifi_exchange() {
-        let mut optifi_client = OptifiClient::new(
+        let optifi_client = OptifiClient::new(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
-        optifi_client.load_optifi_exchange();
+        optifi_client.load_optifi_exchange().unwrap();
 
-        println!("exchange: {:#?}", optifi_client.account.optifi_exchange);
+        println!("exchange: {:#?}", optifi_client.account().optifi_exchange);
     }
 
     #[test]
     fn test_load_user_account() {
-        let mut optifi_client = OptifiClient::new(
+        let optifi_client = OptifiClient::new(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
-        optifi_client.load_user_account();
+        optifi_client.load_user_account().unwrap();
 
         println!("user_account pubkey: {:#?}", optifi_client.user_account);
 
-        println!("user_account: {:#?}", optifi_client.account.user_account);
+        println!("user_account: {:#?}", optifi_client.account().user_account);
     }
 
     #[test]
     fn test_load_margin_stress_account() {
         let optifi_client = OptifiClient::new(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
-        let margin_stress_account = optifi_client.load_margin_stress_account(Asset::Bitcoin);
+        let margin_stress_account = optifi_client
+            .load_margin_stress_account(Asset::Bitcoin)
+            .unwrap();
 
         println!("margin_stress_account: {:#?}", margin_stress_account);
     }
@@ -81,44 +94,80 @@ mod tests {
     fn test_load_fee_account() {
         let optifi_client = OptifiClient::new(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
-        let fee_account = optifi_client.load_fee_account();
+        let fee_account = optifi_client.load_fee_account().unwrap();
 
         println!("fee_account: {:#?}", fee_account);
     }
 
     #[test]
     fn test_load_markets() {
-        let mut optifi_client = OptifiClient::new(
+        let optifi_client = OptifiClient::new(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             // Cluster::Custom("https://optifil-develope-610c.devnet.rpcpool.com/2fc9e4ee-7e7b-47c5-a9af-a3a4dc0f79c9".to_string(), "".to_string()),
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
-        optifi_client.load_optifi_exchange();
+        optifi_client.load_optifi_exchange().unwrap();
 
         // let start = Instant::now();
 
-        optifi_client.load_markets();
+        let failures = optifi_client.load_markets().unwrap();
 
         // println!("Time for load_markets: {:?}", start.elapsed());
 
-        println!("markets: {:#?}", optifi_client.account.markets);
+        println!("markets: {:#?}", optifi_client.account().markets);
+        println!("failures: {:#?}", failures);
+    }
+
+    #[test]
+    fn test_share_client_across_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<OptifiClient>();
+
+        let optifi_client = std::sync::Arc::new(
+            OptifiClient::new(
+                Cluster::from_str(RPC).unwrap(),
+                Network::Devnet,
+                Some(WALLET_PATH.to_owned()),
+                None,
+            )
+            .unwrap(),
+        );
+
+        optifi_client.load_optifi_exchange().unwrap();
+
+        let handle = {
+            let optifi_client = optifi_client.clone();
+            std::thread::spawn(move || optifi_client.load_markets().unwrap())
+        };
+
+        optifi_client.load_user_account().unwrap();
+
+        handle.join().unwrap();
+
+        println!("markets: {}", optifi_client.account().markets.len());
     }
 
     #[test]
     fn test_deposit() {
         let optifi_client = OptifiClient::new(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
-        let signature = optifi_client.deposit(100.).unwrap();
+        let signature = optifi_client.deposit("100".parse().unwrap()).unwrap();
 
         println!("signature: {:#?}", signature);
     }
@@ -127,29 +176,33 @@ mod tests {
     fn test_withdraw() {
         let optifi_client = OptifiClient::new(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
-        let signature = optifi_client.withdraw(100.).unwrap();
+        let signature = optifi_client.withdraw("100".parse().unwrap()).unwrap();
 
         println!("signature: {:#?}", signature);
     }
 
     #[test]
     fn test_initialize_user_on_market() {
-        let mut optifi_client = OptifiClient::new(
+        let optifi_client = OptifiClient::new(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
-        optifi_client.load_optifi_exchange();
+        optifi_client.load_optifi_exchange().unwrap();
 
-        optifi_client.load_markets();
+        optifi_client.load_markets().unwrap();
 
         let signature = optifi_client
-            .initialize_user_on_market(&optifi_client.account.markets[0])
+            .initialize_user_on_market(&optifi_client.account().markets[0])
             .ok();
 
         println!("signature: {:#?}", signature);
@@ -157,13 +210,15 @@ mod tests {
 
     #[test]
     fn test_set_delegation() {
-        let mut optifi_client = OptifiClient::new(
+        let optifi_client = OptifiClient::new(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
-        optifi_client.load_user_account();
+        optifi_client.load_user_account().unwrap();
 
         // Wallet and cluster params.
         let delegatee_wallet_path = "~/.config/solana/delegatee.json".to_string();
@@ -180,18 +235,20 @@ mod tests {
     fn test_place_order() {
         let optifi_client = OptifiClient::initialize(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
-        println!("market: {:#?}", &optifi_client.account.markets[1]);
+        println!("market: {:#?}", &optifi_client.account().markets[1]);
 
         let signature = optifi_client
             .place_order(
-                &optifi_client.account.markets[0],
+                &optifi_client.account().markets[0],
                 OrderSide::Bid,
-                1.,
-                0.01,
+                "1".parse().unwrap(),
+                "0.01".parse().unwrap(),
                 OrderType::Limit,
             )
             .unwrap();
@@ -206,18 +263,20 @@ mod tests {
 
         let optifi_client = OptifiClient::initialize(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some("~/.config/solana/delegatee.json".to_owned()),
             Some(delegator.pubkey()),
-        );
+        )
+        .unwrap();
 
-        // println!("market: {:#?}", &optifi_client.account.markets[0]);
+        // println!("market: {:#?}", &optifi_client.account().markets[0]);
 
         let signature = optifi_client
             .place_order(
-                &optifi_client.account.markets[0],
+                &optifi_client.account().markets[0],
                 OrderSide::Bid,
-                1.,
-                1.,
+                "1".parse().unwrap(),
+                "1".parse().unwrap(),
                 OrderType::Limit,
             )
             .unwrap();
@@ -229,12 +288,14 @@ mod tests {
     fn test_settle_order() {
         let optifi_client = OptifiClient::initialize(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
         let signature = optifi_client
-            .settle_order(&optifi_client.account.markets[0])
+            .settle_order(&optifi_client.account().markets[0])
             .unwrap();
 
         println!("signature: {:#?}", signature);
@@ -244,50 +305,66 @@ mod tests {
     fn test_load_open_orders() {
         let optifi_client = OptifiClient::initialize(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
-        let orders = optifi_client.load_open_orders(&optifi_client.account.markets[0]);
+        let orders = optifi_client
+            .load_open_orders(&optifi_client.account().markets[0])
+            .unwrap();
 
         println!("{:#?}", orders);
     }
 
     #[test]
     fn test_load_order_book() {
-        let mut optifi_client = OptifiClient::new(
+        let optifi_client = OptifiClient::new(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
-        optifi_client.load_optifi_exchange();
+        optifi_client.load_optifi_exchange().unwrap();
 
-        optifi_client.load_markets();
+        optifi_client.load_markets().unwrap();
 
-        println!("{:#?}", &optifi_client.account.markets.last().unwrap());
+        println!("{:#?}", &optifi_client.account().markets.last().unwrap());
 
-        let order_book =
-            optifi_client.load_order_book(&optifi_client.account.markets.last().unwrap());
+        let order_book = optifi_client
+            .load_order_book(&optifi_client.account().markets.last().unwrap(), Some(10))
+            .unwrap();
 
         println!("{:#?}", &order_book);
+        println!(
+            "best bid: {:?}, best ask: {:?}",
+            order_book.best_bid(),
+            order_book.best_ask()
+        );
     }
 
     #[test]
     fn test_cancel_order_with_delegation() {
         let optifi_client = OptifiClient::initialize(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             Some(Pubkey::from_str("GRLYbdHJEtC3yu48cPbHwadqfek2Y3C75CLXsGmvoqE1").unwrap()),
-        );
+        )
+        .unwrap();
 
-        let open_orders = optifi_client.load_open_orders(&optifi_client.account.markets[0]);
+        let open_orders = optifi_client
+            .load_open_orders(&optifi_client.account().markets[0])
+            .unwrap();
 
         println!("open_orders: {:#?}", open_orders);
 
         let signature = optifi_client
             .cancel_order(
-                &optifi_client.account.markets[0],
+                &optifi_client.account().markets[0],
                 open_orders[0].side,
                 open_orders[0].client_order_id,
             )
@@ -300,17 +377,21 @@ mod tests {
     fn test_cancel_order() {
         let optifi_client = OptifiClient::initialize(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
-        let open_orders = optifi_client.load_open_orders(&optifi_client.account.markets[0]);
+        let open_orders = optifi_client
+            .load_open_orders(&optifi_client.account().markets[0])
+            .unwrap();
 
         println!("open_orders: {:#?}", open_orders);
 
         let signature = optifi_client
             .cancel_order(
-                &optifi_client.account.markets[0],
+                &optifi_client.account().markets[0],
                 open_orders[0].side,
                 open_orders[0].client_order_id,
             )
@@ -323,40 +404,54 @@ mod tests {
     fn test_cancel_all_order() {
         let optifi_client = OptifiClient::initialize(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
+
+        let signatures = optifi_client
+            .cancel_all_order(&optifi_client.account().markets[0])
+            .unwrap();
 
-        optifi_client.cancel_all_order(&optifi_client.account.markets[0]);
+        println!("signatures: {:#?}", signatures);
     }
 
     #[test]
     fn test_subscribe_ask() {
         let optifi_client = OptifiClient::initialize(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
-        println!("{:#?}", &optifi_client.account.markets[0]);
+        println!("{:#?}", &optifi_client.account().markets[0]);
 
-        optifi_client.subscribe_ask(&optifi_client.account.markets[0]);
+        optifi_client
+            .subscribe_ask(&optifi_client.account().markets[0], |levels| {
+                println!("{:#?}", levels)
+            })
+            .unwrap();
     }
 
     #[test]
     fn load_other_user() {
         let optifi_client = OptifiClient::new(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
         let user = Pubkey::from_str("H1HDXTwT8SStYffNkAqVzy3p9PEKoeg94nUhYr4YkvQx").unwrap();
 
         let (user_account_key, ..) =
             get_user_account_pda(&optifi_client.optifi_exchange, &user, &optifi_cpi::id());
 
-        let account: UserAccount = optifi_client.program.account(user_account_key).unwrap();
+        let account: UserAccount = optifi_client.program().account(user_account_key).unwrap();
 
         println!("{:#?}", account);
     }
@@ -365,25 +460,35 @@ mod tests {
     fn test_subscribe_open_orders() {
         let optifi_client = OptifiClient::initialize(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
-        println!("{:#?}", &optifi_client.account.markets[0]);
+        println!("{:#?}", &optifi_client.account().markets[0]);
 
-        optifi_client.subscribe_open_orders(&optifi_client.account.markets[0]);
+        optifi_client
+            .subscribe_open_orders(&optifi_client.account().markets[0], |orders| {
+                println!("{:#?}", orders)
+            })
+            .unwrap();
     }
 
     #[test]
     fn test_subscribe_user_account() {
-        let mut optifi_client = OptifiClient::new(
+        let optifi_client = OptifiClient::new(
             Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
             Some(WALLET_PATH.to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
-        optifi_client.load_user_account();
+        optifi_client.load_user_account().unwrap();
 
-        optifi_client.subscribe_user_account();
+        optifi_client
+            .subscribe_user_account(|user_account| println!("{:#?}", user_account))
+            .unwrap();
     }
 }
@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+
+    use optifi_client::compute_budget::{
+        ComputeBudgetPolicy, ComputeBudgets, Operation, PriorityFee, MAX_COMPUTE_UNITS,
+    };
+    use optifi_client::prelude::*;
+
+    #[test]
+    fn test_compute_budget_instruction() {
+        assert!(ComputeBudgetPolicy::none().instruction(None, 5).is_none());
+
+        assert_eq!(
+            ComputeBudgetPolicy::fixed(600_000).instruction(Some(600_000), 5),
+            Some(ComputeBudgetInstruction::request_units(600_000, 0))
+        );
+
+        // Without a unit limit the request keeps the per instruction default.
+        assert_eq!(
+            ComputeBudgetPolicy::none()
+                .with_priority_fee(PriorityFee::Additional(5_000))
+                .instruction(None, 3),
+            Some(ComputeBudgetInstruction::request_units(600_000, 5_000))
+        );
+
+        // 1 micro-lamport per unit over 600_000 units rounds up to 1 lamport.
+        assert_eq!(
+            ComputeBudgetPolicy::none()
+                .with_priority_fee(PriorityFee::PerUnit(1))
+                .instruction(Some(600_000), 5),
+            Some(ComputeBudgetInstruction::request_units(600_000, 1))
+        );
+
+        assert_eq!(
+            ComputeBudgetPolicy::none()
+                .with_priority_fee(PriorityFee::PerUnit(10_000))
+                .instruction(Some(200_000), 5),
+            Some(ComputeBudgetInstruction::request_units(200_000, 2_000))
+        );
+    }
+
+    #[test]
+    fn test_simulated_units() {
+        assert_eq!(
+            ComputeBudgetPolicy::simulated_units(0.2, MAX_COMPUTE_UNITS, 100_000),
+            120_000
+        );
+        assert_eq!(
+            ComputeBudgetPolicy::simulated_units(0.2, MAX_COMPUTE_UNITS, 1_300_000),
+            MAX_COMPUTE_UNITS
+        );
+        assert_eq!(
+            ComputeBudgetPolicy::simulated_units(-1., MAX_COMPUTE_UNITS, 100_000),
+            100_000
+        );
+    }
+
+    #[test]
+    fn test_compute_budgets() {
+        let mut budgets = ComputeBudgets::default();
+
+        assert_eq!(
+            budgets.get(Operation::PlaceOrder),
+            &ComputeBudgetPolicy::fixed(MAX_COMPUTE_UNITS)
+        );
+        assert_eq!(budgets.get(Operation::Other), &ComputeBudgetPolicy::none());
+
+        budgets.set(Operation::Other, ComputeBudgetPolicy::simulated(0.1));
+
+        assert_eq!(
+            budgets.get(Operation::Other),
+            &ComputeBudgetPolicy::simulated(0.1)
+        );
+    }
+}
@@ -2,17 +2,20 @@
 mod tests {
 
     use optifi_client::cranker::*;
+    use optifi_client::network::Network;
     use optifi_client::prelude::*;
 
     #[test]
     fn test_fetch_all_user_accounts() {
         let optifi_client = OptifiClient::new(
             Cluster::Devnet,
+            Network::Devnet,
             Some("~/.config/solana/optifi.json".to_owned()),
             None,
-        );
+        )
+        .unwrap();
 
-        let user_accounts = optifi_client.fetch_all_user_accounts();
+        let user_accounts = optifi_client.fetch_all_user_accounts().unwrap();
 
         println!("user_accounts: {:?}", user_accounts.len());
     }
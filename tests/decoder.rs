@@ -0,0 +1,199 @@
+#[cfg(test)]
+mod tests {
+
+    use optifi_client::decoder::{
+        MarketState, OpenOrdersView, OrderSlab, ACCOUNT_FLAG_ASKS, ACCOUNT_FLAG_BIDS,
+        ACCOUNT_FLAG_INITIALIZED, ACCOUNT_FLAG_MARKET, ACCOUNT_FLAG_OPEN_ORDERS,
+    };
+    use optifi_client::prelude::*;
+
+    fn account(flags: u64, body: &[u8]) -> Vec<u8> {
+        let mut data = b"serum".to_vec();
+        data.extend_from_slice(&(ACCOUNT_FLAG_INITIALIZED | flags).to_le_bytes());
+        data.extend_from_slice(body);
+        data.extend_from_slice(b"padding");
+        data
+    }
+
+    fn inner_node(prefix_len: u32, key: u128, children: [u32; 2]) -> Vec<u8> {
+        let mut node = 1u32.to_le_bytes().to_vec();
+        node.extend_from_slice(&prefix_len.to_le_bytes());
+        node.extend_from_slice(&key.to_le_bytes());
+        node.extend_from_slice(&children[0].to_le_bytes());
+        node.extend_from_slice(&children[1].to_le_bytes());
+        node.resize(72, 0);
+        node
+    }
+
+    fn leaf_node(key: u128, owner: &Pubkey, quantity: u64, client_order_id: u64) -> Vec<u8> {
+        let mut node = 2u32.to_le_bytes().to_vec();
+        node.extend_from_slice(&[3, 0, 0, 0]);
+        node.extend_from_slice(&key.to_le_bytes());
+        node.extend_from_slice(owner.as_ref());
+        node.extend_from_slice(&quantity.to_le_bytes());
+        node.extend_from_slice(&client_order_id.to_le_bytes());
+        node
+    }
+
+    fn key(price: u64, seq_num: u64) -> u128 {
+        (u128::from(price) << 64) | u128::from(seq_num)
+    }
+
+    /// Asks at prices 100 and 200 under one inner node.
+    fn asks(owner: &Pubkey) -> Vec<u8> {
+        let mut body = vec![];
+        body.extend_from_slice(&3u64.to_le_bytes()); // bump_index
+        body.extend_from_slice(&0u64.to_le_bytes()); // free_list_len
+        body.extend_from_slice(&0u32.to_le_bytes()); // free_list_head
+        body.extend_from_slice(&0u32.to_le_bytes()); // root_node
+        body.extend_from_slice(&2u64.to_le_bytes()); // leaf_count
+        body.extend(inner_node(56, key(100, 1), [1, 2]));
+        body.extend(leaf_node(key(100, 1), owner, 10, 7));
+        body.extend(leaf_node(key(200, 2), owner, 20, 9));
+        account(ACCOUNT_FLAG_ASKS, &body)
+    }
+
+    #[test]
+    fn test_order_slab() {
+        let owner = Pubkey::new_unique();
+        let data = asks(&owner);
+
+        let slab = OrderSlab::decode(&data, OrderSide::Ask).unwrap();
+
+        assert_eq!(slab.len(), 2);
+
+        let orders = slab.orders().unwrap();
+
+        assert_eq!(
+            orders
+                .iter()
+                .map(|order| (order.price(), order.quantity, order.client_order_id))
+                .collect::<Vec<_>>(),
+            vec![(100, 10, 7), (200, 20, 9)]
+        );
+        assert_eq!(orders[0].owner, owner);
+        assert_eq!(orders[0].owner_slot, 3);
+
+        assert_eq!(slab.find(key(200, 2)).unwrap(), Some(orders[1]));
+        assert_eq!(slab.find(key(200, 3)).unwrap(), None);
+        assert_eq!(slab.find(key(50, 1)).unwrap(), None);
+
+        assert!(OrderSlab::decode(&data, OrderSide::Bid).is_err());
+        assert!(OrderSlab::decode(&data[..40], OrderSide::Ask).is_err());
+
+        let mut empty = vec![0; 32];
+        empty.extend(leaf_node(0, &owner, 0, 0));
+        let empty = account(ACCOUNT_FLAG_BIDS, &empty);
+
+        let slab = OrderSlab::decode(&empty, OrderSide::Bid).unwrap();
+
+        assert!(slab.is_empty());
+        assert!(slab.orders().unwrap().is_empty());
+        assert_eq!(slab.find(key(100, 1)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_market_state() {
+        let pubkeys: Vec<Pubkey> = (0..9).map(|_| Pubkey::new_unique()).collect();
+
+        let mut body = vec![];
+        body.extend_from_slice(pubkeys[0].as_ref()); // own_address
+        body.extend_from_slice(&4u64.to_le_bytes()); // vault_signer_nonce
+        body.extend_from_slice(pubkeys[1].as_ref()); // coin_mint
+        body.extend_from_slice(pubkeys[2].as_ref()); // pc_mint
+        body.extend_from_slice(pubkeys[3].as_ref()); // coin_vault
+        body.extend_from_slice(&[0; 16]); // coin deposits and fees
+        body.extend_from_slice(pubkeys[4].as_ref()); // pc_vault
+        body.extend_from_slice(&[0; 24]); // pc deposits, fees and dust threshold
+        body.extend_from_slice(pubkeys[5].as_ref()); // req_q
+        body.extend_from_slice(pubkeys[6].as_ref()); // event_q
+        body.extend_from_slice(pubkeys[7].as_ref()); // bids
+        body.extend_from_slice(pubkeys[8].as_ref()); // asks
+        body.extend_from_slice(&1_000u64.to_le_bytes()); // coin_lot_size
+        body.extend_from_slice(&10u64.to_le_bytes()); // pc_lot_size
+        body.extend_from_slice(&22u64.to_le_bytes()); // fee_rate_bps
+        body.extend_from_slice(&0u64.to_le_bytes()); // referrer_rebates_accrued
+
+        let data = account(ACCOUNT_FLAG_MARKET, &body);
+
+        assert_eq!(data.len(), 388);
+
+        let market_state = MarketState::decode(&data).unwrap();
+
+        assert_eq!(
+            market_state,
+            MarketState {
+                own_address: pubkeys[0],
+                vault_signer_nonce: 4,
+                coin_mint: pubkeys[1],
+                pc_mint: pubkeys[2],
+                coin_vault: pubkeys[3],
+                pc_vault: pubkeys[4],
+                req_q: pubkeys[5],
+                event_q: pubkeys[6],
+                bids: pubkeys[7],
+                asks: pubkeys[8],
+                coin_lot_size: 1_000,
+                pc_lot_size: 10,
+                fee_rate_bps: 22,
+            }
+        );
+
+        assert!(MarketState::decode(&account(ACCOUNT_FLAG_ASKS, &body)).is_err());
+    }
+
+    #[test]
+    fn test_open_orders() {
+        let market = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        let mut orders = [0u128; 128];
+        let mut client_order_ids = [0u64; 128];
+
+        orders[0] = key(100, 1);
+        client_order_ids[0] = 7;
+        orders[3] = key(200, 2);
+        client_order_ids[3] = 9;
+
+        let mut body = vec![];
+        body.extend_from_slice(market.as_ref());
+        body.extend_from_slice(owner.as_ref());
+        body.extend_from_slice(&[0; 32]); // native coin and pc amounts
+        body.extend_from_slice(&(!0b1001u128).to_le_bytes()); // free_slot_bits
+        body.extend_from_slice(&0b0001u128.to_le_bytes()); // is_bid_bits
+        orders
+            .iter()
+            .for_each(|order| body.extend_from_slice(&order.to_le_bytes()));
+        client_order_ids
+            .iter()
+            .for_each(|id| body.extend_from_slice(&id.to_le_bytes()));
+        body.extend_from_slice(&0u64.to_le_bytes()); // referrer_rebates_accrued
+
+        let data = account(ACCOUNT_FLAG_OPEN_ORDERS, &body);
+
+        assert_eq!(data.len(), 3228);
+
+        let open_orders = OpenOrdersView::decode(&data).unwrap();
+
+        assert_eq!(open_orders.market(), market);
+        assert_eq!(open_orders.owner(), owner);
+
+        let orders: Vec<_> = open_orders
+            .orders()
+            .iter()
+            .map(|order| {
+                (
+                    order.slot,
+                    matches!(order.side, OrderSide::Bid),
+                    order.order_id,
+                    order.client_order_id,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            orders,
+            vec![(0, true, key(100, 1), 7), (3, false, key(200, 2), 9)]
+        );
+    }
+}
@@ -0,0 +1,123 @@
+#[cfg(test)]
+mod tests {
+
+    use optifi_client::client::{OptifiAccount, OptifiClient};
+    use optifi_client::config::OptifiConfig;
+    use optifi_client::instructions::{requires_signature, transaction_size, InstructionBuilder};
+    use optifi_client::network::Network;
+    use optifi_client::prelude::*;
+
+    const RPC: &str = "https://devnet.genesysgo.net";
+
+    const WALLET_PATH: &str = "~/.config/solana/optifi.json";
+
+    #[test]
+    fn test_builder_without_network() {
+        let config = OptifiConfig::for_network(&Network::default()).unwrap();
+        let account = OptifiAccount::default();
+
+        let payer = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        let builder = InstructionBuilder {
+            config: &config,
+            account: &account,
+            payer,
+            owner,
+            user_account: Pubkey::new_unique(),
+        };
+
+        let instructions = builder.set_delegation(Some(Pubkey::new_unique()));
+        assert_eq!(instructions.len(), 1);
+        assert_eq!(instructions[0].program_id, config.optifi_program_id);
+        assert!(requires_signature(&instructions, &owner));
+        assert!(!requires_signature(&instructions, &payer));
+
+        let instructions = builder.deposit(1_000_000, Pubkey::new_unique());
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[0].program_id, config.optifi_usdc_program_id);
+        assert_eq!(instructions[1].program_id, config.optifi_program_id);
+
+        // Margin stress needs the cached exchange.
+        assert!(builder.margin_stress_calculate(Asset::Bitcoin).is_err());
+    }
+
+    #[test]
+    fn test_transaction_size() {
+        let payer = Pubkey::new_unique();
+
+        let transfer = system_instruction::transfer(&payer, &Pubkey::new_unique(), 1);
+
+        assert_eq!(transaction_size(&[transfer], &payer), 215);
+    }
+
+    #[test]
+    fn test_place_order_instructions() {
+        let optifi_client = OptifiClient::initialize(
+            Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
+            Some(WALLET_PATH.to_owned()),
+            None,
+        )
+        .unwrap();
+
+        let market = &optifi_client.account().markets[0];
+
+        let setup = optifi_client
+            .ensure_market_ready_instructions(market)
+            .unwrap();
+
+        let batches = optifi_client
+            .place_order_instructions(
+                market,
+                OrderSide::Bid,
+                "10".parse().unwrap(),
+                "0.01".parse().unwrap(),
+                OrderType::Limit,
+            )
+            .unwrap();
+
+        // The setup is never dropped: it either leads the order's batch or is a batch of its own.
+        let order_batch = batches.last().unwrap();
+        assert_eq!(order_batch.orders, 0..1);
+        match batches.as_slice() {
+            [batch] => assert_eq!(batch.setup, setup.len()),
+            [setup_batch, _] => {
+                assert!(setup_batch.orders.is_empty());
+                assert_eq!(setup_batch.instructions.len(), setup.len());
+                assert_eq!(order_batch.setup, 0);
+            }
+            _ => panic!("expected at most one setup batch, got {}", batches.len()),
+        }
+        // margin stress, place order, consume events, settle funds, margin calculate
+        assert_eq!(order_batch.instructions.len(), order_batch.setup + 5);
+
+        optifi_client.ensure_market_ready(market).unwrap();
+
+        let batches = optifi_client
+            .place_order_instructions(
+                market,
+                OrderSide::Bid,
+                "10".parse().unwrap(),
+                "0.01".parse().unwrap(),
+                OrderType::Limit,
+            )
+            .unwrap();
+
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].setup, 0);
+        assert_eq!(batches[0].instructions.len(), 5);
+        assert!(requires_signature(
+            &batches[0].instructions,
+            &optifi_client.owner.pubkey()
+        ));
+
+        let instructions = optifi_client.settle_order_instructions(market).unwrap();
+
+        assert_eq!(instructions.len(), 4);
+        assert!(!requires_signature(
+            &instructions,
+            &optifi_client.owner.pubkey()
+        ));
+    }
+}
@@ -0,0 +1,124 @@
+#[cfg(test)]
+mod tests {
+
+    use std::path::Path;
+
+    use optifi_client::builder::OptifiClientBuilder;
+    use optifi_client::market_cache::MarketCache;
+    use optifi_client::network::Network;
+    use optifi_client::prelude::*;
+
+    const RPC: &str = "https://devnet.genesysgo.net";
+
+    const WALLET_PATH: &str = "~/.config/solana/optifi.json";
+
+    #[test]
+    fn test_market_cache_file() {
+        let dir = std::env::temp_dir().join(format!("optifi-cache-{}", Pubkey::new_unique()));
+
+        let optifi_exchange = Pubkey::new_unique();
+        let path = MarketCache::path(&dir, &optifi_exchange);
+
+        assert_eq!(path, dir.join(format!("markets-{}.json", optifi_exchange)));
+
+        assert!(MarketCache::read(&path, &optifi_exchange)
+            .unwrap()
+            .is_none());
+
+        MarketCache {
+            optifi_exchange,
+            markets: vec![],
+        }
+        .write(&path)
+        .unwrap();
+
+        let cache = MarketCache::read(&path, &optifi_exchange).unwrap().unwrap();
+
+        assert_eq!(cache.optifi_exchange, optifi_exchange);
+        assert!(cache.markets.is_empty());
+
+        // A cache of another exchange is ignored.
+        assert!(MarketCache::read(&path, &Pubkey::new_unique())
+            .unwrap()
+            .is_none());
+
+        std::fs::write(
+            &path,
+            json!({
+                "version": 0,
+                "optifi_exchange": optifi_exchange.to_string(),
+                "markets": [],
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        assert!(MarketCache::read(&path, &optifi_exchange)
+            .unwrap()
+            .is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(!Path::new(&dir).exists());
+    }
+
+    #[test]
+    fn test_load_markets_cached() {
+        let dir = std::env::temp_dir().join(format!("optifi-cache-{}", Pubkey::new_unique()));
+
+        let optifi_client = OptifiClientBuilder::new(Cluster::from_str(RPC).unwrap())
+            .network(Network::Devnet)
+            .wallet_path(WALLET_PATH)
+            .market_cache_dir(&dir)
+            .build()
+            .unwrap();
+
+        optifi_client.load_optifi_exchange().unwrap();
+
+        let start = Instant::now();
+        let failures = optifi_client.load_markets_cached().unwrap();
+
+        println!(
+            "cold: {} markets in {:?}, failures: {:#?}",
+            optifi_client.account().markets.len(),
+            start.elapsed(),
+            failures
+        );
+
+        let start = Instant::now();
+        let failures = optifi_client.load_markets_cached().unwrap();
+
+        println!(
+            "warm: {} markets in {:?}, failures: {:#?}",
+            optifi_client.account().markets.len(),
+            start.elapsed(),
+            failures
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_markets_cached_unwritable_cache() {
+        // A file where the cache directory should be makes every write fail.
+        let dir = std::env::temp_dir().join(format!("optifi-cache-{}", Pubkey::new_unique()));
+        std::fs::write(&dir, "").unwrap();
+
+        let optifi_client = OptifiClientBuilder::new(Cluster::from_str(RPC).unwrap())
+            .network(Network::Devnet)
+            .wallet_path(WALLET_PATH)
+            .market_cache_dir(&dir)
+            .build()
+            .unwrap();
+
+        optifi_client.load_optifi_exchange().unwrap();
+
+        assert!(optifi_client.save_market_cache().is_err());
+
+        let failures = optifi_client.load_markets_cached().unwrap();
+
+        assert!(!optifi_client.account().markets.is_empty());
+        println!("failures: {:#?}", failures);
+
+        std::fs::remove_file(&dir).unwrap();
+    }
+}
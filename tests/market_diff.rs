@@ -0,0 +1,232 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::mpsc;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use optifi_client::client::{Market, OptifiAccount, OptifiClient};
+    use optifi_client::market_diff::{diff_markets, MarketDiff};
+    use optifi_client::network::Network;
+    use optifi_client::prelude::*;
+
+    const RPC: &str = "https://devnet.genesysgo.net";
+
+    const WALLET_PATH: &str = "~/.config/solana/optifi.json";
+
+    const NOW: u64 = 1_660_000_000;
+
+    fn market(instrument: Pubkey, expiry_date: u64) -> Market {
+        let optifi_market_pubkey = Pubkey::new_unique();
+
+        Market {
+            optifi_market: OptifiMarket {
+                instrument,
+                ..OptifiMarket::default()
+            },
+            optifi_market_key_data: OptifiMarketKeyData {
+                optifi_market_pubkey,
+                expiry_date,
+                is_stopped: false,
+            },
+            instrument_common: InstrumentCommon {
+                asset: Asset::Bitcoin,
+                expiry_date,
+                expiry_type: ExpiryType::Standard,
+            },
+            strike: 20_000,
+            instrument_type: InstrumentType::Call,
+            market_pubkeys: MarketPubkeys {
+                market: Box::new(Pubkey::new_unique()),
+                req_q: Box::new(Pubkey::new_unique()),
+                event_q: Box::new(Pubkey::new_unique()),
+                bids: Box::new(Pubkey::new_unique()),
+                asks: Box::new(Pubkey::new_unique()),
+                coin_vault: Box::new(Pubkey::new_unique()),
+                pc_vault: Box::new(Pubkey::new_unique()),
+                vault_signer_key: Box::new(Pubkey::new_unique()),
+            },
+            serum_account: anchor_client::solana_sdk::account::Account::default(),
+        }
+    }
+
+    /// The same market listing `instrument` instead.
+    fn relist(market: &Market, instrument: Pubkey, expiry_date: u64) -> Market {
+        let mut relisted = market.clone();
+        relisted.optifi_market.instrument = instrument;
+        relisted.optifi_market_key_data.expiry_date = expiry_date;
+        relisted.instrument_common.expiry_date = expiry_date;
+        relisted
+    }
+
+    /// An exchange listing `markets`, the stopped ones flagged.
+    fn exchange(markets: &[(&Market, bool)]) -> Exchange {
+        Exchange {
+            markets: markets
+                .iter()
+                .map(|(market, is_stopped)| OptifiMarketKeyData {
+                    is_stopped: *is_stopped,
+                    ..market.optifi_market_key_data
+                })
+                .collect(),
+            ..Exchange::default()
+        }
+    }
+
+    fn pubkeys(markets: &[Market]) -> Vec<Pubkey> {
+        markets
+            .iter()
+            .map(|market| market.optifi_market_key_data.optifi_market_pubkey)
+            .collect()
+    }
+
+    fn pubkey(market: &Market) -> Pubkey {
+        market.optifi_market_key_data.optifi_market_pubkey
+    }
+
+    #[test]
+    fn test_empty_diff() {
+        assert!(MarketDiff::default().is_empty());
+
+        let live = market(Pubkey::new_unique(), NOW + 3600);
+
+        let previous = OptifiAccount {
+            optifi_exchange: Some(exchange(&[(&live, false)])),
+            user_account: None,
+            markets: vec![live.clone()],
+        };
+
+        let diff = diff_markets(&previous, &exchange(&[(&live, false)]), &[live], NOW);
+
+        assert!(diff.is_empty());
+        assert!(diff.failures.is_empty());
+    }
+
+    #[test]
+    fn test_diff_markets() {
+        let unchanged = market(Pubkey::new_unique(), NOW + 3600);
+        let stopped = market(Pubkey::new_unique(), NOW + 3600);
+        let delisted = market(Pubkey::new_unique(), NOW + 3600);
+        let expiring = market(Pubkey::new_unique(), NOW);
+        let replaced = market(Pubkey::new_unique(), NOW - 60);
+        let restarted = market(Pubkey::new_unique(), NOW + 3600);
+        let added = market(Pubkey::new_unique(), NOW + 7200);
+        let added_expired = market(Pubkey::new_unique(), NOW - 60);
+
+        let replacement = relist(&replaced, Pubkey::new_unique(), NOW + 7200);
+
+        // `restarted` was listed but stopped, so it was not cached.
+        let previous = OptifiAccount {
+            optifi_exchange: Some(exchange(&[
+                (&unchanged, false),
+                (&stopped, false),
+                (&delisted, false),
+                (&expiring, false),
+                (&replaced, false),
+                (&restarted, true),
+            ])),
+            user_account: None,
+            markets: vec![
+                unchanged.clone(),
+                stopped.clone(),
+                delisted.clone(),
+                expiring.clone(),
+                replaced.clone(),
+            ],
+        };
+
+        let optifi_exchange = exchange(&[
+            (&unchanged, false),
+            (&stopped, true),
+            (&expiring, false),
+            (&replacement, false),
+            (&restarted, false),
+            (&added, false),
+            (&added_expired, false),
+        ]);
+
+        let markets = vec![
+            unchanged,
+            expiring.clone(),
+            replacement.clone(),
+            restarted.clone(),
+            added.clone(),
+            added_expired,
+        ];
+
+        let diff = diff_markets(&previous, &optifi_exchange, &markets, NOW);
+
+        assert!(!diff.is_empty());
+        assert_eq!(pubkeys(&diff.added), vec![pubkey(&added)]);
+        assert_eq!(
+            pubkeys(&diff.relisted),
+            vec![pubkey(&replacement), pubkey(&restarted)]
+        );
+        assert_eq!(
+            pubkeys(&diff.stopped),
+            vec![pubkey(&stopped), pubkey(&delisted)]
+        );
+        assert_eq!(
+            pubkeys(&diff.expired),
+            vec![pubkey(&expiring), pubkey(&replaced)]
+        );
+
+        // The expired listing is reported, the new one is the relisting.
+        assert_eq!(
+            diff.expired[1].optifi_market.instrument,
+            replaced.optifi_market.instrument
+        );
+        assert_eq!(
+            diff.relisted[0].optifi_market.instrument,
+            replacement.optifi_market.instrument
+        );
+        assert!(diff.failures.is_empty());
+    }
+
+    #[test]
+    fn test_refresh_markets() {
+        let optifi_client = OptifiClient::initialize(
+            Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
+            Some(WALLET_PATH.to_owned()),
+            None,
+        )
+        .unwrap();
+
+        let diff = optifi_client.refresh_markets().unwrap();
+
+        println!(
+            "added: {}, relisted: {}, stopped: {}, expired: {}, failures: {:#?}",
+            diff.added.len(),
+            diff.relisted.len(),
+            diff.stopped.len(),
+            diff.expired.len(),
+            diff.failures
+        );
+    }
+
+    #[test]
+    fn test_spawn_market_refresh() {
+        let optifi_client = Arc::new(
+            OptifiClient::initialize(
+                Cluster::from_str(RPC).unwrap(),
+                Network::Devnet,
+                Some(WALLET_PATH.to_owned()),
+                None,
+            )
+            .unwrap(),
+        );
+
+        let (tx, rx) = mpsc::channel();
+
+        let handle = optifi_client.spawn_market_refresh(Duration::from_secs(1), move |diff| {
+            let _ = tx.send(diff.map(|diff| diff.is_empty()));
+        });
+
+        let unchanged = rx.recv_timeout(Duration::from_secs(60)).unwrap();
+
+        handle.stop();
+
+        println!("unchanged: {:?}", unchanged);
+    }
+}
@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+
+    use optifi_client::client::OptifiClient;
+    use optifi_client::market_setup::MarketSetup;
+    use optifi_client::network::Network;
+    use optifi_client::prelude::*;
+
+    const RPC: &str = "https://devnet.genesysgo.net";
+
+    const WALLET_PATH: &str = "~/.config/solana/optifi.json";
+
+    #[test]
+    fn test_is_ready() {
+        assert!(!MarketSetup::default().is_ready());
+
+        assert!(!MarketSetup {
+            open_orders: true,
+            long_token_account: true,
+            short_token_account: false,
+        }
+        .is_ready());
+
+        assert!(MarketSetup {
+            open_orders: true,
+            long_token_account: true,
+            short_token_account: true,
+        }
+        .is_ready());
+    }
+
+    #[test]
+    fn test_from_accounts() {
+        let account = || Some(solana_sdk::account::Account::default());
+
+        assert_eq!(MarketSetup::from_accounts(&[]), MarketSetup::default());
+
+        assert_eq!(
+            MarketSetup::from_accounts(&[account(), None, account()]),
+            MarketSetup {
+                open_orders: true,
+                long_token_account: false,
+                short_token_account: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_ensure_market_ready() {
+        let optifi_client = OptifiClient::initialize(
+            Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
+            Some(WALLET_PATH.to_owned()),
+            None,
+        )
+        .unwrap();
+
+        let market = &optifi_client.account().markets[0];
+
+        let setup = optifi_client.load_market_setup(market).unwrap();
+
+        println!("setup: {:?}", setup);
+
+        let instructions = optifi_client
+            .ensure_market_ready_instructions(market)
+            .unwrap();
+
+        println!("setup instructions: {}", instructions.len());
+
+        let outcome = optifi_client.ensure_market_ready(market).unwrap();
+
+        println!("outcome: {:#?}", outcome);
+    }
+}
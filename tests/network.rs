@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use optifi_client::builder::OptifiClientBuilder;
+    use optifi_client::network::{Deployment, Network};
+    use optifi_client::prelude::*;
+
+    #[test]
+    fn test_network_from_str() {
+        assert_eq!(Network::from_str("mainnet").unwrap(), Network::Mainnet);
+        assert_eq!(Network::from_str("Devnet").unwrap(), Network::Devnet);
+        assert_eq!(Network::from_str("l").unwrap(), Network::Localnet);
+        assert!(Network::from_str("testnet").is_err());
+
+        assert_eq!(Network::Devnet.to_string(), "devnet");
+    }
+
+    #[test]
+    fn test_default_network_uses_compiled_addresses() {
+        let network = Network::default();
+
+        assert_eq!(
+            network,
+            if cfg!(feature = "devnet") {
+                Network::Devnet
+            } else {
+                Network::Mainnet
+            }
+        );
+        assert_eq!(
+            network.deployment().unwrap(),
+            Deployment::compiled().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_both_networks_without_feature() {
+        let mainnet = Network::Mainnet.deployment().unwrap();
+        let devnet = Network::Devnet.deployment().unwrap();
+
+        assert_eq!(
+            mainnet.optifi_program_id,
+            Pubkey::from_str("optF8UCDznwL6mJ2UPF4jnHaHbJe5g9RY8CaU846noM").unwrap()
+        );
+        assert_eq!(
+            devnet.optifi_program_id,
+            Pubkey::from_str("opDV2tLVsRPGk9aYqm4gdtGotiRwjuYKUmWzWB7NfCR").unwrap()
+        );
+        assert_ne!(mainnet.optifi_exchange, devnet.optifi_exchange);
+        assert_ne!(
+            mainnet.optifi_usdc_program_id,
+            devnet.optifi_usdc_program_id
+        );
+        assert_ne!(mainnet.serum_dex_program_id, devnet.serum_dex_program_id);
+        assert_ne!(mainnet.usdc_token_mint, devnet.usdc_token_mint);
+
+        for network in [Network::Mainnet, Network::Devnet] {
+            let optifi_client = OptifiClientBuilder::new(network.cluster())
+                .network(network.clone())
+                .signer(Arc::new(Keypair::new()))
+                .build()
+                .unwrap();
+
+            let deployment = network.deployment().unwrap();
+
+            assert_eq!(optifi_client.optifi_exchange, deployment.optifi_exchange);
+            assert_eq!(
+                optifi_client.config.optifi_program_id,
+                deployment.optifi_program_id
+            );
+        }
+    }
+
+    #[test]
+    fn test_other_network_with_overridden_address() {
+        let other = if Network::default() == Network::Mainnet {
+            Network::Devnet
+        } else {
+            Network::Mainnet
+        };
+
+        let optifi_exchange = Pubkey::new_unique();
+
+        let optifi_client = OptifiClientBuilder::new(other.cluster())
+            .network(other.clone())
+            .signer(Arc::new(Keypair::new()))
+            .optifi_exchange(optifi_exchange)
+            .build()
+            .unwrap();
+
+        assert_eq!(optifi_client.optifi_exchange, optifi_exchange);
+        assert_eq!(
+            optifi_client.usdc_token_mint,
+            other.deployment().unwrap().usdc_token_mint
+        );
+    }
+
+    #[test]
+    fn test_custom_network() {
+        let deployment = Deployment {
+            optifi_exchange: Pubkey::new_unique(),
+            optifi_program_id: Pubkey::new_unique(),
+            optifi_usdc_program_id: Pubkey::new_unique(),
+            serum_dex_program_id: Pubkey::new_unique(),
+            usdc_token_mint: Pubkey::new_unique(),
+        };
+
+        let optifi_client = OptifiClientBuilder::new(Cluster::Localnet)
+            .network(Network::Custom(deployment.clone()))
+            .signer(Arc::new(Keypair::new()))
+            .build()
+            .unwrap();
+
+        assert_eq!(optifi_client.optifi_exchange, deployment.optifi_exchange);
+        assert_eq!(
+            optifi_client.config.optifi_program_id,
+            deployment.optifi_program_id
+        );
+        assert_eq!(
+            optifi_client.config.serum_dex_program_id,
+            deployment.serum_dex_program_id
+        );
+        assert_eq!(optifi_client.usdc_token_mint, deployment.usdc_token_mint);
+    }
+}
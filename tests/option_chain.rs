@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod tests {
+
+    use std::collections::BTreeMap;
+
+    use optifi_client::client::OptifiClient;
+    use optifi_client::network::Network;
+    use optifi_client::option_chain::{ExpiryChain, StrikeMarkets};
+    use optifi_client::prelude::*;
+    use optifi_client::types::Price;
+
+    const RPC: &str = "https://devnet.genesysgo.net";
+
+    const WALLET_PATH: &str = "~/.config/solana/optifi.json";
+
+    fn expiry(strikes: &[u32]) -> ExpiryChain {
+        ExpiryChain {
+            expiry_date: 1_666_944_000,
+            strikes: strikes
+                .iter()
+                .map(|&strike| {
+                    (
+                        strike,
+                        StrikeMarkets {
+                            strike,
+                            call: None,
+                            put: None,
+                        },
+                    )
+                })
+                .collect::<BTreeMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn test_nearest_strike() {
+        let chain = expiry(&[22000, 18000, 20000]);
+
+        assert_eq!(chain.strikes(), vec![18000, 20000, 22000]);
+
+        let nearest = |spot: &str| {
+            chain
+                .nearest_strike(Price::from_str(spot).unwrap())
+                .map(|strike| strike.strike)
+        };
+
+        assert_eq!(nearest("19500.5"), Some(20000));
+        assert_eq!(nearest("19000"), Some(18000));
+        assert_eq!(nearest("1"), Some(18000));
+        assert_eq!(nearest("30000"), Some(22000));
+
+        assert_eq!(
+            expiry(&[])
+                .nearest_strike(Price::from_str("1").unwrap())
+                .map(|s| s.strike),
+            None
+        );
+    }
+
+    #[test]
+    fn test_option_chain() {
+        let optifi_client = OptifiClient::initialize(
+            Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
+            Some(WALLET_PATH.to_owned()),
+            None,
+        )
+        .unwrap();
+
+        let chain = optifi_client.option_chain();
+
+        for asset in chain.assets.iter() {
+            println!("asset: {:?}", asset.asset);
+
+            for expiry_date in asset.expiries() {
+                println!(
+                    "expiry: {}, strikes: {:?}",
+                    expiry_date,
+                    chain.strikes(asset.asset, expiry_date)
+                );
+            }
+        }
+    }
+}
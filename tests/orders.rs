@@ -0,0 +1,224 @@
+#[cfg(test)]
+mod tests {
+
+    use optifi_client::client::OptifiClient;
+    use optifi_client::decoder::OpenOrder;
+    use optifi_client::network::Network;
+    use optifi_client::orders::{match_client_order_ids, OrderRequest};
+    use optifi_client::prelude::*;
+    use optifi_client::types::Price;
+
+    const RPC: &str = "https://devnet.genesysgo.net";
+
+    const WALLET_PATH: &str = "~/.config/solana/optifi.json";
+
+    fn quotes() -> Vec<OrderRequest> {
+        (1..=4)
+            .flat_map(|i| {
+                [
+                    OrderRequest {
+                        side: OrderSide::Bid,
+                        price: Price::from(Decimal::from(i)),
+                        size: "0.01".parse().unwrap(),
+                        order_type: OrderType::Limit,
+                    },
+                    OrderRequest {
+                        side: OrderSide::Ask,
+                        price: Price::from(Decimal::from(100 + i)),
+                        size: "0.01".parse().unwrap(),
+                        order_type: OrderType::Limit,
+                    },
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_match_client_order_ids() {
+        let asset = Asset::Bitcoin;
+
+        let request = |side, price| OrderRequest {
+            side,
+            price: Price::from_native(asset, price),
+            size: "0.01".parse().unwrap(),
+            order_type: OrderType::Limit,
+        };
+
+        let open_order = |side, price: u64, client_order_id| OpenOrder {
+            slot: 0,
+            side,
+            order_id: u128::from(price) << 64,
+            client_order_id,
+        };
+
+        let requests = [
+            request(OrderSide::Bid, 100),
+            request(OrderSide::Bid, 100),
+            request(OrderSide::Ask, 105),
+            request(OrderSide::Ask, 110),
+        ];
+
+        // The ask at 110 filled, the rest rested in another order.
+        let placed = vec![
+            open_order(OrderSide::Ask, 105, 12),
+            open_order(OrderSide::Bid, 100, 11),
+            open_order(OrderSide::Bid, 100, 10),
+        ];
+
+        assert_eq!(
+            match_client_order_ids(asset, &requests, placed),
+            vec![Some(10), Some(11), Some(12), None]
+        );
+    }
+
+    #[test]
+    fn test_place_orders_instructions() {
+        let optifi_client = OptifiClient::initialize(
+            Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
+            Some(WALLET_PATH.to_owned()),
+            None,
+        )
+        .unwrap();
+
+        let market = &optifi_client.account().markets[0];
+        let orders = quotes();
+
+        let batches = optifi_client
+            .place_orders_instructions(market, &orders)
+            .unwrap();
+
+        assert_eq!(batches.first().unwrap().orders.start, 0);
+        assert_eq!(batches.last().unwrap().orders.end, orders.len());
+
+        let setup = optifi_client
+            .ensure_market_ready_instructions(market)
+            .unwrap();
+
+        for (i, batch) in batches.iter().enumerate() {
+            assert!(batch.orders.len() <= optifi_client.config.max_orders_per_transaction);
+
+            // only the first batch creates the missing accounts, with or without orders
+            if i > 0 || setup.is_empty() {
+                assert_eq!(batch.setup, 0);
+            } else {
+                assert_eq!(batch.setup, setup.len());
+            }
+
+            if batch.orders.is_empty() {
+                assert_eq!(batch.instructions.len(), batch.setup);
+            } else {
+                // margin stress, place orders, consume events, settle funds, margin calculate
+                assert_eq!(
+                    batch.instructions.len(),
+                    batch.setup + batch.orders.len() + 4
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_place_orders() {
+        let optifi_client = OptifiClient::initialize(
+            Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
+            Some(WALLET_PATH.to_owned()),
+            None,
+        )
+        .unwrap();
+
+        let results = optifi_client
+            .place_orders(&optifi_client.account().markets[0], &quotes())
+            .unwrap();
+
+        for result in results.iter() {
+            println!(
+                "{:?} placed: {} client order id: {:?} caused failure: {}",
+                result.request,
+                result.is_placed(),
+                result.client_order_id,
+                result.caused_failure()
+            );
+        }
+    }
+
+    #[test]
+    fn test_cancel_all_markets() {
+        let optifi_client = OptifiClient::initialize(
+            Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
+            Some(WALLET_PATH.to_owned()),
+            None,
+        )
+        .unwrap();
+
+        for (optifi_market, results) in optifi_client.cancel_all_markets() {
+            match results {
+                Ok(results) => {
+                    for result in results.iter() {
+                        println!(
+                            "{} {:?} {} cancelled: {} error: {:?}",
+                            result.optifi_market,
+                            result.side,
+                            result.client_order_id,
+                            result.is_cancelled(),
+                            result.outcome.as_ref().err()
+                        );
+                    }
+                }
+                Err(err) => println!("{} failed: {}", optifi_market, err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_replace_order() {
+        let optifi_client = OptifiClient::initialize(
+            Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
+            Some(WALLET_PATH.to_owned()),
+            None,
+        )
+        .unwrap();
+
+        let market = &optifi_client.account().markets[0];
+
+        let order = optifi_client
+            .load_open_orders(market)
+            .unwrap()
+            .pop()
+            .unwrap();
+
+        let instructions = optifi_client
+            .replace_order_instructions(
+                market,
+                order.side,
+                order.client_order_id,
+                "2".parse().unwrap(),
+                "0.01".parse().unwrap(),
+            )
+            .unwrap();
+
+        // margin stress, cancel, place, consume events, settle funds, margin calculate
+        assert_eq!(instructions.len(), 6);
+
+        let placed = optifi_client
+            .replace_order(
+                market,
+                order.side,
+                order.client_order_id,
+                "2".parse().unwrap(),
+                "0.01".parse().unwrap(),
+            )
+            .unwrap();
+
+        println!("placed: {:#?}", placed);
+
+        if let Some(client_order_id) = placed.client_order_id {
+            assert!(optifi_client
+                .load_client_order_ids(market)
+                .unwrap()
+                .contains(&client_order_id));
+        }
+    }
+}
@@ -0,0 +1,207 @@
+#[cfg(test)]
+mod tests {
+
+    use optifi_client::config::OptifiConfig;
+    use optifi_client::network::Network;
+    use optifi_client::prelude::*;
+    use optifi_client::program_error::{
+        decode_failure, parse_failed_program, FailureReason, OptifiUsdcErrorCode,
+    };
+    use solana_sdk::instruction::InstructionError;
+    use solana_sdk::transaction::TransactionError;
+
+    fn instructions(program_ids: &[Pubkey]) -> Vec<Instruction> {
+        program_ids
+            .iter()
+            .map(|program_id| Instruction::new_with_bytes(*program_id, &[], vec![]))
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_optifi_error() {
+        let config = OptifiConfig::for_network(&Network::default()).unwrap();
+        let optifi = config.optifi_program_id;
+
+        let logs = vec![
+            format!("Program {} invoke [1]", optifi),
+            "Program log: AnchorError occurred. Error Code: InsufficientMargin. Error Number: 6010. Error Message: Insufficient Margin!.".to_owned(),
+            format!("Program {} consumed 30000 of 1300000 compute units", optifi),
+            format!("Program {} failed: custom program error: 0x177a", optifi),
+        ];
+
+        let failure = decode_failure(
+            &config,
+            &instructions(&[optifi, optifi]),
+            &TransactionError::InstructionError(1, InstructionError::Custom(6010)),
+            &logs,
+        )
+        .unwrap();
+
+        assert_eq!(failure.instruction_index, 1);
+        assert_eq!(failure.program_id, Some(optifi));
+        assert_eq!(failure.reason.code(), Some(6010));
+
+        match &failure.reason {
+            FailureReason::Optifi {
+                error: OptifiErrorCode::InsufficientMargin,
+                log: Some(log),
+            } => assert_eq!(log.code, "InsufficientMargin"),
+            reason => panic!("unexpected reason {:?}", reason),
+        }
+
+        assert_eq!(
+            failure.reason.to_string(),
+            "optifi error InsufficientMargin (6010): Insufficient Margin!"
+        );
+    }
+
+    #[test]
+    fn test_decode_serum_error() {
+        let config = OptifiConfig::for_network(&Network::default()).unwrap();
+        let optifi = config.optifi_program_id;
+        let serum = config.serum_dex_program_id;
+
+        let logs = vec![
+            format!("Program {} invoke [1]", optifi),
+            format!("Program {} invoke [2]", serum),
+            format!("Program {} failed: custom program error: 0x2a", serum),
+            format!("Program {} failed: custom program error: 0x2a", optifi),
+        ];
+
+        assert_eq!(parse_failed_program(&logs), Some(serum));
+
+        let failure = decode_failure(
+            &config,
+            &instructions(&[optifi]),
+            &TransactionError::InstructionError(0, InstructionError::Custom(42)),
+            &logs,
+        )
+        .unwrap();
+
+        assert_eq!(failure.program_id, Some(serum));
+        assert!(matches!(
+            failure.reason,
+            FailureReason::SerumDex { code: 42, .. }
+        ));
+    }
+
+    #[test]
+    fn test_decode_by_program() {
+        let config = OptifiConfig::for_network(&Network::default()).unwrap();
+        let optifi = config.optifi_program_id;
+        let optifi_usdc = config.optifi_usdc_program_id;
+
+        let decode = |program_ids: &[Pubkey], code: u32, logs: &[String]| {
+            decode_failure(
+                &config,
+                &instructions(program_ids),
+                &TransactionError::InstructionError(0, InstructionError::Custom(code)),
+                logs,
+            )
+            .unwrap()
+        };
+
+        // Without logs, the failing instruction's program and the code range decide.
+        assert!(matches!(
+            decode(&[optifi], 6010, &[]).reason,
+            FailureReason::Optifi {
+                error: OptifiErrorCode::InsufficientMargin,
+                log: None
+            }
+        ));
+        assert_eq!(
+            decode(&[optifi], 6014, &[]).reason.to_string(),
+            "optifi error CannotRecordPnLForStoppedMarket (6014): Cannot record pnl when market \
+             is stopped!"
+        );
+        assert_eq!(
+            decode(&[optifi_usdc], 6000, &[]).reason,
+            FailureReason::OptifiUsdc {
+                error: OptifiUsdcErrorCode::Locked,
+                log: None
+            }
+        );
+        assert!(matches!(
+            decode(&[optifi], 2003, &[]).reason,
+            FailureReason::Anchor {
+                error: anchor_lang::error::ErrorCode::ConstraintRaw,
+                log: None
+            }
+        ));
+
+        // Codes missing from the program's error table are still reported.
+        assert_eq!(
+            decode(&[optifi], 6999, &[]).reason,
+            FailureReason::Custom {
+                code: 6999,
+                log: None
+            }
+        );
+
+        let failure = decode(&[optifi], 42, &[]);
+
+        assert_eq!(failure.program_id, Some(config.serum_dex_program_id));
+        assert!(matches!(
+            failure.reason,
+            FailureReason::SerumDex { code: 42, .. }
+        ));
+
+        // A nested optifi usdc error is not mistaken for an optifi one.
+        let logs = vec![
+            format!("Program {} invoke [1]", optifi),
+            format!("Program {} invoke [2]", optifi_usdc),
+            "Program log: AnchorError occurred. Error Code: Locked. Error Number: 6000. Error Message: the wrap/unwrap is currently locked.".to_owned(),
+            format!("Program {} failed: custom program error: 0x1770", optifi_usdc),
+            format!("Program {} failed: custom program error: 0x1770", optifi),
+        ];
+
+        let failure = decode(&[optifi], 6000, &logs);
+
+        assert_eq!(failure.program_id, Some(optifi_usdc));
+        assert!(matches!(
+            failure.reason,
+            FailureReason::OptifiUsdc {
+                error: OptifiUsdcErrorCode::Locked,
+                log: Some(..)
+            }
+        ));
+
+        let unknown = Pubkey::new_unique();
+
+        assert_eq!(
+            decode(&[unknown], 6001, &[]).reason,
+            FailureReason::Custom {
+                code: 6001,
+                log: None
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_without_logs() {
+        let config = OptifiConfig::for_network(&Network::default()).unwrap();
+        let optifi = config.optifi_program_id;
+
+        let failure = decode_failure(
+            &config,
+            &instructions(&[optifi]),
+            &TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature),
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(failure.program_id, Some(optifi));
+        assert_eq!(
+            failure.reason,
+            FailureReason::Instruction(InstructionError::MissingRequiredSignature)
+        );
+
+        assert!(decode_failure(
+            &config,
+            &instructions(&[optifi]),
+            &TransactionError::BlockhashNotFound,
+            &[]
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,247 @@
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use optifi_client::client::OptifiClient;
+    use optifi_client::config::OptifiConfig;
+    use optifi_client::error::OptifiClientError;
+    use optifi_client::network::Network;
+    use optifi_client::prelude::*;
+    use optifi_client::send::{
+        confirmed_outcome, is_transient, poll_confirmation, preflight_error, send_failed,
+        Confirmation, OutcomeStatus, RetryPolicy,
+    };
+    use solana_client::client_error::ClientError as RpcClientError;
+    use solana_sdk::instruction::InstructionError;
+    use solana_sdk::transaction::TransactionError;
+    use solana_transaction_status::{TransactionConfirmationStatus, TransactionStatus};
+
+    const RPC: &str = "https://devnet.genesysgo.net";
+
+    const WALLET_PATH: &str = "~/.config/solana/optifi.json";
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::default();
+
+        assert_eq!(policy.backoff(0), Duration::from_millis(250));
+        assert_eq!(policy.backoff(2), Duration::from_secs(1));
+        assert_eq!(policy.backoff(10), policy.max_backoff);
+        assert_eq!(policy.backoff(u32::MAX), policy.max_backoff);
+    }
+
+    #[test]
+    fn test_error_classification() {
+        let io = RpcClientError::from(std::io::Error::new(
+            std::io::ErrorKind::ConnectionReset,
+            "connection reset",
+        ));
+
+        assert!(is_transient(&io));
+        assert!(preflight_error(&io).is_none());
+
+        let expired = RpcClientError::from(TransactionError::BlockhashNotFound);
+
+        assert!(!is_transient(&expired));
+        assert_eq!(
+            preflight_error(&expired),
+            Some(TransactionError::BlockhashNotFound)
+        );
+    }
+
+    #[test]
+    fn test_unconfirmed_keeps_signature() {
+        let signature = Signature::new_unique();
+
+        let err = OptifiClientError::Unconfirmed {
+            signature,
+            source: Box::new(OptifiClientError::Rpc(RpcClientError::from(
+                std::io::Error::new(std::io::ErrorKind::TimedOut, "timed out"),
+            ))),
+        };
+
+        assert_eq!(err.signature(), Some(signature));
+        assert_eq!(
+            OptifiClientError::NotInitialized("user account").signature(),
+            None
+        );
+    }
+
+    fn status(
+        confirmation_status: TransactionConfirmationStatus,
+        err: Option<TransactionError>,
+    ) -> TransactionStatus {
+        TransactionStatus {
+            slot: 42,
+            confirmations: Some(0),
+            status: err.clone().map_or(Ok(()), Err),
+            err,
+            confirmation_status: Some(confirmation_status),
+        }
+    }
+
+    #[test]
+    fn test_poll_confirmation() {
+        let confirmed = CommitmentConfig::confirmed();
+
+        assert_eq!(poll_confirmation(None, confirmed, 100, 100), None);
+        assert_eq!(
+            poll_confirmation(None, confirmed, 101, 100),
+            Some(Confirmation::Expired)
+        );
+
+        // A status short of the commitment keeps polling, even past the last valid height.
+        assert_eq!(
+            poll_confirmation(
+                Some(status(TransactionConfirmationStatus::Processed, None)),
+                confirmed,
+                101,
+                100
+            ),
+            None
+        );
+
+        let error = TransactionError::InstructionError(1, InstructionError::Custom(6000));
+
+        assert_eq!(
+            poll_confirmation(
+                Some(status(
+                    TransactionConfirmationStatus::Confirmed,
+                    Some(error.clone())
+                )),
+                confirmed,
+                100,
+                100
+            ),
+            Some(Confirmation::Landed {
+                slot: 42,
+                error: Some(error)
+            })
+        );
+    }
+
+    #[test]
+    fn test_confirmed_outcome() {
+        let config = OptifiConfig::for_network(&Network::Devnet).unwrap();
+        let signature = Signature::new_unique();
+        let max_resigns = config.retry_policy.max_resigns;
+
+        let outcome = confirmed_outcome(
+            &config,
+            &[],
+            signature,
+            1,
+            Confirmation::Landed {
+                slot: 42,
+                error: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(outcome.status, OutcomeStatus::Confirmed);
+        assert_eq!(outcome.slot, Some(42));
+        assert_eq!(outcome.signature, signature);
+
+        let outcome = confirmed_outcome(
+            &config,
+            &[],
+            signature,
+            1,
+            Confirmation::Landed {
+                slot: 42,
+                error: Some(TransactionError::AccountNotFound),
+            },
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(outcome.status, OutcomeStatus::Failed);
+        assert_eq!(outcome.error, Some(TransactionError::AccountNotFound));
+
+        // Expired transactions are re-signed until the re-signs run out.
+        assert!(confirmed_outcome(
+            &config,
+            &[],
+            signature,
+            max_resigns,
+            Confirmation::Expired,
+            &[]
+        )
+        .is_none());
+
+        let outcome = confirmed_outcome(
+            &config,
+            &[],
+            signature,
+            max_resigns + 1,
+            Confirmation::Expired,
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(outcome.status, OutcomeStatus::Expired);
+        assert_eq!(outcome.attempts, max_resigns + 1);
+    }
+
+    #[test]
+    fn test_send_failed() {
+        let config = OptifiConfig::for_network(&Network::Devnet).unwrap();
+        let signature = Signature::new_unique();
+
+        let expired = || RpcClientError::from(TransactionError::BlockhashNotFound);
+
+        assert!(send_failed(&config, &[], signature, 1, expired())
+            .unwrap()
+            .is_none());
+
+        let outcome = send_failed(
+            &config,
+            &[],
+            signature,
+            config.retry_policy.max_resigns + 1,
+            expired(),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(outcome.status, OutcomeStatus::Rejected);
+
+        let err = send_failed(
+            &config,
+            &[],
+            signature,
+            1,
+            RpcClientError::from(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "timed out",
+            )),
+        )
+        .unwrap_err();
+
+        assert_eq!(err.signature(), Some(signature));
+    }
+
+    #[test]
+    fn test_send_and_confirm() {
+        let optifi_client = OptifiClient::initialize(
+            Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
+            Some(WALLET_PATH.to_owned()),
+            None,
+        )
+        .unwrap();
+
+        let outcome = optifi_client
+            .send_and_confirm(
+                &optifi_client
+                    .settle_order_instructions(&optifi_client.account().markets[0])
+                    .unwrap(),
+                &[],
+            )
+            .unwrap();
+
+        println!("outcome: {:#?}", outcome);
+    }
+}
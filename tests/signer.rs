@@ -0,0 +1,111 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use optifi_client::client::OptifiClient;
+    use optifi_client::network::Network;
+    use optifi_client::prelude::*;
+    use optifi_client::signer::{keypair_from_bytes, keypair_from_env, RemoteSigner};
+
+    const RPC: &str = "https://devnet.genesysgo.net";
+
+    const WALLET_PATH: &str = "~/.config/solana/optifi.json";
+
+    #[test]
+    fn test_keypair_from_env() {
+        let keypair = Keypair::new();
+
+        std::env::set_var(
+            "OPTIFI_TEST_KEYPAIR_JSON",
+            serde_json::to_string(&keypair.to_bytes().to_vec()).unwrap(),
+        );
+        std::env::set_var("OPTIFI_TEST_KEYPAIR_BASE58", keypair.to_base58_string());
+
+        assert_eq!(
+            keypair_from_env("OPTIFI_TEST_KEYPAIR_JSON")
+                .unwrap()
+                .pubkey(),
+            keypair.pubkey()
+        );
+        assert_eq!(
+            keypair_from_env("OPTIFI_TEST_KEYPAIR_BASE58")
+                .unwrap()
+                .pubkey(),
+            keypair.pubkey()
+        );
+        assert!(keypair_from_env("OPTIFI_TEST_KEYPAIR_MISSING").is_err());
+        assert!(keypair_from_bytes(&[0; 10]).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remote_signer_over_unix_socket() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::os::unix::net::UnixListener;
+
+        let keypair = Keypair::new();
+        let pubkey = keypair.pubkey();
+
+        let path = std::env::temp_dir().join(format!("optifi-signer-{}.sock", pubkey));
+        let _ = std::fs::remove_file(&path);
+
+        let listener = UnixListener::bind(&path).unwrap();
+
+        // Stub signer answering a single request.
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+
+            let request: Value = serde_json::from_str(&line).unwrap();
+            let message = solana_sdk::bs58::decode(request["message"].as_str().unwrap())
+                .into_vec()
+                .unwrap();
+
+            let signature = keypair.sign_message(&message);
+
+            writeln!(&stream, "{}", json!({ "signature": signature.to_string() })).unwrap();
+        });
+
+        let signer = RemoteSigner::unix(&path, pubkey);
+
+        let message = b"optifi";
+        let signature = signer.try_sign_message(message).unwrap();
+
+        server.join().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(signer.pubkey(), pubkey);
+        assert!(signature.verify(pubkey.as_ref(), message));
+    }
+
+    #[test]
+    fn test_new_with_signers() {
+        let payer = Keypair::new();
+        let owner = read_keypair_file(shellexpand::tilde(WALLET_PATH).as_ref()).unwrap();
+        let owner_pubkey = owner.pubkey();
+
+        let optifi_client = OptifiClient::new_with_signers(
+            Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
+            Arc::new(payer),
+            Arc::new(owner),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(optifi_client.owner.pubkey(), owner_pubkey);
+        assert_ne!(optifi_client.payer.pubkey(), owner_pubkey);
+
+        let (user_account, ..) = get_user_account_pda(
+            &optifi_client.optifi_exchange,
+            &owner_pubkey,
+            &optifi_cpi::id(),
+        );
+
+        assert_eq!(optifi_client.user_account, user_account);
+    }
+}
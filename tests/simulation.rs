@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod tests {
+
+    use optifi_client::client::OptifiClient;
+    use optifi_client::network::Network;
+    use optifi_client::orders::OrderRequest;
+    use optifi_client::prelude::*;
+    use optifi_client::simulation::{parse_anchor_error, parse_units_consumed};
+
+    const RPC: &str = "https://devnet.genesysgo.net";
+
+    const WALLET_PATH: &str = "~/.config/solana/optifi.json";
+
+    fn logs(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_simulation_logs() {
+        let logs = logs(&[
+            "Program ComputeBudget111111111111111111111111111111 invoke [1]",
+            "Program ComputeBudget111111111111111111111111111111 success",
+            "Program OptifiProgram invoke [1]",
+            "Program log: Instruction: PlaceOrder",
+            "Program SerumDexProgram invoke [2]",
+            "Program SerumDexProgram consumed 20000 of 1300000 compute units",
+            "Program SerumDexProgram success",
+            "Program OptifiProgram consumed 100000 of 1400000 compute units",
+            "Program OptifiProgram success",
+            "Program OptifiProgram invoke [1]",
+            "Program log: AnchorError occurred. Error Code: InsufficientMargin. Error Number: 6012. Error Message: user has insufficient margin.",
+            "Program OptifiProgram consumed 30000 of 1300000 compute units",
+            "Program OptifiProgram failed: custom program error: 0x177c",
+        ]);
+
+        assert_eq!(parse_units_consumed(&logs), 130000);
+
+        let anchor_error = parse_anchor_error(&logs).unwrap();
+
+        assert_eq!(anchor_error.code, "InsufficientMargin");
+        assert_eq!(anchor_error.number, 6012);
+        assert_eq!(anchor_error.message, "user has insufficient margin");
+
+        assert!(parse_anchor_error(&logs[..10]).is_none());
+    }
+
+    #[test]
+    fn test_simulate_place_order() {
+        let optifi_client = OptifiClient::initialize(
+            Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
+            Some(WALLET_PATH.to_owned()),
+            None,
+        )
+        .unwrap();
+
+        let market = &optifi_client.account().markets[0];
+
+        let batches = optifi_client
+            .simulate_place_order(
+                market,
+                OrderSide::Bid,
+                "10".parse().unwrap(),
+                "0.01".parse().unwrap(),
+                OrderType::Limit,
+            )
+            .unwrap();
+
+        // A setup-only batch has no orders.
+        for batch in batches.iter() {
+            println!("orders {:?}: {:#?}", batch.orders, batch.simulation);
+        }
+    }
+
+    #[test]
+    fn test_simulate_place_orders() {
+        let optifi_client = OptifiClient::initialize(
+            Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
+            Some(WALLET_PATH.to_owned()),
+            None,
+        )
+        .unwrap();
+
+        let market = &optifi_client.account().markets[0];
+
+        let orders = [
+            OrderRequest {
+                side: OrderSide::Bid,
+                price: "10".parse().unwrap(),
+                size: "0.01".parse().unwrap(),
+                order_type: OrderType::Limit,
+            },
+            OrderRequest {
+                side: OrderSide::Ask,
+                price: "100".parse().unwrap(),
+                size: "0.01".parse().unwrap(),
+                order_type: OrderType::Limit,
+            },
+        ];
+
+        let batches = optifi_client
+            .simulate_place_orders(market, &orders)
+            .unwrap();
+
+        for batch in batches.iter() {
+            println!(
+                "orders {:?} ok: {} units: {}",
+                batch.orders,
+                batch.simulation.is_ok(),
+                batch.simulation.units_consumed
+            );
+        }
+
+        let batches = optifi_client.simulate_cancel_all_order(market).unwrap();
+
+        println!("cancel all: {:#?}", batches);
+    }
+}
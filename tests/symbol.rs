@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod tests {
+
+    use optifi_client::client::OptifiClient;
+    use optifi_client::network::Network;
+    use optifi_client::prelude::*;
+    use optifi_client::symbol::{ExpiryDate, InstrumentSymbol};
+
+    const RPC: &str = "https://devnet.genesysgo.net";
+
+    const WALLET_PATH: &str = "~/.config/solana/optifi.json";
+
+    #[test]
+    fn test_expiry_date() {
+        let date = |year, month, day| ExpiryDate { year, month, day };
+
+        assert_eq!(
+            ExpiryDate::from_unix_timestamp(1_666_944_000),
+            date(2022, 10, 28)
+        );
+        assert_eq!(
+            ExpiryDate::from_unix_timestamp(951_782_400),
+            date(2000, 2, 29)
+        );
+        assert_eq!(
+            ExpiryDate::from_unix_timestamp(1_709_251_199),
+            date(2024, 2, 29)
+        );
+        assert_eq!(ExpiryDate::from_unix_timestamp(0), date(1970, 1, 1));
+
+        assert_eq!(date(2022, 10, 28).to_string(), "28OCT22");
+        assert_eq!(date(2023, 1, 6).to_string(), "6JAN23");
+
+        assert_eq!(ExpiryDate::from_str("6jan23").unwrap(), date(2023, 1, 6));
+        assert!(ExpiryDate::from_str("28OCT2022").is_err());
+        assert!(ExpiryDate::from_str("0OCT22").is_err());
+        assert!(ExpiryDate::from_str("28XYZ22").is_err());
+    }
+
+    #[test]
+    fn test_instrument_symbol() {
+        let symbol = InstrumentSymbol {
+            asset: Asset::Bitcoin,
+            expiry: ExpiryDate::from_unix_timestamp(1_666_944_000),
+            strike: 20000,
+            is_call: true,
+        };
+
+        assert_eq!(symbol.to_string(), "BTC-28OCT22-20000-C");
+        assert_eq!(
+            InstrumentSymbol::from_str("BTC-28OCT22-20000-C").unwrap(),
+            symbol
+        );
+        assert_eq!(
+            InstrumentSymbol::from_str("btc-28oct22-20000-p").unwrap(),
+            InstrumentSymbol {
+                is_call: false,
+                ..symbol
+            }
+        );
+
+        assert!(InstrumentSymbol::from_str("BTC-28OCT22-20000").is_err());
+        assert!(InstrumentSymbol::from_str("BTC-28OCT22-20000-X").is_err());
+        assert!(InstrumentSymbol::from_str("XYZ-28OCT22-20000-C").is_err());
+    }
+
+    #[test]
+    fn test_market_by_symbol() {
+        let optifi_client = OptifiClient::initialize(
+            Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
+            Some(WALLET_PATH.to_owned()),
+            None,
+        )
+        .unwrap();
+
+        let market = &optifi_client.account().markets[0];
+
+        println!("market: {}", market);
+
+        let found = optifi_client.market_by_symbol(&market.to_string()).unwrap();
+
+        assert_eq!(
+            found.optifi_market_key_data.optifi_market_pubkey,
+            market.optifi_market_key_data.optifi_market_pubkey
+        );
+    }
+}
@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod tests {
+
+    use optifi_client::client::get_order_native_amounts;
+    use optifi_client::prelude::*;
+    use optifi_client::types::{Price, Quantity, UsdcAmount};
+
+    #[test]
+    fn test_native_conversions() {
+        let asset = Asset::Bitcoin;
+
+        let price = Price::from_native(asset, 12345);
+
+        assert_eq!(price.to_native(asset).unwrap(), 12345);
+        assert_eq!(
+            price,
+            Price::new(Decimal::new(12345, Price::decimals(asset)))
+        );
+
+        let size = Quantity::from_native(asset, 1);
+
+        assert_eq!(size.to_native(asset).unwrap(), 1);
+        assert_eq!(
+            size.value(),
+            Decimal::ONE / Decimal::from(10_u64.pow(Quantity::decimals(asset)))
+        );
+
+        // Finer than one native unit can not be sent.
+        assert!(Quantity::new(size.value() / Decimal::from(2))
+            .to_native(asset)
+            .is_err());
+        assert!(Price::from_str("-1").unwrap().to_native(asset).is_err());
+
+        let amount = UsdcAmount::from_str("100.25").unwrap();
+
+        assert_eq!(amount.to_native().unwrap(), 100_250_000);
+        assert_eq!(UsdcAmount::from_native(100_250_000), amount);
+        assert_eq!(amount.to_string(), "100.25");
+    }
+
+    #[test]
+    fn test_order_native_amounts() {
+        let asset = Asset::Bitcoin;
+
+        let price = Price::from_str("10").unwrap();
+        let size = Quantity::from_native(asset, 3);
+
+        let (limit, max_coin_qty, max_pc_qty) =
+            get_order_native_amounts(asset, price, size).unwrap();
+
+        assert_eq!(limit, price.to_native(asset).unwrap());
+        assert_eq!(max_coin_qty, 3);
+        assert!(max_pc_qty >= limit * max_coin_qty);
+    }
+}
@@ -0,0 +1,163 @@
+#[cfg(test)]
+mod tests {
+
+    use optifi_client::client::OptifiClient;
+    use optifi_client::network::Network;
+    use optifi_client::orders::OrderRequest;
+    use optifi_client::prelude::*;
+    use optifi_client::types::{Price, Quantity};
+    use optifi_client::validation::{MarketRules, OrderRejection, OrderValidation};
+
+    const RPC: &str = "https://devnet.genesysgo.net";
+
+    const WALLET_PATH: &str = "~/.config/solana/optifi.json";
+
+    fn rules() -> MarketRules {
+        MarketRules {
+            optifi_market: Pubkey::new_unique(),
+            tick_size: "0.5".parse().unwrap(),
+            lot_size: "0.01".parse().unwrap(),
+            is_stopped: false,
+            expiry_date: 1_000,
+        }
+    }
+
+    fn order(side: OrderSide, price: &str, size: &str) -> OrderRequest {
+        OrderRequest {
+            side,
+            price: price.parse().unwrap(),
+            size: size.parse().unwrap(),
+            order_type: OrderType::Limit,
+        }
+    }
+
+    #[test]
+    fn test_reject() {
+        let rules = rules();
+
+        let checked = rules
+            .check(
+                order(OrderSide::Bid, "10.5", "0.03"),
+                OrderValidation::Reject,
+                0,
+            )
+            .unwrap();
+
+        assert_eq!(checked.price, Price::from_str("10.5").unwrap());
+        assert_eq!(checked.size, Quantity::from_str("0.03").unwrap());
+
+        assert!(matches!(
+            rules.check(
+                order(OrderSide::Bid, "10.2", "0.03"),
+                OrderValidation::Reject,
+                0
+            ),
+            Err(OrderRejection::OffTick { .. })
+        ));
+        assert!(matches!(
+            rules.check(
+                order(OrderSide::Bid, "10.5", "0.035"),
+                OrderValidation::Reject,
+                0
+            ),
+            Err(OrderRejection::OffLot { .. })
+        ));
+        assert_eq!(
+            rules.check(
+                order(OrderSide::Bid, "10.5", "0.03"),
+                OrderValidation::Reject,
+                1_000
+            ),
+            Err(OrderRejection::Expired(1_000))
+        );
+
+        let stopped = MarketRules {
+            is_stopped: true,
+            ..rules
+        };
+
+        assert_eq!(
+            stopped.check(
+                order(OrderSide::Ask, "10.5", "0.03"),
+                OrderValidation::Reject,
+                0
+            ),
+            Err(OrderRejection::MarketStopped(rules.optifi_market))
+        );
+
+        // Off skips every check.
+        assert!(stopped
+            .check(
+                order(OrderSide::Ask, "10.2", "0.035"),
+                OrderValidation::Off,
+                2_000
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn test_round() {
+        let rules = rules();
+
+        let bid = rules
+            .check(
+                order(OrderSide::Bid, "10.2", "0.035"),
+                OrderValidation::Round,
+                0,
+            )
+            .unwrap();
+
+        assert_eq!(bid.price, Price::from_str("10").unwrap());
+        assert_eq!(bid.size, Quantity::from_str("0.03").unwrap());
+
+        let ask = rules
+            .check(
+                order(OrderSide::Ask, "10.2", "0.035"),
+                OrderValidation::Round,
+                0,
+            )
+            .unwrap();
+
+        assert_eq!(ask.price, Price::from_str("10.5").unwrap());
+
+        assert!(matches!(
+            rules.check(
+                order(OrderSide::Bid, "0.2", "0.03"),
+                OrderValidation::Round,
+                0
+            ),
+            Err(OrderRejection::InvalidPrice(_))
+        ));
+        assert!(matches!(
+            rules.check(
+                order(OrderSide::Bid, "10", "0.005"),
+                OrderValidation::Round,
+                0
+            ),
+            Err(OrderRejection::InvalidSize { .. })
+        ));
+    }
+
+    #[test]
+    fn test_market_rules() {
+        let optifi_client = OptifiClient::initialize(
+            Cluster::from_str(RPC).unwrap(),
+            Network::Devnet,
+            Some(WALLET_PATH.to_owned()),
+            None,
+        )
+        .unwrap();
+
+        let market = &optifi_client.account().markets[0];
+
+        let rules = MarketRules::new(market).unwrap();
+
+        println!("rules: {:#?}", rules);
+
+        let orders = optifi_client
+            .validate_orders(market, &[order(OrderSide::Bid, "1", "0.01")])
+            .unwrap();
+
+        println!("orders: {:#?}", orders);
+    }
+}